@@ -0,0 +1,163 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Counts allocations, a measure of parsing cost that doesn't depend on the machine
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// A script of about 6000 lines, shaped like a large monorepo Argcfile
+fn large_script() -> String {
+    tasks_script(350)
+}
+
+fn tasks_script(tasks: usize) -> String {
+    let mut output = String::from(
+        r#"#!/usr/bin/env bash
+# @describe Monorepo tasks
+# @version 1.0.0
+# @flag -v --verbose Print more
+
+set -e
+"#,
+    );
+    for i in 0..tasks {
+        output.push_str(&format!(
+            r#"
+# @cmd Task {i}
+# Extra help for task {i}
+# @alias t{i}
+# @option -e --env[dev|stage|prod] Target environment
+# @option --jobs=4 Parallel jobs
+# @flag --dry-run Only print what would run
+# @arg targets* Targets to build
+task{i}() {{
+    local target
+    for target in "${{argc_targets[@]}}"; do
+        echo "building $target for $argc_env"
+    done
+    if [[ -n "$argc_dry_run" ]]; then
+        return 0
+    fi
+}}
+"#
+        ));
+    }
+    output.push_str("\neval \"$(argc --argc-eval \"$0\" \"$@\")\"\n");
+    output
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let source = large_script();
+    let args: Vec<String> = ["prog", "task150", "-e", "prod", "x"]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    c.bench_function("export large script", |b| {
+        b.iter(|| argc::export(&source, None).unwrap())
+    });
+    c.bench_function("eval large script", |b| {
+        b.iter(|| argc::eval(&source, &args, None, None).unwrap())
+    });
+    let compgen_args: Vec<String> = ["prog", "task150", "-e", ""]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    c.bench_function("new large script", |b| {
+        b.iter(|| argc::Command::new(&source).unwrap())
+    });
+    c.bench_function("compgen large script", |b| {
+        b.iter(|| argc::compgen(argc::Shell::Bash, "", &source, &compgen_args).unwrap())
+    });
+}
+
+fn fastest(run: &dyn Fn()) -> Duration {
+    (0..20)
+        .map(|_| {
+            let now = Instant::now();
+            run();
+            now.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+/// Completion only parses the params of the commands it may reach, so it must beat a full parse
+fn assert_compgen_speedup(_: &mut Criterion) {
+    let source = large_script();
+    let args: Vec<String> = ["prog", "task150", "-e", ""]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    let full = fastest(&|| {
+        argc::Command::new(&source).unwrap();
+    });
+    let lazy = fastest(&|| {
+        argc::compgen(argc::Shell::Bash, "", &source, &args).unwrap();
+    });
+    assert!(
+        lazy * 2 < full,
+        "compgen took {lazy:?} while a full parse took {full:?}"
+    );
+}
+
+/// A full parse of the large script made about 117k allocations before the parser was tuned
+/// and about 40k after. Unlike its time, that count is the same on every machine
+fn assert_parse_allocations(_: &mut Criterion) {
+    let source = large_script();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    argc::Command::new(&source).unwrap();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert!(
+        allocations < 45_000,
+        "a full parse made {allocations} allocations"
+    );
+}
+
+/// Tasks are parsed one by one, so ten times the tasks must not take much more than ten times as long
+fn assert_parse_linear(_: &mut Criterion) {
+    let small = tasks_script(35);
+    let large = large_script();
+    let small = fastest(&|| {
+        argc::Command::new(&small).unwrap();
+    });
+    let large = fastest(&|| {
+        argc::Command::new(&large).unwrap();
+    });
+    assert!(
+        large < small * 15,
+        "350 tasks took {large:?} while 35 tasks took {small:?}"
+    );
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    assert_compgen_speedup,
+    assert_parse_allocations,
+    assert_parse_linear
+);
+criterion_main!(benches);
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Whole scripts also cover the description lines taken after each tag
+fuzz_target!(|source: &str| {
+    argc::fuzzing::parse(source);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Lines are matched one at a time, so a single line reaches every tag parser
+fuzz_target!(|line: &str| {
+    argc::fuzzing::parse_line(line);
+});
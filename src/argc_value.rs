@@ -1,4 +1,4 @@
-use crate::utils::{escape_shell_words, hyphens_to_underscores};
+use crate::utils::{escape_shell_words, to_var_name};
 
 pub const VARIABLE_PREFIX: &str = "argc";
 
@@ -7,80 +7,187 @@ pub enum ArgcValue {
     Single(String, String),
     SingleFn(String, String),
     Multiple(String, Vec<String>),
+    /// Like `Multiple`, also splits the `key=value` items into `_keys` and `_values` arrays
+    Map(String, Vec<String>),
     PositionalSingle(String, String),
     PositionalSingleFn(String, String),
     PositionalMultiple(String, Vec<String>),
     ExtraPositionalMultiple(Vec<String>),
+    ValidateFn(String, Vec<String>),
+    /// Like `ValidateFn`, for the variable a function set, as with a ``=`fn` `` default
+    ValidateFnVar(String, String),
     CmdFn(String),
+    NoForwardPositionals,
+    /// Replaces the `argc_` prefix of the variables set after it
+    VarPrefix(String),
+    /// Set the multiple values after it as newline-separated strings, for `@meta shell` without arrays
+    NoArrays,
+    /// Time the call of the command function, printing its exit code and elapsed time to stderr
+    TraceDispatch,
+    /// Print the assignments and the call instead of running them
+    DryRun,
+    /// Run with `set -euo pipefail`, restoring the caller's shell options once the command function returns
+    StrictShell,
     ParamFn(String),
+    /// Printed to stderr before anything else runs
+    Warning(String),
     Error((String, i32)),
+    /// Like `Error`, but the message is escaped for an unquoted heredoc so it can expand variables
+    ErrorExpand((String, i32)),
 }
 
 impl ArgcValue {
     pub fn to_shell(values: Vec<Self>) -> String {
+        let (mut output, lines) = Self::to_shell_lines(values);
+        let lines: Vec<String> = lines.into_iter().map(|(line, _)| line).collect();
+        output.push_str(&lines.join("\n"));
+        output
+    }
+
+    /// Like `to_shell`, split into the assignments and the rest that calls functions of the script,
+    /// so the script body can run in between
+    pub fn to_shell_parts(values: Vec<Self>) -> (String, String) {
+        let (mut head, lines) = Self::to_shell_lines(values);
+        let (head_lines, tail_lines): (Vec<_>, Vec<_>) =
+            lines.into_iter().partition(|(_, calls_fn)| !calls_fn);
+        let join = |lines: Vec<(String, bool)>| -> String {
+            lines
+                .into_iter()
+                .map(|(line, _)| line)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        head.push_str(&join(head_lines));
+        (head, join(tail_lines))
+    }
+
+    /// The warnings or final output, and the lines with whether they call functions of the script
+    fn to_shell_lines(values: Vec<Self>) -> (String, Vec<(String, bool)>) {
         let mut variables = vec![];
         let mut last = String::new();
         let mut call = String::new();
         let mut positional_args = vec![];
+        let mut forward_positionals = true;
+        let mut validations = vec![];
+        let mut prefix = format!("{VARIABLE_PREFIX}_");
+        let mut warnings = String::new();
+        let mut arrays = true;
+        let mut trace_dispatch = false;
+        let mut dry_run = false;
+        let mut strict_shell = false;
+        let mut positional_fns = false;
         for value in values {
             match value {
+                ArgcValue::StrictShell => {
+                    strict_shell = true;
+                }
+                ArgcValue::DryRun => {
+                    dry_run = true;
+                }
+                ArgcValue::TraceDispatch => {
+                    trace_dispatch = true;
+                }
+                ArgcValue::VarPrefix(value) => {
+                    prefix = value;
+                }
+                ArgcValue::NoArrays => {
+                    arrays = false;
+                }
                 ArgcValue::Single(name, value) => {
-                    variables.push(format!(
-                        "{}_{}={}",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        escape_shell_words(&value)
+                    variables.push((
+                        format!(
+                            "{}{}={}",
+                            prefix,
+                            to_var_name(&name),
+                            escape_shell_words(&value)
+                        ),
+                        false,
                     ));
                 }
                 ArgcValue::SingleFn(name, fn_name) => {
-                    variables.push(format!(
-                        "{}_{}=`{}`",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        fn_name,
+                    variables.push((
+                        format!("{}{}=`{}`", prefix, to_var_name(&name), fn_name),
+                        true,
                     ));
                 }
                 ArgcValue::Multiple(name, values) => {
-                    variables.push(format!(
-                        "{}_{}=( {} )",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        values
-                            .iter()
-                            .map(|v| escape_shell_words(v))
-                            .collect::<Vec<String>>()
-                            .join(" ")
+                    let values: Vec<String> =
+                        values.iter().map(|v| escape_shell_words(v)).collect();
+                    variables.push((
+                        render_list(
+                            &format!("{}{}", prefix, to_var_name(&name)),
+                            &values,
+                            arrays,
+                        ),
+                        false,
+                    ));
+                }
+                ArgcValue::Map(name, values) => {
+                    let name = to_var_name(&name);
+                    let mut entries: Vec<(&str, &str)> = vec![];
+                    for value in values.iter() {
+                        let (key, value) = value.split_once('=').unwrap_or((value, ""));
+                        match entries.iter_mut().find(|(k, _)| *k == key) {
+                            Some(entry) => entry.1 = value,
+                            None => entries.push((key, value)),
+                        }
+                    }
+                    let escape = |values: Vec<&str>| -> Vec<String> {
+                        values.into_iter().map(escape_shell_words).collect()
+                    };
+                    variables.push((
+                        render_list(
+                            &format!("{}{}", prefix, name),
+                            &escape(values.iter().map(|v| v.as_str()).collect()),
+                            arrays,
+                        ),
+                        false,
+                    ));
+                    variables.push((
+                        render_list(
+                            &format!("{}{}__keys", prefix, name),
+                            &escape(entries.iter().map(|v| v.0).collect()),
+                            arrays,
+                        ),
+                        false,
+                    ));
+                    variables.push((
+                        render_list(
+                            &format!("{}{}__values", prefix, name),
+                            &escape(entries.iter().map(|v| v.1).collect()),
+                            arrays,
+                        ),
+                        false,
                     ));
                 }
                 ArgcValue::PositionalSingle(name, value) => {
                     let value = escape_shell_words(&value);
-                    variables.push(format!(
-                        "{}_{}={}",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        &value
+                    variables.push((
+                        format!("{}{}={}", prefix, to_var_name(&name), &value),
+                        false,
                     ));
                     positional_args.push(value);
                 }
                 ArgcValue::PositionalSingleFn(name, fn_name) => {
-                    variables.push(format!(
-                        "{}_{}=`{}`",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        &fn_name
+                    variables.push((
+                        format!("{}{}=`{}`", prefix, to_var_name(&name), &fn_name),
+                        true,
                     ));
                     positional_args.push(format!("`{}`", fn_name));
+                    positional_fns = true;
                 }
                 ArgcValue::PositionalMultiple(name, values) => {
                     let values = values
                         .iter()
                         .map(|v| escape_shell_words(v))
                         .collect::<Vec<String>>();
-                    variables.push(format!(
-                        "{}_{}=( {} )",
-                        VARIABLE_PREFIX,
-                        hyphens_to_underscores(&name),
-                        values.join(" ")
+                    variables.push((
+                        render_list(
+                            &format!("{}{}", prefix, to_var_name(&name)),
+                            &values,
+                            arrays,
+                        ),
+                        false,
                     ));
                     positional_args.extend(values);
                 }
@@ -91,12 +198,35 @@ impl ArgcValue {
                         .collect::<Vec<String>>();
                     positional_args.extend(values);
                 }
+                ArgcValue::ValidateFn(fn_name, values) => {
+                    for value in values {
+                        validations.push(format!(
+                            "{} {} || exit $?",
+                            fn_name,
+                            escape_shell_words(&value)
+                        ));
+                    }
+                }
+                ArgcValue::ValidateFnVar(fn_name, name) => {
+                    validations.push(format!(
+                        "{} \"${}{}\" || exit $?",
+                        fn_name,
+                        prefix,
+                        to_var_name(&name)
+                    ));
+                }
+                ArgcValue::NoForwardPositionals => {
+                    forward_positionals = false;
+                }
                 ArgcValue::CmdFn(name) => {
-                    if positional_args.is_empty() {
+                    if positional_args.is_empty() || !forward_positionals {
                         last = name.to_string();
                     } else {
                         last = format!("{} {}", name, positional_args.join(" "));
                     }
+                    if trace_dispatch {
+                        last = render_trace_dispatch(&name, &last);
+                    }
                     call = name.clone();
                 }
                 ArgcValue::ParamFn(name) => {
@@ -107,30 +237,145 @@ impl ArgcValue {
                     }
                     call = name.clone();
                 }
+                ArgcValue::Warning(message) => {
+                    let message = escape_shell_words(&format!("warning: {message}"));
+                    warnings.push_str(&format!("printf '%s\\n' {message} >&2\n"));
+                }
                 ArgcValue::Error((error, exit)) => {
-                    return format!("cat >&2 <<-'EOF' \n{}\nEOF\nexit {}", error, exit)
+                    return (warnings + &render_message(&error, exit, "'EOF'"), vec![])
+                }
+                ArgcValue::ErrorExpand((error, exit)) => {
+                    return (warnings + &render_message(&error, exit, "EOF"), vec![])
                 }
             }
         }
 
-        variables.push(format!(
-            "{}__args=( {} )",
-            VARIABLE_PREFIX,
-            positional_args.join(" ")
+        variables.push((
+            render_list(&format!("{}_args", prefix), &positional_args, arrays),
+            positional_fns,
         ));
 
+        variables.extend(validations.into_iter().map(|v| (v, true)));
+
         if !call.is_empty() {
-            variables.push(format!("{}__fn={}", VARIABLE_PREFIX, call));
+            variables.push((format!("{}_fn={}", prefix, call), true));
         }
 
         if !last.is_empty() {
-            variables.push(last);
+            variables.push((last, true));
+        }
+
+        if strict_shell {
+            // Shells without arrays may not have `pipefail`
+            let options = if arrays { "-euo pipefail" } else { "-eu" };
+            variables.insert(
+                0,
+                (
+                    format!("_argc_shell_options=\"$(set +o)\"\nset {options}"),
+                    false,
+                ),
+            );
+            variables.push((r#"eval "$_argc_shell_options""#.into(), true));
         }
 
-        variables.join("\n")
+        if dry_run {
+            let plan: Vec<&str> = variables.iter().map(|(v, _)| v.as_str()).collect();
+            let plan = escape_shell_words(&plan.join("\n"));
+            return (warnings + &format!("printf '%s\\n' {plan}\nexit 0"), vec![]);
+        }
+
+        (warnings, variables)
     }
 
     pub fn is_cmd_fn(&self) -> bool {
         matches!(self, Self::CmdFn(_))
     }
+
+    /// The function to call and each param with its value and source, e.g. `param output=out (cli)`
+    pub fn to_trace_lines(values: &[Self]) -> Vec<String> {
+        let mut params: Vec<(String, String, String)> = vec![];
+        let mut call = None;
+        for value in values {
+            let (name, value) = match value {
+                ArgcValue::Single(name, value) | ArgcValue::PositionalSingle(name, value) => {
+                    if let Some(name) = name.strip_suffix("__source") {
+                        if let Some(index) = params.iter().rposition(|v| v.0 == name) {
+                            if value.is_empty() {
+                                // The args don't set it, the variable is only initialized
+                                params.remove(index);
+                            } else {
+                                params[index].2.clone_from(value);
+                            }
+                        }
+                        continue;
+                    }
+                    (name, escape_shell_words(value))
+                }
+                ArgcValue::SingleFn(name, fn_name)
+                | ArgcValue::PositionalSingleFn(name, fn_name) => (name, format!("`{fn_name}`")),
+                ArgcValue::Multiple(name, values)
+                | ArgcValue::Map(name, values)
+                | ArgcValue::PositionalMultiple(name, values) => {
+                    let values: Vec<String> =
+                        values.iter().map(|v| escape_shell_words(v)).collect();
+                    (name, values.join(" "))
+                }
+                ArgcValue::CmdFn(name) => {
+                    call = Some(name.clone());
+                    continue;
+                }
+                _ => continue,
+            };
+            params.push((name.clone(), value, String::new()));
+        }
+        let mut output = vec![];
+        if let Some(name) = call {
+            output.push(format!("fn {name}"));
+        }
+        for (name, value, source) in params {
+            if source.is_empty() {
+                output.push(format!("param {name}={value}"));
+            } else {
+                output.push(format!("param {name}={value} ({source})"));
+            }
+        }
+        output
+    }
+}
+
+/// Run the command function, then print its exit code and elapsed time to stderr.
+/// Bash 5 has `EPOCHREALTIME`, other shells get whole seconds
+fn render_trace_dispatch(name: &str, call: &str) -> String {
+    let now = "${EPOCHREALTIME:-$(date +%s)}";
+    format!(
+        r#"_argc_trace_start="{now}"
+{call}
+_argc_trace_status=$?
+printf 'argc> %s exited with %s in %ss\n' {name} "$_argc_trace_status" "$(awk "BEGIN {{ printf \"%.3f\", {now} - $_argc_trace_start }}")" >&2
+(exit $_argc_trace_status)"#
+    )
+}
+
+/// Assign shell words to an array, or to a string with one word per line for shells without arrays
+fn render_list(name: &str, words: &[String], arrays: bool) -> String {
+    if arrays {
+        format!("{}=( {} )", name, words.join(" "))
+    } else if words.is_empty() {
+        format!("{}=", name)
+    } else {
+        format!("{}=\"$(printf '%s\\n' {})\"", name, words.join(" "))
+    }
+}
+
+/// Errors go to stderr, help and version go to stdout through a pager when taller than the terminal,
+/// or through `cat` when the pager isn't installed
+fn render_message(message: &str, exit: i32, delimiter: &str) -> String {
+    if exit != 0 {
+        return format!("cat >&2 <<-{delimiter} \n{message}\nEOF\nexit {exit}");
+    }
+    let lines = message.lines().count();
+    let pager = format!(
+        r#"if [ -t 1 ] && [ -z "${{ARGC_NO_PAGER:-}}" ] && [ {lines} -gt "${{LINES:-$(tput lines 2>/dev/null || echo {lines})}}" ] 2>/dev/null; then ${{PAGER:-less -FRX}} 2>/dev/null || [ $? -ne 127 ] || cat; else cat; fi"#
+    );
+    format!("cat <<-{delimiter} | {{ {pager}; }}\n{message}\nEOF\nexit {exit}")
 }
@@ -30,10 +30,40 @@ _argc_completer() {
 
     if [[ ${#candicates[@]} -gt 0 ]]; then
         compopt -o nospace
-        COMPREPLY=(${candicates[@]})
+        COMPREPLY=($(_argc_complete_display "${candicates[@]}"))
     fi
 }
 
+_argc_complete_display() {
+    local IFS=$'\n'
+    local values=() descriptions=() width=0 has_description=0 candicate value i
+    for candicate in "$@"; do
+        value="${candicate%%$'\t'*}"
+        if [[ "$value" != "$candicate" ]]; then
+            has_description=1
+            descriptions+=("${candicate#*$'\t'}")
+        else
+            descriptions+=("")
+        fi
+        values+=("$value")
+        if [[ ${#value} -gt $width ]]; then
+            width=${#value}
+        fi
+    done
+    # A lone candidate is inserted as is, menu-complete (COMP_TYPE 37) inserts every entry it cycles through
+    if [[ $# -eq 1 || $has_description -eq 0 || "${BASH_VERSINFO[0]}" -lt 4 || "$COMP_TYPE" == "37" ]]; then
+        printf '%s\n' "${values[@]}"
+        return
+    fi
+    for i in "${!values[@]}"; do
+        if [[ -n "${descriptions[$i]}" ]]; then
+            printf "%-${width}s  (%s)\n" "${values[$i]}" "${descriptions[$i]}"
+        else
+            printf '%s\n' "${values[$i]}"
+        fi
+    done
+}
+
 _argc_complete_path() {
     local cur="$1"
     local kind="$2"
@@ -1,6 +1,7 @@
 use anyhow::Result;
 
 use argc::Shell;
+use std::fs;
 
 const BASH_SCRIPT: &str = include_str!("argc.bash");
 
@@ -17,8 +18,9 @@ const NUSHELL_SCRIPT: &str = include_str!("argc.nu");
 const XONSH_SCRIPT: &str = include_str!("argc.xsh");
 
 pub fn generate(shell: Shell, args: &[String]) -> Result<String> {
+    let names: Vec<String> = args.iter().map(|v| cmd_name(v)).collect();
     let mut cmds = vec!["argc"];
-    cmds.extend(args.iter().map(|v| v.as_str()));
+    cmds.extend(names.iter().map(|v| v.as_str()));
     let output = match shell {
         Shell::Bash => {
             let code = format!("complete -F _argc_completer {}", cmds.join(" "));
@@ -80,7 +82,16 @@ $env.config.completions.external = {{
     Ok(output)
 }
 
+/// A path to a script with `@name` registers that name, anything else is a command name
+fn cmd_name(arg: &str) -> String {
+    fs::read_to_string(arg)
+        .ok()
+        .and_then(|source| argc::extract_metadata(&source).ok())
+        .and_then(|metadata| metadata.name)
+        .unwrap_or_else(|| arg.to_string())
+}
+
 #[test]
 fn feature() {
-    format!("{:?}", vec!["a", "b"]);
+    let _ = format!("{:?}", vec!["a", "b"]);
 }
@@ -1,5 +1,7 @@
 mod completions;
+mod tasks;
 mod utils;
+mod watch;
 
 use anyhow::{anyhow, bail, Context, Result};
 use argc::{
@@ -8,7 +10,10 @@ use argc::{
 };
 use std::{
     collections::HashMap,
-    env, fs, process,
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -16,6 +21,9 @@ use std::{
 };
 use utils::*;
 
+/// Name of the code file in the temp dir of a shebang script
+const SHEBANG_FILE: &str = "script.sh";
+
 fn main() {
     match run() {
         Ok(code) => {
@@ -31,7 +39,17 @@ fn main() {
 }
 
 fn run() -> Result<i32> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    // Scripts started from here inherit these, so their `--argc-eval` picks them up as well
+    loop {
+        match args.get(1).map(|v| v.as_str()) {
+            Some("--argc-no-cache") => env::set_var("ARGC_NO_CACHE", "1"),
+            Some("--argc-trace") => env::set_var("ARGC_TRACE", "1"),
+            Some("--argc-dry-run") => env::set_var("ARGC_DRY_RUN", "1"),
+            _ => break,
+        }
+        args.remove(1);
+    }
     let mut argc_cmd = None;
     if let Some(arg) = args.get(1) {
         if arg.starts_with("--argc-") {
@@ -43,28 +61,153 @@ fn run() -> Result<i32> {
         match argc_cmd {
             "--argc-eval" => {
                 let (source, cmd_args) = parse_script_args(&args[2..])?;
-                let values = argc::eval(&source, &cmd_args, Some(&args[2]), termwidth())?;
-                let export_pwd = match env::var("ARGC_PWD").ok().or_else(get_current_dir) {
-                    Some(v) => format!("export ARGC_PWD={v}\n"),
-                    None => String::new(),
+                match eval_script(&source, &cmd_args, &args[2]) {
+                    Ok(output) => println!("{output}"),
+                    Err(err) => {
+                        // The script evals our stdout, so make it stop instead of running on
+                        eprintln!("{err}");
+                        println!("exit {}", argc::spec_error_exit_code(&source));
+                        return Ok(1);
+                    }
+                }
+            }
+            "--argc-run" => {
+                let (script_file, fn_name) = match (args.get(2), args.get(3)) {
+                    (Some(script_file), Some(fn_name)) => (script_file, fn_name),
+                    _ => bail!("Usage: argc --argc-run <SCRIPT> <FN> [ARGS...]"),
                 };
-                println!("{export_pwd}{}", argc::ArgcValue::to_shell(values))
+                // Fail early on unknown functions, the script itself would just exit 0
+                let (source, cmd_args) = parse_script_args(&args[2..3])?;
+                argc::eval_fn(&source, fn_name, &cmd_args, Some(script_file), None)?;
+                let status = shell_command(&source)?
+                    .arg(script_file)
+                    .args(&args[4..])
+                    .env("ARGC_RUN_FN", fn_name)
+                    .status()
+                    .with_context(|| format!("Failed to run `{script_file}`"))?;
+                return Ok(status.code().unwrap_or_default());
+            }
+            "--argc-parallel" | "--argc-seq" => {
+                if args.len() < 3 {
+                    bail!("Usage: argc {argc_cmd} <TASK> [ARGS...] [::: <TASK> [ARGS...]]...");
+                }
+                let tasks = tasks::split_tasks(&args[2..])?;
+                let interrupt = set_interrupt_handler()?;
+                let new_command = |task: &[String]| Ok(argcfile_command(task)?.0);
+                let code = if argc_cmd == "--argc-parallel" {
+                    tasks::run_parallel(&tasks, new_command)?
+                } else {
+                    tasks::run_seq(&tasks, new_command)?
+                };
+                return Ok(if interrupt.load(Ordering::Relaxed) {
+                    130
+                } else {
+                    code
+                });
+            }
+            "--argc-watch" => {
+                let no_ignore = args.get(2).is_some_and(|v| v == "--no-ignore");
+                let rest = &args[if no_ignore { 3 } else { 2 }..];
+                if rest.len() < 2 {
+                    bail!("Usage: argc --argc-watch [--no-ignore] <GLOB> <TASK> [ARGS...]");
+                }
+                let (script_dir, _) = get_script_path(true).ok_or_else(|| {
+                    anyhow!("Argcfile not found, try `argc --argc-help` for help.")
+                })?;
+                let filter = watch::WatchFilter::new(&script_dir, &rest[0], no_ignore)?;
+                let interrupt = set_interrupt_handler()?;
+                return watch::run_watch(&filter, &rest[1..], interrupt, |task| {
+                    Ok(argcfile_command(task)?.0)
+                });
             }
             "--argc-create" => {
                 if let Some((_, script_file)) = get_script_path(false) {
                     bail!("Already exist {}", script_file.display());
                 }
                 let content = generate_boilerplate(&args[2..]);
+                argc::export(&content, None).with_context(|| "Invalid task names")?;
                 let names = candidate_script_names();
                 fs::write(&names[0], content)
                     .with_context(|| format!("Failed to create {}", &names[0]))?;
                 println!("{} has been successfully created.", &names[0]);
             }
             "--argc-export" => {
-                let (source, _) = parse_script_args(&args[2..])?;
-                let json = argc::export(&source)?;
+                let (source, cmd_args) = parse_script_args(&args[2..])?;
+                let json = argc::export_command(&source, Some(&args[2]), &cmd_args[1..])?;
                 println!("{}", serde_json::to_string_pretty(&json)?);
             }
+            "--argc-format" => {
+                let (check, script_file) = match &args[2..] {
+                    [flag, script_file] if flag == "--check" => (true, script_file),
+                    [script_file] if script_file != "--check" => (false, script_file),
+                    _ => bail!("Usage: argc --argc-format [--check] <SCRIPT>"),
+                };
+                let (source, _) = parse_script_args(std::slice::from_ref(script_file))?;
+                let output = argc::format_script(&source)?;
+                if output != source {
+                    if check {
+                        eprintln!("{script_file} is not formatted");
+                        return Ok(1);
+                    }
+                    fs::write(script_file, output)
+                        .with_context(|| format!("Failed to write script at '{script_file}'"))?;
+                }
+            }
+            "--argc-check" => {
+                let (source, _) = parse_script_args(&args[2..])?;
+                let mut warnings = argc::check(&source)?;
+                // A script run by `#!/usr/bin/env argc` gets its args parsed without one
+                if argc::script_eval_lines(&source)?.is_empty() && !is_argc_shebang(&args[2]) {
+                    warnings.push(
+                        "no `eval \"$(argc --argc-eval \"$0\" \"$@\")\"` line, so the args are never parsed"
+                            .to_string(),
+                    );
+                }
+                for warning in warnings.iter() {
+                    eprintln!("error: {warning}");
+                }
+                if !warnings.is_empty() {
+                    return Ok(1);
+                }
+            }
+            "--argc-version-check" => {
+                let (source, _) = parse_script_args(&args[2..])?;
+                println!("{}", get_argc_version());
+                match argc::check_min_version(&source)? {
+                    Some((version, true)) => println!("min-argc-version {version}: satisfied"),
+                    Some((version, false)) => {
+                        println!("min-argc-version {version}: not satisfied");
+                        return Ok(1);
+                    }
+                    None => println!("min-argc-version: not set"),
+                }
+            }
+            "--argc-migrate" => {
+                let Some(script_file) = args.get(2) else {
+                    bail!("Usage: argc --argc-migrate <SCRIPT>");
+                };
+                let (source, _) = parse_script_args(&args[2..3])?;
+                let output = argc::migrate_script(&source)?;
+                if output != source {
+                    print!("{}", line_diff(script_file, &source, &output));
+                    fs::write(script_file, output)
+                        .with_context(|| format!("Failed to write script at '{script_file}'"))?;
+                }
+            }
+            "--argc-dot" => {
+                let (source, cmd_args) = parse_script_args(&args[2..])?;
+                println!(
+                    "{}",
+                    argc::export_dot(&source, Some(&args[2]), &cmd_args[0])?
+                );
+            }
+            "--argc-export-clap" => {
+                let (source, cmd_args) = parse_script_args(&args[2..])?;
+                println!(
+                    "{}",
+                    argc::export_clap(&source, Some(&args[2]), &cmd_args[0])?
+                );
+            }
             "--argc-compgen" => {
                 let shell: Shell = match args.get(2) {
                     Some(v) => v.parse()?,
@@ -100,23 +243,43 @@ fn run() -> Result<i32> {
             }
         }
         Ok(0)
-    } else {
-        let shell = get_shell_path().ok_or_else(|| anyhow!("Shell not found"))?;
-        let (script_dir, script_file) = get_script_path(true)
-            .ok_or_else(|| anyhow!("Argcfile not found, try `argc --argc-help` for help."))?;
-        let interrupt = Arc::new(AtomicBool::new(false));
-        let interrupt_me = interrupt.clone();
-        ctrlc::set_handler(move || interrupt_me.store(true, Ordering::Relaxed))
-            .with_context(|| "Failed to set CTRL-C handler")?;
-        let mut envs = HashMap::new();
-        if let Some(cwd) = get_current_dir() {
-            envs.insert("ARGC_PWD".to_string(), escape_shell_words(&cwd));
+    } else if let Some(script_file) = args.get(1).filter(|v| is_argc_shebang(v)) {
+        // Started by the kernel for a script with `#!/usr/bin/env argc`
+        let (source, cmd_args) = parse_script_args(&args[1..])?;
+        let mut command = shell_command(&source)?;
+        let mut temp_dir = None;
+        if argc::script_eval_lines(&source).is_ok_and(|v| !v.is_empty()) {
+            command.arg(script_file);
+        } else {
+            let (preamble, values) = match eval_values(&source, &cmd_args, script_file) {
+                Ok(v) => v,
+                Err(err) => {
+                    eprintln!("{err}");
+                    return Ok(argc::spec_error_exit_code(&source));
+                }
+            };
+            // The script sees the variables, and defines the functions the rest then calls
+            let (head, tail) = argc::ArgcValue::to_shell_parts(values);
+            let dir = shebang_dir(&format!("{preamble}{head}\n{source}\n{tail}\n"))?;
+            let file = dir.join(SHEBANG_FILE);
+            // The arg following `-c` becomes `$0`
+            command
+                .arg("-c")
+                .arg(format!(". {}", escape_shell_words(&file.to_string_lossy())))
+                .arg(script_file);
+            temp_dir = Some(dir);
         }
-        let status = process::Command::new(shell)
-            .arg(&script_file)
-            .args(&args[1..])
-            .current_dir(script_dir)
-            .envs(envs)
+        command.args(&args[2..]);
+        let result = exec_command(command);
+        // Only reached when the shell didn't start, or without `exec` once it exits
+        if let Some(dir) = temp_dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+        result.with_context(|| format!("Failed to run `{script_file}`"))
+    } else {
+        let interrupt = set_interrupt_handler()?;
+        let (mut command, script_file) = argcfile_command(&args[1..])?;
+        let status = command
             .status()
             .with_context(|| format!("Failed to run `{}`", script_file.display()))?;
         if interrupt.load(Ordering::Relaxed) {
@@ -127,6 +290,156 @@ fn run() -> Result<i32> {
     }
 }
 
+/// Command running the Argcfile found from the current dir with `args`, in the Argcfile's
+/// directory unless it sets `@meta inherit-cwd`
+fn argcfile_command(args: &[String]) -> Result<(process::Command, PathBuf)> {
+    let (script_dir, script_file) = get_script_path(true)
+        .ok_or_else(|| anyhow!("Argcfile not found, try `argc --argc-help` for help."))?;
+    let source = fs::read_to_string(&script_file).unwrap_or_default();
+    let mut command = shell_command(&source)?;
+    let mut envs = HashMap::new();
+    if let Some(cwd) = get_current_dir() {
+        envs.insert("ARGC_PWD".to_string(), escape_shell_words(&cwd));
+    }
+    command.arg(&script_file).args(args).envs(envs);
+    if !argc::script_inherits_cwd(&source) {
+        command.current_dir(script_dir);
+    }
+    Ok((command, script_file))
+}
+
+/// CTRL-C reaches the children too, argc only notes it to exit with 130 once they're done
+fn set_interrupt_handler() -> Result<Arc<AtomicBool>> {
+    let interrupt = Arc::new(AtomicBool::new(false));
+    let interrupt_me = interrupt.clone();
+    ctrlc::set_handler(move || interrupt_me.store(true, Ordering::Relaxed))
+        .with_context(|| "Failed to set CTRL-C handler")?;
+    Ok(interrupt)
+}
+
+/// The shell code that `--argc-eval` prints for a script
+fn eval_script(source: &str, cmd_args: &[String], script_file: &str) -> Result<String> {
+    let (preamble, values) = eval_values(source, cmd_args, script_file)?;
+    Ok(format!("{preamble}{}", argc::ArgcValue::to_shell(values)))
+}
+
+/// The values for `cmd_args`, after the shell code that sets up their environment
+fn eval_values(
+    source: &str,
+    cmd_args: &[String],
+    script_file: &str,
+) -> Result<(String, Vec<argc::ArgcValue>)> {
+    let mut cmd_args = cmd_args.to_vec();
+    let dry_run = if cmd_args.get(1).is_some_and(|v| v == "--argc-dry-run") {
+        cmd_args.remove(1);
+        true
+    } else {
+        env::var_os("ARGC_DRY_RUN").is_some_and(|v| !v.is_empty())
+    };
+    let cmd_args = cmd_args.as_slice();
+    // Diagnostics go to stderr with a prefix, stdout is the script's own
+    let tracing = env::var_os("ARGC_TRACE").is_some_and(|v| !v.is_empty());
+    if tracing && env::var_os("ARGC_RUN_FN").is_none() {
+        // Errors are left to the eval below
+        if let Ok(lines) = argc::trace(source, cmd_args) {
+            for line in lines {
+                eprintln!("argc> {line}");
+            }
+        }
+    }
+    let (mut values, unset_run_fn) = match env::var("ARGC_RUN_FN").ok() {
+        Some(fn_name) => (
+            argc::eval_fn(source, &fn_name, cmd_args, Some(script_file), termwidth())?,
+            "unset ARGC_RUN_FN\n",
+        ),
+        None => (
+            argc::eval(source, cmd_args, Some(script_file), termwidth())?,
+            "",
+        ),
+    };
+    if tracing && !dry_run && values.iter().any(|v| v.is_cmd_fn()) {
+        for line in argc::ArgcValue::to_trace_lines(&values) {
+            eprintln!("argc> {line}");
+        }
+        values.insert(0, argc::ArgcValue::TraceDispatch);
+    }
+    if dry_run {
+        values.insert(0, argc::ArgcValue::DryRun);
+    }
+    let export_pwd = match env::var("ARGC_PWD").ok().or_else(get_current_dir) {
+        Some(v) => format!("export ARGC_PWD={v}\n"),
+        None => String::new(),
+    };
+    Ok((format!("{unset_run_fn}{export_pwd}"), values))
+}
+
+/// Private temp dir holding the code to run for a shebang script as `SHEBANG_FILE`, which
+/// removes the dir once sourced. As an arg the code could hit the size limit of the command line
+fn shebang_dir(code: &str) -> Result<PathBuf> {
+    let mut dir_builder = fs::DirBuilder::new();
+    let mut file_options = fs::OpenOptions::new();
+    file_options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        dir_builder.mode(0o700);
+        file_options.mode(0o600);
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|v| v.subsec_nanos())
+        .unwrap_or_default();
+    let temp_dir = env::temp_dir();
+    for attempt in 0..100 {
+        let name = format!("argc-{}-{}", process::id(), nanos.wrapping_add(attempt));
+        let dir = temp_dir.join(name);
+        match dir_builder.create(&dir) {
+            Ok(()) => {}
+            // Taken by an earlier run or someone else, never reuse it
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed to create `{}`", dir.display()))
+            }
+        }
+        let remove = format!("rm -rf {}\n", escape_shell_words(&dir.to_string_lossy()));
+        let path = dir.join(SHEBANG_FILE);
+        if let Err(err) = file_options
+            .open(&path)
+            .and_then(|mut file| file.write_all(format!("{remove}{code}").as_bytes()))
+        {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err).with_context(|| format!("Failed to write `{}`", path.display()));
+        }
+        return Ok(dir);
+    }
+    bail!("Failed to create a temp dir in `{}`", temp_dir.display())
+}
+
+/// Command running the shell that `@meta shell` names, bash by default
+fn shell_command(source: &str) -> Result<process::Command> {
+    if let Some(value) = argc::script_shell(source) {
+        let mut words = value.split_whitespace();
+        let mut command = process::Command::new(words.next().unwrap_or_default());
+        command.args(words);
+        return Ok(command);
+    }
+    let shell = get_shell_path().ok_or_else(|| anyhow!("Shell not found"))?;
+    Ok(process::Command::new(shell))
+}
+
+/// Replace argc with the command, which keeps its stdio, signals and exit code
+#[cfg(unix)]
+fn exec_command(mut command: process::Command) -> Result<i32> {
+    use std::os::unix::process::CommandExt;
+    Err(command.exec().into())
+}
+
+#[cfg(not(unix))]
+fn exec_command(mut command: process::Command) -> Result<i32> {
+    let status = command.status()?;
+    Ok(status.code().unwrap_or_default())
+}
+
 fn get_argc_help() -> String {
     let about = concat!(
         env!("CARGO_PKG_DESCRIPTION"),
@@ -139,9 +452,22 @@ fn get_argc_help() -> String {
 USAGE:
     argc --argc-eval <SCRIPT> [ARGS...]             Use `eval "$(argc --argc-eval "$0" "$@")"`
     argc --argc-create [TASKS...]                   Create a boilerplate argcfile
+    argc --argc-parallel <TASK> [::: <TASK>]...     Run argcfile tasks at the same time, prefixing their output
+    argc --argc-seq <TASK> [::: <TASK>]...          Run argcfile tasks one by one, stopping at the first failure
+    argc --argc-watch [--no-ignore] <GLOB> <TASK>   Rerun an argcfile task when files matching <GLOB> change
     argc --argc-completions <SHELL> [CMDS...]       Generate completion scripts for bash,elvish,fish,nushell,powershell,xsh,zsh
     argc --argc-compgen <SHELL> <SCRIPT> <ARGS...>  Generate dynamic completion word
-    argc --argc-export <SCRIPT>                     Export command line definitions as json
+    argc --argc-run <SCRIPT> <FN> [ARGS...]         Call the function <FN> with argc parsing the args for it
+    argc --argc-export <SCRIPT> [CMDS...]           Export command line definitions as json
+    argc --argc-format [--check] <SCRIPT>           Rewrite the comment tags in canonical form
+    argc --argc-check <SCRIPT>                      Check the comment tags, failing on warnings too
+    argc --argc-migrate <SCRIPT>                    Rewrite outdated tag syntax and print the diff
+    argc --argc-version-check <SCRIPT>              Check the script's min-argc-version against this argc
+    argc --argc-dot <SCRIPT>                        Export command tree as graphviz dot
+    argc --argc-export-clap <SCRIPT>                Export command line definitions as clap builder code
+    argc --argc-no-cache <ARGS...>                  Run the rest of the command without the parse cache
+    argc --argc-trace <ARGS...>                     Run the rest of the command, tracing arg matching and timing
+    argc --argc-dry-run <ARGS...>                   Print what the rest of the command would set and call instead of running it
     argc --argc-script-path                         Print current argcfile path
     argc --argc-help                                Print help information
     argc --argc-version                             Print version information
@@ -0,0 +1,97 @@
+use anyhow::{bail, Context, Result};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+pub const TASK_SEPARATOR: &str = ":::";
+
+/// Split `args` into task invocations at each `:::`, e.g. `build js ::: test` is two tasks
+pub fn split_tasks(args: &[String]) -> Result<Vec<Vec<String>>> {
+    let tasks: Vec<Vec<String>> = args
+        .split(|v| v == TASK_SEPARATOR)
+        .map(|v| v.to_vec())
+        .collect();
+    if tasks.iter().any(|v| v.is_empty()) {
+        bail!("Empty task, tasks are separated by `{TASK_SEPARATOR}`");
+    }
+    Ok(tasks)
+}
+
+/// Run the tasks one after another, stopping at the first one that fails
+pub fn run_seq(
+    tasks: &[Vec<String>],
+    new_command: impl Fn(&[String]) -> Result<Command>,
+) -> Result<i32> {
+    for task in tasks {
+        let status = new_command(task)?
+            .status()
+            .with_context(|| format!("Failed to run task `{}`", task[0]))?;
+        let code = status.code().unwrap_or(1);
+        if code != 0 {
+            return Ok(code);
+        }
+    }
+    Ok(0)
+}
+
+/// Run the tasks at the same time, prefixing each line they print with `[task]`.
+/// All tasks run to the end, the exit code is the one of the first failed task
+pub fn run_parallel(
+    tasks: &[Vec<String>],
+    new_command: impl Fn(&[String]) -> Result<Command>,
+) -> Result<i32> {
+    let mut children = vec![];
+    for task in tasks {
+        let mut child = new_command(task)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run task `{}`", task[0]))?;
+        let prefix = format!("[{}] ", task[0]);
+        let stdout = child.stdout.take().map(|v| {
+            let prefix = prefix.clone();
+            thread::spawn(move || forward_lines(v, &prefix, io::stdout()))
+        });
+        let stderr = child
+            .stderr
+            .take()
+            .map(|v| thread::spawn(move || forward_lines(v, &prefix, io::stderr())));
+        children.push((child, stdout, stderr));
+    }
+    let mut exit_code = 0;
+    for (mut child, stdout, stderr) in children {
+        let status = child.wait()?;
+        for handle in [stdout, stderr].into_iter().flatten() {
+            let _ = handle.join();
+        }
+        let code = status.code().unwrap_or(1);
+        if exit_code == 0 {
+            exit_code = code;
+        }
+    }
+    Ok(exit_code)
+}
+
+/// Copy `reader` to `writer` line by line, a whole line at a time so tasks don't interleave within one
+fn forward_lines(reader: impl Read, prefix: &str, writer: impl Write) {
+    let mut reader = BufReader::new(reader);
+    let mut writer = writer;
+    let mut line = vec![];
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if !line.ends_with(b"\n") {
+                    line.push(b'\n');
+                }
+                let mut output = prefix.as_bytes().to_vec();
+                output.extend_from_slice(&line);
+                let _ = writer.write_all(&output);
+                let _ = writer.flush();
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{bail, Context, Result};
 use std::{
     env, fs,
+    io::{BufRead, BufReader},
     path::{Path, PathBuf},
 };
 
@@ -51,6 +52,8 @@ pub fn generate_boilerplate(args: &[String]) -> String {
         r#"#!/usr/bin/env bash
 
 set -e
+
+# @describe A boilerplate argcfile
 {tasks}
 eval "$(argc --argc-eval "$0" "$@")"
 "#
@@ -85,3 +88,44 @@ pub fn candidate_script_names() -> Vec<String> {
     names.extend(ARGC_SCRIPT_NAMES.into_iter().map(|v| v.to_string()));
     names
 }
+
+/// Diff of two texts with the same lines count, one hunk per changed line
+pub fn line_diff(path: &str, old: &str, new: &str) -> String {
+    let mut output = format!("--- {path}\n+++ {path}\n");
+    for (i, (old_line, new_line)) in old.lines().zip(new.lines()).enumerate() {
+        if old_line != new_line {
+            output.push_str(&format!(
+                "@@ -{0} +{0} @@\n-{old_line}\n+{new_line}\n",
+                i + 1
+            ));
+        }
+    }
+    output
+}
+
+/// Whether the file at `path` starts with a shebang running argc, like `#!/usr/bin/env argc`
+pub fn is_argc_shebang(path: &str) -> bool {
+    if !Path::new(path).is_file() {
+        return false;
+    }
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut line = String::new();
+    if BufReader::new(file).read_line(&mut line).is_err() {
+        return false;
+    }
+    let Some(command) = line.strip_prefix("#!") else {
+        return false;
+    };
+    let program_name = |v: &str| {
+        let name = Path::new(v).file_name().and_then(|v| v.to_str());
+        name.map(|v| v.strip_suffix(".exe").unwrap_or(v).to_string())
+    };
+    let mut words = command.split_whitespace();
+    let mut program = words.next();
+    if program.and_then(program_name).as_deref() == Some("env") {
+        program = words.find(|v| !v.starts_with('-'));
+    }
+    program.and_then(program_name).as_deref() == Some("argc")
+}
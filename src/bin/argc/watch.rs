@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use ignore::gitignore::Gitignore;
+use notify::{RecursiveMode, Watcher};
+use std::{
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+/// Changes arriving this close together trigger a single run
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Which changed paths under `root` re-run the task
+pub struct WatchFilter {
+    root: PathBuf,
+    pattern: glob::Pattern,
+    gitignore: Option<Gitignore>,
+}
+
+impl WatchFilter {
+    /// `pattern` is relative to `root`, `.gitignore` in `root` applies unless `no_ignore`
+    pub fn new(root: &Path, pattern: &str, no_ignore: bool) -> Result<Self> {
+        let pattern = glob::Pattern::new(pattern)
+            .with_context(|| format!("Invalid watch pattern `{pattern}`"))?;
+        let gitignore = if no_ignore {
+            None
+        } else {
+            let (gitignore, _) = Gitignore::new(root.join(".gitignore"));
+            Some(gitignore)
+        };
+        Ok(Self {
+            root: root.to_path_buf(),
+            pattern,
+            gitignore,
+        })
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.starts_with(".git") {
+            return false;
+        }
+        if let Some(gitignore) = &self.gitignore {
+            if gitignore
+                .matched_path_or_any_parents(relative, path.is_dir())
+                .is_ignore()
+            {
+                return false;
+            }
+        }
+        self.pattern.matches_path(relative)
+    }
+}
+
+/// Run the task, then again each time a watched file changes, until CTRL-C
+pub fn run_watch(
+    filter: &WatchFilter,
+    task: &[String],
+    interrupt: Arc<AtomicBool>,
+    new_command: impl Fn(&[String]) -> Result<Command>,
+) -> Result<i32> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).with_context(|| "Failed to start watching files")?;
+    watcher
+        .watch(&filter.root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch `{}`", filter.root.display()))?;
+    loop {
+        if io::stdout().is_terminal() {
+            print!("\x1B[2J\x1B[H");
+            let _ = io::stdout().flush();
+        }
+        let status = new_command(task)?
+            .status()
+            .with_context(|| format!("Failed to run task `{}`", task[0]))?;
+        if interrupt.load(Ordering::Relaxed) {
+            return Ok(130);
+        }
+        eprintln!(
+            "[argc] `{}` exited with {}, waiting for changes...",
+            task.join(" "),
+            status.code().unwrap_or(1)
+        );
+        loop {
+            if interrupt.load(Ordering::Relaxed) {
+                return Ok(130);
+            }
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Ok(event)) if event.paths.iter().any(|v| filter.is_match(v)) => break,
+                Ok(_) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(0),
+            }
+        }
+        // Let the rest of a burst of changes settle, e.g. a save touching several files
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
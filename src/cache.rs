@@ -0,0 +1,127 @@
+use crate::parser::{parse, Event};
+use crate::Result;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+/// Parsed events of a script, valid while the script keeps its path, size, mtime and content
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    version: String,
+    path: PathBuf,
+    size: u64,
+    mtime: (u64, u32),
+    hash: u64,
+    events: Vec<Event>,
+}
+
+/// Parse the script, reusing the events cached for it when caching is turned on.
+/// Any problem with the cache falls back to parsing
+pub(crate) fn parse_cached(source: &str, script_path: Option<&str>) -> Result<Vec<Event>> {
+    let Some((cache_file, entry)) = script_path.and_then(|v| lookup(source, Path::new(v))) else {
+        return parse(source);
+    };
+    if let Some(events) = read(&cache_file, &entry) {
+        return Ok(events);
+    }
+    let events = parse(source)?;
+    let entry = CacheEntry { events, ..entry };
+    save(&cache_file, &entry);
+    Ok(entry.events)
+}
+
+/// The cached events of the script, without parsing it on a miss
+pub(crate) fn read_cached(source: &str, script_path: Option<&str>) -> Option<Vec<Event>> {
+    let (cache_file, entry) = lookup(source, Path::new(script_path?))?;
+    read(&cache_file, &entry)
+}
+
+/// The cache file of the script and the entry it should hold, without events
+fn lookup(source: &str, script_path: &Path) -> Option<(PathBuf, CacheEntry)> {
+    let cache_dir = get_cache_dir()?;
+    let path = fs::canonicalize(script_path).ok()?;
+    let metadata = fs::metadata(&path).ok()?;
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let cache_file = cache_dir.join(format!("{:016x}.bin", hash(&path)));
+    let entry = CacheEntry {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        path,
+        size: metadata.len(),
+        mtime: (mtime.as_secs(), mtime.subsec_nanos()),
+        hash: hash(source),
+        events: vec![],
+    };
+    Some((cache_file, entry))
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, other: &CacheEntry) -> bool {
+        self.version == other.version
+            && self.path == other.path
+            && self.size == other.size
+            && self.mtime == other.mtime
+            && self.hash == other.hash
+    }
+}
+
+/// `ARGC_CACHE_DIR`, or the argc folder of the XDG cache dir when `ARGC_CACHE` is set
+fn get_cache_dir() -> Option<PathBuf> {
+    if env::var_os("ARGC_NO_CACHE").is_some_and(|v| !v.is_empty()) {
+        return None;
+    }
+    if let Some(dir) = env::var_os("ARGC_CACHE_DIR").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    env::var_os("ARGC_CACHE").filter(|v| !v.is_empty())?;
+    let base_dir = match env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(v) => PathBuf::from(v),
+        None => PathBuf::from(env::var_os("HOME").filter(|v| !v.is_empty())?).join(".cache"),
+    };
+    Some(base_dir.join("argc"))
+}
+
+fn read(cache_file: &Path, entry: &CacheEntry) -> Option<Vec<Event>> {
+    fs::read(cache_file)
+        .ok()
+        .and_then(|data| decode(&data))
+        .filter(|v| v.is_fresh(entry))
+        .map(|v| v.events)
+}
+
+fn decode(data: &[u8]) -> Option<CacheEntry> {
+    // The limit keeps a corrupted length from allocating more than the file holds
+    bincode::DefaultOptions::new()
+        .with_limit(data.len() as u64)
+        .deserialize(data)
+        .ok()
+}
+
+/// Write through a temporary file so concurrent readers never see a partial entry
+fn save(cache_file: &Path, entry: &CacheEntry) {
+    let Ok(data) = bincode::DefaultOptions::new().serialize(entry) else {
+        return;
+    };
+    let Some(cache_dir) = cache_file.parent() else {
+        return;
+    };
+    let tmp_file = cache_file.with_extension(format!("{}.tmp", std::process::id()));
+    let saved = fs::create_dir_all(cache_dir).is_ok()
+        && fs::write(&tmp_file, data).is_ok()
+        && fs::rename(&tmp_file, cache_file).is_ok();
+    if !saved {
+        let _ = fs::remove_file(&tmp_file);
+    }
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
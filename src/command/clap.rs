@@ -0,0 +1,207 @@
+use super::Command;
+use crate::param::{FlagOptionParam, PositionalParam, ValueType};
+
+impl Command {
+    /// Render clap builder code, one function per command
+    pub(crate) fn to_clap(&self, name: &str) -> String {
+        let name = self.name.as_deref().unwrap_or(name);
+        let mut fns = vec![];
+        self.render_clap(&[name], &mut fns);
+        let root_fn = clap_fn_name(&[name]);
+        let mut output = vec![
+            "use clap::{Arg, ArgAction, Command};".to_string(),
+            String::new(),
+            "pub fn command() -> Command {".to_string(),
+            format!("    {root_fn}()"),
+            "}".to_string(),
+        ];
+        for v in fns {
+            output.push(String::new());
+            output.push(v);
+        }
+        output.join("\n")
+    }
+
+    fn render_clap(&self, cmd_paths: &[&str], fns: &mut Vec<String>) {
+        let name = cmd_paths.last().unwrap();
+        let mut lines = vec![format!("Command::new({name:?})")];
+        if !self.describe.is_empty() {
+            lines.push(format!(".about({:?})", self.describe));
+        }
+        if let Some(version) = &self.version {
+            lines.push(format!(".version({:?})", version));
+        }
+        if let Some(author) = &self.author {
+            lines.push(format!(".author({:?})", author));
+        }
+        for (alias, visible, _) in &self.aliases {
+            if *visible {
+                lines.push(format!(".visible_alias({alias:?})"));
+            } else {
+                lines.push(format!(".alias({alias:?})"));
+            }
+        }
+        for param in &self.flag_option_params {
+            lines.push(format!(".arg({})", clap_flag_option(param)));
+        }
+        // For `src* dest!`, clap fills `dest` first when given a single value, like argc
+        if self
+            .positional_params
+            .iter()
+            .skip_while(|v| v.required)
+            .any(|v| v.required)
+        {
+            lines.push(".allow_missing_positional(true)".to_string());
+        }
+        for param in &self.positional_params {
+            lines.push(format!(".arg({})", clap_positional(param)));
+        }
+        let subcommands: Vec<&Command> = self.subcommands.iter().filter(|v| !v.is_main()).collect();
+        let mut children = vec![];
+        for subcmd in subcommands {
+            let name = subcmd.name.clone().unwrap_or_default();
+            let mut child_paths = cmd_paths.to_vec();
+            child_paths.push(&name);
+            lines.push(format!(".subcommand({}())", clap_fn_name(&child_paths)));
+            subcmd.render_clap(&child_paths, &mut children);
+        }
+        let body = lines.join("\n        ");
+        fns.push(format!(
+            "fn {}() -> Command {{\n    {}\n}}",
+            clap_fn_name(cmd_paths),
+            body
+        ));
+        fns.extend(children);
+    }
+}
+
+fn clap_flag_option(param: &FlagOptionParam) -> String {
+    let mut output = format!("Arg::new({:?})", param.name);
+    if param.dashes.is_empty() {
+        output.push_str(&format!(".short({:?})", param.name.chars().next().unwrap()));
+    } else {
+        if let Some(short) = param.short {
+            output.push_str(&format!(".short({short:?})"));
+        }
+        output.push_str(&format!(".long({:?})", param.name));
+    }
+    for alias in &param.aliases {
+        output.push_str(&format!(".alias({:?})", alias.trim_start_matches('-')));
+    }
+    if !param.describe.is_empty() {
+        output.push_str(&format!(".help({:?})", param.describe));
+    }
+    if let Some(group) = &param.group {
+        output.push_str(&format!(".help_heading({group:?})"));
+    }
+    if param.global {
+        output.push_str(".global(true)");
+    }
+    if param.is_flag() {
+        if param.multiple {
+            output.push_str(".action(ArgAction::Count)");
+        } else {
+            output.push_str(".action(ArgAction::SetTrue)");
+        }
+        return output;
+    }
+    if param.multiple {
+        output.push_str(".action(ArgAction::Append)");
+    } else {
+        output.push_str(".action(ArgAction::Set)");
+    }
+    if param.required {
+        output.push_str(".required(true)");
+    }
+    if param.optional_value {
+        output.push_str(&format!(
+            ".num_args(0..=1).require_equals(true).default_missing_value({:?})",
+            param.get_bare_value()
+        ));
+    }
+    if param.arg_value_names.len() > 1 {
+        output.push_str(&format!(
+            ".value_names({:?}).num_args({})",
+            param.arg_value_names,
+            param.arg_value_names.len()
+        ));
+    } else {
+        output.push_str(&format!(".value_name({:?})", param.arg_value_names[0]));
+    }
+    output.push_str(&clap_values(
+        &param.choices.clone().filter(|_| !param.choices_any),
+        param.choices_ignore_case,
+        param.range,
+        param.value_type(0),
+        &param.default,
+    ));
+    if let Some(env) = &param.env {
+        output.push_str(&format!(".env({env:?})"));
+    }
+    output
+}
+
+fn clap_positional(param: &PositionalParam) -> String {
+    let mut output = format!("Arg::new({:?})", param.name);
+    if !param.describe.is_empty() {
+        output.push_str(&format!(".help({:?})", param.describe));
+    }
+    output.push_str(&format!(".value_name({:?})", param.arg_value_name));
+    if param.required {
+        output.push_str(".required(true)");
+    }
+    if param.terminated {
+        output.push_str(".num_args(1..).trailing_var_arg(true).allow_hyphen_values(true)");
+    } else if param.multiple {
+        output.push_str(".num_args(1..).action(ArgAction::Append)");
+    }
+    output.push_str(&clap_values(
+        &param.choices.clone().filter(|_| !param.choices_any),
+        param.choices_ignore_case,
+        param.range,
+        param.value_type,
+        &param.default,
+    ));
+    output
+}
+
+fn clap_values(
+    choices: &Option<Vec<String>>,
+    ignore_case: bool,
+    range: Option<(i64, i64)>,
+    value_type: Option<ValueType>,
+    default: &Option<String>,
+) -> String {
+    let mut output = String::new();
+    if let Some(choices) = choices {
+        output.push_str(&format!(".value_parser({:?})", choices));
+        if ignore_case {
+            output.push_str(".ignore_case(true)");
+        }
+    } else if let Some((start, end)) = range {
+        output.push_str(&format!(
+            ".value_parser(clap::value_parser!(i64).range({start}..={end}))"
+        ));
+    } else if let Some(value_type) = value_type {
+        let kind = match value_type {
+            ValueType::Int => "i64",
+            ValueType::Float => "f64",
+            ValueType::Bool => "bool",
+            ValueType::File | ValueType::Dir | ValueType::Path => "std::path::PathBuf",
+        };
+        output.push_str(&format!(".value_parser(clap::value_parser!({kind}))"));
+    }
+    if let Some(default) = default {
+        output.push_str(&format!(".default_value({default:?})"));
+    }
+    output
+}
+
+fn clap_fn_name(cmd_paths: &[&str]) -> String {
+    let name: String = cmd_paths
+        .join("_")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("cmd_{}", name.to_lowercase())
+}
@@ -0,0 +1,121 @@
+use super::Command;
+
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// Defaults and choices of a param, read from the `@meta config` file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigEntry {
+    default: Option<ConfigValue>,
+    choices: Option<Vec<ConfigValue>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ConfigValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl ConfigValue {
+    fn to_value(&self) -> String {
+        match self {
+            ConfigValue::Bool(v) => v.to_string(),
+            ConfigValue::Int(v) => v.to_string(),
+            ConfigValue::Float(v) => v.to_string(),
+            ConfigValue::Text(v) => v.clone(),
+        }
+    }
+}
+
+impl Command {
+    /// Apply `@meta config` files to the command and its subcommands, paths are relative to the script
+    pub(crate) fn load_config_files(&mut self, script_path: Option<&str>) -> Result<()> {
+        let base_dir = script_path
+            .and_then(|v| Path::new(v).parent())
+            .unwrap_or_else(|| Path::new(""));
+        self.apply_config_files(base_dir)
+    }
+
+    fn apply_config_files(&mut self, base_dir: &Path) -> Result<()> {
+        if let Some((_, file, pos)) = self.metadata.iter().find(|(k, _, _)| k == "config") {
+            let (file, pos) = (file.clone(), *pos);
+            let path = base_dir.join(&file);
+            let content = match fs::read_to_string(&path) {
+                Ok(v) => v,
+                Err(_) => bail!("@meta(line {}) config '{}' is missing", pos, file),
+            };
+            let entries: std::result::Result<IndexMap<String, ConfigEntry>, String> =
+                match path.extension().and_then(|v| v.to_str()) {
+                    Some("json") => serde_json::from_str(&content).map_err(|err| err.to_string()),
+                    Some("toml") => toml::from_str(&content).map_err(|err| err.to_string()),
+                    _ => bail!(
+                        "@meta(line {}) config '{}' must be a .json or .toml file",
+                        pos,
+                        file
+                    ),
+                };
+            let entries = match entries {
+                Ok(v) => v,
+                Err(err) => bail!("@meta(line {}) config '{}' is invalid, {}", pos, file, err),
+            };
+            for (name, entry) in entries.iter() {
+                if !self.apply_config_entry(name, entry) {
+                    bail!(
+                        "@meta(line {}) config '{}' has unknown option '{}'",
+                        pos,
+                        file,
+                        name
+                    );
+                }
+            }
+        }
+        for subcmd in self.subcommands.iter_mut() {
+            subcmd.apply_config_files(base_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Fill in what the inline modifiers leave unset, returns whether any param matches
+    fn apply_config_entry(&mut self, name: &str, entry: &ConfigEntry) -> bool {
+        let default = entry.default.as_ref().map(|v| v.to_value());
+        let choices: Option<Vec<String>> = entry
+            .choices
+            .as_ref()
+            .map(|v| v.iter().map(|v| v.to_value()).collect());
+        let mut found = false;
+        for param in self
+            .flag_option_params
+            .iter_mut()
+            .filter(|v| v.name == name && !v.is_flag())
+        {
+            found = true;
+            if param.default.is_none() && param.default_fn.is_none() {
+                param.default.clone_from(&default);
+            }
+            if param.choices.is_none() && param.choices_fn.is_none() {
+                param.choices.clone_from(&choices);
+            }
+        }
+        for param in self.positional_params.iter_mut().filter(|v| v.name == name) {
+            found = true;
+            if param.default.is_none() && param.default_fn.is_none() {
+                param.default.clone_from(&default);
+            }
+            if param.choices.is_none() && param.choices_fn.is_none() {
+                param.choices.clone_from(&choices);
+            }
+        }
+        for subcmd in self.subcommands.iter_mut() {
+            if subcmd.apply_config_entry(name, entry) {
+                found = true;
+            }
+        }
+        found
+    }
+}
@@ -0,0 +1,337 @@
+use super::Command;
+use crate::param::{FlagOptionParam, PositionalParam};
+
+/// What a `SpecChange` is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpecChangeKind {
+    CommandAdded,
+    CommandRemoved,
+    OptionAdded,
+    OptionRemoved,
+    PositionalAdded,
+    PositionalRemoved,
+    RequiredChanged,
+    ChoicesChanged,
+    DefaultChanged,
+    NamesChanged,
+    KindChanged,
+    MultipleChanged,
+    ValuesChanged,
+}
+
+impl SpecChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpecChangeKind::CommandAdded => "command-added",
+            SpecChangeKind::CommandRemoved => "command-removed",
+            SpecChangeKind::OptionAdded => "option-added",
+            SpecChangeKind::OptionRemoved => "option-removed",
+            SpecChangeKind::PositionalAdded => "positional-added",
+            SpecChangeKind::PositionalRemoved => "positional-removed",
+            SpecChangeKind::RequiredChanged => "required-changed",
+            SpecChangeKind::ChoicesChanged => "choices-changed",
+            SpecChangeKind::DefaultChanged => "default-changed",
+            SpecChangeKind::NamesChanged => "names-changed",
+            SpecChangeKind::KindChanged => "kind-changed",
+            SpecChangeKind::MultipleChanged => "multiple-changed",
+            SpecChangeKind::ValuesChanged => "values-changed",
+        }
+    }
+}
+
+/// A difference between two specs, from `diff_specs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecChange {
+    pub kind: SpecChangeKind,
+    /// Subcommand names from the root to the command the change is in
+    pub path: Vec<String>,
+    /// Whether command lines accepted by the old spec can be rejected by the new one
+    pub breaking: bool,
+    pub summary: String,
+}
+
+/// Compare two command trees, commands and params are matched by name.
+/// Changes are listed command by command, depth first, in script order
+pub fn diff_specs(old: &Command, new: &Command) -> Vec<SpecChange> {
+    let mut output = vec![];
+    diff_commands(old, new, &mut vec![], &mut output);
+    output
+}
+
+fn diff_commands(
+    old: &Command,
+    new: &Command,
+    path: &mut Vec<String>,
+    output: &mut Vec<SpecChange>,
+) {
+    let mut push = |kind, breaking, summary: String| {
+        let summary = if path.is_empty() {
+            summary
+        } else {
+            format!("{}: {}", path.join(" "), summary)
+        };
+        output.push(SpecChange {
+            kind,
+            path: path.clone(),
+            breaking,
+            summary,
+        })
+    };
+
+    for param in &old.flag_option_params {
+        if !new.flag_option_params.iter().any(|v| v.name == param.name) {
+            let summary = format!("{} removed", describe_flag_option(param));
+            push(SpecChangeKind::OptionRemoved, true, summary);
+        }
+    }
+    for param in &new.flag_option_params {
+        match old.flag_option_params.iter().find(|v| v.name == param.name) {
+            None => {
+                let summary = format!("{} added", describe_flag_option(param));
+                push(SpecChangeKind::OptionAdded, param.required, summary);
+            }
+            Some(old_param) => diff_params(
+                &describe_flag_option(param),
+                ParamValues::from_flag_option(old_param),
+                ParamValues::from_flag_option(param),
+                &mut push,
+            ),
+        }
+    }
+
+    for param in &old.positional_params {
+        if !new.positional_params.iter().any(|v| v.name == param.name) {
+            let summary = format!("{} removed", describe_positional(param));
+            push(SpecChangeKind::PositionalRemoved, true, summary);
+        }
+    }
+    for param in &new.positional_params {
+        match old.positional_params.iter().find(|v| v.name == param.name) {
+            None => {
+                let summary = format!("{} added", describe_positional(param));
+                push(SpecChangeKind::PositionalAdded, param.required, summary);
+            }
+            Some(old_param) => diff_params(
+                &describe_positional(param),
+                ParamValues::from_positional(old_param),
+                ParamValues::from_positional(param),
+                &mut push,
+            ),
+        }
+    }
+
+    for subcmd in &old.subcommands {
+        let name = subcmd.name.clone().unwrap_or_default();
+        if !new.subcommands.iter().any(|v| v.name == subcmd.name) {
+            push(
+                SpecChangeKind::CommandRemoved,
+                true,
+                format!("command '{name}' removed"),
+            );
+        }
+    }
+    for subcmd in &new.subcommands {
+        let name = subcmd.name.clone().unwrap_or_default();
+        match old.subcommands.iter().find(|v| v.name == subcmd.name) {
+            None => push(
+                SpecChangeKind::CommandAdded,
+                false,
+                format!("command '{name}' added"),
+            ),
+            Some(old_subcmd) => {
+                let aliases = |cmd: &Command| -> Vec<String> {
+                    cmd.aliases.iter().map(|(v, _, _)| v.clone()).collect()
+                };
+                let (old_aliases, new_aliases) = (aliases(old_subcmd), aliases(subcmd));
+                if old_aliases != new_aliases {
+                    push(
+                        SpecChangeKind::NamesChanged,
+                        old_aliases.iter().any(|v| !new_aliases.contains(v)),
+                        describe_list(
+                            &format!("command '{name}'"),
+                            "aliases",
+                            &old_aliases,
+                            &new_aliases,
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    for subcmd in &new.subcommands {
+        if let Some(old_subcmd) = old.subcommands.iter().find(|v| v.name == subcmd.name) {
+            path.push(subcmd.name.clone().unwrap_or_default());
+            diff_commands(old_subcmd, subcmd, path, output);
+            path.pop();
+        }
+    }
+}
+
+/// The fields of a flag/option or positional that `diff_specs` compares
+struct ParamValues<'a> {
+    /// Every name the param is matched by on the command line, none for a positional
+    names: Vec<String>,
+    /// Whether the param is a flag, none for a positional
+    flag: Option<bool>,
+    multiple: bool,
+    /// How many values one occurrence takes
+    values: usize,
+    required: bool,
+    choices: &'a Option<Vec<String>>,
+    choices_any: bool,
+    default: &'a Option<String>,
+    default_fn: &'a Option<String>,
+}
+
+impl<'a> ParamValues<'a> {
+    fn from_flag_option(param: &'a FlagOptionParam) -> Self {
+        Self {
+            names: param.list_all_names(),
+            flag: Some(param.is_flag()),
+            multiple: param.multiple,
+            values: if param.is_flag() {
+                0
+            } else {
+                param.arg_value_names.len()
+            },
+            required: param.required,
+            choices: &param.choices,
+            choices_any: param.choices_any,
+            default: &param.default,
+            default_fn: &param.default_fn,
+        }
+    }
+
+    fn from_positional(param: &'a PositionalParam) -> Self {
+        Self {
+            names: vec![],
+            flag: None,
+            multiple: param.multiple,
+            values: 1,
+            required: param.required,
+            choices: &param.choices,
+            choices_any: param.choices_any,
+            default: &param.default,
+            default_fn: &param.default_fn,
+        }
+    }
+}
+
+fn diff_params(
+    name: &str,
+    old: ParamValues,
+    new: ParamValues,
+    push: &mut impl FnMut(SpecChangeKind, bool, String),
+) {
+    if old.names != new.names {
+        push(
+            SpecChangeKind::NamesChanged,
+            old.names.iter().any(|v| !new.names.contains(v)),
+            describe_list(name, "names", &old.names, &new.names),
+        );
+    }
+    if old.flag != new.flag {
+        let summary = if new.flag == Some(true) {
+            format!("{name} was an option")
+        } else {
+            format!("{name} was a flag")
+        };
+        push(SpecChangeKind::KindChanged, true, summary);
+    } else if old.multiple != new.multiple {
+        let summary = if new.multiple {
+            format!("{name} became multiple")
+        } else {
+            format!("{name} became single")
+        };
+        push(SpecChangeKind::MultipleChanged, old.multiple, summary);
+    } else if old.values != new.values {
+        let summary = format!(
+            "{name} takes {} values instead of {}",
+            new.values, old.values
+        );
+        push(SpecChangeKind::ValuesChanged, true, summary);
+    }
+    if old.required != new.required {
+        let summary = if new.required {
+            format!("{name} became required")
+        } else {
+            format!("{name} became optional")
+        };
+        push(SpecChangeKind::RequiredChanged, new.required, summary);
+    }
+    if (old.choices, old.choices_any) != (new.choices, new.choices_any) {
+        push(
+            SpecChangeKind::ChoicesChanged,
+            is_choices_breaking(&old, &new),
+            describe_list(
+                name,
+                "choices",
+                old.choices.as_deref().unwrap_or_default(),
+                new.choices.as_deref().unwrap_or_default(),
+            ),
+        );
+    }
+    if (old.default, old.default_fn) != (new.default, new.default_fn) {
+        let render = |values: &ParamValues| match (values.default, values.default_fn) {
+            (Some(value), _) => format!("'{value}'"),
+            (None, Some(default_fn)) => format!("`{default_fn}`"),
+            (None, None) => "none".into(),
+        };
+        push(
+            SpecChangeKind::DefaultChanged,
+            false,
+            format!(
+                "{name} default changed from {} to {}",
+                render(&old),
+                render(&new)
+            ),
+        );
+    }
+}
+
+fn describe_flag_option(param: &FlagOptionParam) -> String {
+    let kind = if param.is_flag() { "flag" } else { "option" };
+    format!("{kind} '{}'", param.render_name())
+}
+
+fn describe_positional(param: &PositionalParam) -> String {
+    format!("positional '{}'", param.name)
+}
+
+/// What was added to and removed from a list like the choices, e.g. `option '--x' choices added c`
+fn describe_list(name: &str, what: &str, old: &[String], new: &[String]) -> String {
+    let added: Vec<&str> = new
+        .iter()
+        .filter(|v| !old.contains(v))
+        .map(|v| v.as_str())
+        .collect();
+    let removed: Vec<&str> = old
+        .iter()
+        .filter(|v| !new.contains(v))
+        .map(|v| v.as_str())
+        .collect();
+    let mut parts = vec![];
+    if !added.is_empty() {
+        parts.push(format!("added {}", added.join(", ")));
+    }
+    if !removed.is_empty() {
+        parts.push(format!("removed {}", removed.join(", ")));
+    }
+    if parts.is_empty() {
+        format!("{name} {what} changed")
+    } else {
+        format!("{name} {what} {}", parts.join("; "))
+    }
+}
+
+/// A new choice list rejects values an old one accepted
+fn is_choices_breaking(old: &ParamValues, new: &ParamValues) -> bool {
+    match (old.choices, new.choices) {
+        (_, None) => false,
+        (_, Some(_)) if new.choices_any => false,
+        (None, Some(_)) => true,
+        (Some(old_choices), Some(new_choices)) => {
+            old.choices_any || old_choices.iter().any(|v| !new_choices.contains(v))
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::parser::Position;
+use crate::utils::did_you_mean;
+
+use anyhow::{bail, Result};
+use semver::Version;
+
+/// Values accepted by a `@meta` key
+enum MetaValue {
+    /// A switch without value, e.g. `no-forward-positionals`
+    Switch,
+    /// Any non-empty value
+    Text,
+    /// One of the listed values
+    Choices(&'static [&'static str]),
+}
+
+/// Classes of errors that `@meta exit-code` can map to exit codes, all exit 1 by default
+pub(crate) const ERROR_CLASSES: [&str; 6] = [
+    "spec-error",
+    "unknown-subcommand",
+    "unknown-option",
+    "unexpected-argument",
+    "missing-required",
+    "invalid-value",
+];
+
+/// Known `@meta` keys, they apply to the command and its subcommands
+const META_KEYS: [(&str, MetaValue); 20] = [
+    ("no-forward-positionals", MetaValue::Switch),
+    ("require-subcommand", MetaValue::Switch),
+    ("no-auto-annotations", MetaValue::Switch),
+    ("full-help-on-error", MetaValue::Switch),
+    ("preserve-order", MetaValue::Switch),
+    ("inherit-cwd", MetaValue::Switch),
+    ("pass-unknown", MetaValue::Switch),
+    ("strict-shell", MetaValue::Switch),
+    ("sparse-vars", MetaValue::Switch),
+    ("args-separator", MetaValue::Text),
+    ("usage", MetaValue::Text),
+    ("config", MetaValue::Text),
+    ("var-prefix", MetaValue::Text),
+    ("exit-code", MetaValue::Text),
+    ("doc-marker", MetaValue::Text),
+    ("lang", MetaValue::Text),
+    ("shell", MetaValue::Text),
+    ("min-argc-version", MetaValue::Text),
+    ("notation-complete", MetaValue::Text),
+    ("parse-mode", MetaValue::Choices(&["gnu", "posix"])),
+];
+
+pub(crate) fn check_meta(key: &str, value: &str, pos: Position) -> Result<()> {
+    let kind = match META_KEYS.iter().find(|(name, _)| *name == key) {
+        Some((_, kind)) => kind,
+        None => {
+            let names: Vec<String> = META_KEYS.iter().map(|(v, _)| v.to_string()).collect();
+            match did_you_mean(key, &names) {
+                Some(name) => bail!(
+                    "@meta(line {}) has unknown key '{}', did you mean '{}'?",
+                    pos,
+                    key,
+                    name
+                ),
+                None => bail!("@meta(line {}) has unknown key '{}'", pos, key),
+            }
+        }
+    };
+    match kind {
+        MetaValue::Switch => {
+            if !value.is_empty() {
+                bail!("@meta(line {}) {} takes no value", pos, key);
+            }
+        }
+        MetaValue::Text => {
+            if value.is_empty() {
+                bail!("@meta(line {}) {} requires a value", pos, key);
+            }
+        }
+        MetaValue::Choices(choices) => {
+            if !choices.contains(&value) {
+                let list: Vec<String> = choices.iter().map(|v| format!("'{v}'")).collect();
+                bail!("@meta(line {}) {} must be {}", pos, key, list.join(" or "));
+            }
+        }
+    }
+    if key == "var-prefix"
+        && !value
+            .chars()
+            .enumerate()
+            .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()))
+    {
+        bail!(
+            "@meta(line {}) var-prefix '{}' is not a valid variable name",
+            pos,
+            value
+        );
+    }
+    if key == "doc-marker" && !value.chars().all(|c| c == '#') {
+        bail!(
+            "@meta(line {}) doc-marker '{}' must only contain '#'",
+            pos,
+            value
+        );
+    }
+    if key == "min-argc-version" {
+        let Some(min_version) = parse_min_version(value) else {
+            bail!(
+                "@meta(line {}) min-argc-version '{}' is not a version like '1.8' or '1.8.2'",
+                pos,
+                value
+            );
+        };
+        if argc_version() < min_version {
+            bail!(
+                "@meta(line {}) min-argc-version requires argc {} or newer, this is argc {}",
+                pos,
+                value,
+                env!("CARGO_PKG_VERSION")
+            );
+        }
+    }
+    if key == "exit-code" {
+        check_exit_codes(value, pos)?;
+    }
+    if key == "notation-complete" && parse_notation_complete(value).is_none() {
+        bail!(
+            "@meta(line {}) notation-complete '{}' must be like 'NOTATION fn'",
+            pos,
+            value
+        );
+    }
+    if key == "args-separator" && value != "--" && value.starts_with('-') {
+        bail!(
+            "@meta(line {}) args-separator '{}' looks like an option",
+            pos,
+            value
+        );
+    }
+    Ok(())
+}
+
+/// Split a `notation-complete` value into the notation and the function completing it
+pub(crate) fn parse_notation_complete(value: &str) -> Option<(&str, &str)> {
+    match value.split_whitespace().collect::<Vec<_>>()[..] {
+        [notation, fn_name] => Some((notation, fn_name)),
+        _ => None,
+    }
+}
+
+/// Read a `min-argc-version` value as semver, `1.8` means `1.8.0`
+pub(crate) fn parse_min_version(value: &str) -> Option<Version> {
+    let (core, rest) = value.split_at(value.find(['-', '+']).unwrap_or(value.len()));
+    let missing = 3usize.saturating_sub(core.split('.').count());
+    Version::parse(&format!("{core}{}{rest}", ".0".repeat(missing))).ok()
+}
+
+pub(crate) fn argc_version() -> Version {
+    Version::parse(env!("CARGO_PKG_VERSION")).unwrap()
+}
+
+/// Check `class=code` pairs of `@meta exit-code`
+fn check_exit_codes(value: &str, pos: Position) -> Result<()> {
+    for pair in value.split_whitespace() {
+        let Some((class, code)) = pair.split_once('=') else {
+            bail!(
+                "@meta(line {}) exit-code '{}' must be like 'class=code'",
+                pos,
+                pair
+            );
+        };
+        if !ERROR_CLASSES.contains(&class) {
+            let names: Vec<String> = ERROR_CLASSES.iter().map(|v| v.to_string()).collect();
+            match did_you_mean(class, &names) {
+                Some(name) => bail!(
+                    "@meta(line {}) exit-code has unknown error class '{}', did you mean '{}'?",
+                    pos,
+                    class,
+                    name
+                ),
+                None => bail!(
+                    "@meta(line {}) exit-code has unknown error class '{}'",
+                    pos,
+                    class
+                ),
+            }
+        }
+        if !matches!(code.parse::<u8>(), Ok(1..)) {
+            bail!(
+                "@meta(line {}) exit-code '{}' needs a code between 1 and 255",
+                pos,
+                pair
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Exit code of the error class under a `@meta exit-code` value
+pub(crate) fn get_exit_code(value: Option<&str>, class: &str) -> i32 {
+    value
+        .and_then(|value| {
+            value
+                .split_whitespace()
+                .find_map(|pair| match pair.split_once('=') {
+                    Some((name, code)) if name == class => code.parse().ok(),
+                    _ => None,
+                })
+        })
+        .unwrap_or(1)
+}
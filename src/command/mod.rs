@@ -1,16 +1,28 @@
+mod clap;
+mod config;
+mod diff;
+mod meta;
 mod names_checker;
 mod root_data;
 
-use self::names_checker::NamesChecker;
+pub use self::diff::{diff_specs, SpecChange, SpecChangeKind};
+pub(crate) use self::meta::get_exit_code;
+use self::meta::{argc_version, check_meta, parse_min_version, parse_notation_complete};
+use self::names_checker::{check_cmd_name, NamesChecker};
 use self::root_data::RootData;
 
 use crate::argc_value::ArgcValue;
+use crate::cache::{parse_cached, read_cached};
 use crate::matcher::Matcher;
-use crate::param::{FlagOptionParam, PositionalParam};
-use crate::parser::{parse, Event, EventData, EventScope, Position};
+use crate::param::{in_range, FlagOptionParam, PositionalParam};
+use crate::parser::{
+    find_root_meta, parse, parse_lazy, Event, EventData, EventScope, Locales, Position,
+};
+use crate::utils::{display_width, localize, render_plain_text, shell_has_arrays, to_cobol_case};
 use crate::Result;
 
 use anyhow::{bail, Context};
+use indexmap::IndexMap;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::result::Result as StdResult;
@@ -22,37 +34,251 @@ pub fn eval(
     script_path: Option<&str>,
     term_width: Option<usize>,
 ) -> Result<Vec<ArgcValue>> {
-    let mut cmd = Command::new(script_content)?;
+    let mut cmd = Command::new_cached(script_content, script_path)?;
     cmd.eval(args, script_path, term_width)
 }
 
-pub fn export(source: &str) -> Result<serde_json::Value> {
-    let cmd = Command::new(source)?;
+/// What `eval_outcome` returns, the values `eval` would return or the help text the args asked for
+#[derive(Debug, PartialEq, Eq)]
+pub enum EvalOutcome {
+    Values(Vec<ArgcValue>),
+    HelpRequested(String),
+}
+
+/// Like `eval`, but hand back the help text instead of values that print it and exit,
+/// so a program embedding argc can show it where it wants
+pub fn eval_outcome(
+    script_content: &str,
+    args: &[String],
+    script_path: Option<&str>,
+    term_width: Option<usize>,
+) -> Result<EvalOutcome> {
+    let mut cmd = Command::new_cached(script_content, script_path)?;
+    cmd.eval_outcome(args, script_path, term_width)
+}
+
+/// Like `eval`, but dispatch to the function `fn_name` instead of resolving a subcommand from the args
+pub fn eval_fn(
+    script_content: &str,
+    fn_name: &str,
+    args: &[String],
+    script_path: Option<&str>,
+    term_width: Option<usize>,
+) -> Result<Vec<ArgcValue>> {
+    let mut cmd = Command::new_cached(script_content, script_path)?;
+    cmd.eval_fn(fn_name, args, script_path, term_width)
+}
+
+/// How each of `args` is matched, one JSON object per arg after the program name
+pub fn trace(script_content: &str, args: &[String]) -> Result<Vec<serde_json::Value>> {
+    if args.is_empty() {
+        bail!("Invalid args");
+    }
+    let cmd = Command::new(script_content)?;
+    Ok(Matcher::new(&cmd, args).to_trace())
+}
+
+pub fn export(source: &str, script_path: Option<&str>) -> Result<serde_json::Value> {
+    let cmd = Command::new_with_path(source, script_path)?;
     cmd.to_json().with_context(|| "Failed to export json")
 }
 
+/// Export a single subcommand, resolved by its names or aliases
+pub fn export_command(
+    source: &str,
+    script_path: Option<&str>,
+    names: &[String],
+) -> Result<serde_json::Value> {
+    let root = Command::new_with_path(source, script_path)?;
+    let mut cmds = vec![&root];
+    for name in names {
+        match cmds[cmds.len() - 1].find_subcommand(name) {
+            Some(subcmd) => cmds.push(subcmd),
+            None => bail!("Unknown subcommand '{}'", names.join(" ")),
+        }
+    }
+    let (cmd, parents) = cmds.split_last().unwrap();
+    cmd.to_node_json(parents)
+        .with_context(|| "Failed to export json")
+}
+
+pub fn export_dot(source: &str, script_path: Option<&str>, name: &str) -> Result<String> {
+    let cmd = Command::new_with_path(source, script_path)?;
+    Ok(cmd.to_dot(name))
+}
+
+pub fn export_clap(source: &str, script_path: Option<&str>, name: &str) -> Result<String> {
+    let cmd = Command::new_with_path(source, script_path)?;
+    Ok(cmd.to_clap(name))
+}
+
+/// Where `Command::resolve_path` failed to resolve a subcommand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveError {
+    /// Index of the unresolved token in the args
+    pub index: usize,
+    /// The unresolved token
+    pub token: String,
+    /// Subcommand names resolved before the token
+    pub path: Vec<String>,
+    /// Names and aliases accepted at that point
+    pub candidates: Vec<String>,
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unknown subcommand '{}' at argument {}",
+            self.token, self.index
+        )
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// Parse the script and list the problems with its comment tags that don't stop it from running
+pub fn check(source: &str) -> Result<Vec<String>> {
+    let cmd = Command::new(source)?;
+    let warnings = cmd.root.borrow().warnings.clone();
+    Ok(warnings)
+}
+
+/// Lines of the `eval "$(argc --argc-eval ...)"` calls in the script
+pub fn script_eval_lines(source: &str) -> Result<Vec<usize>> {
+    let events = parse(source)?;
+    Ok(events
+        .into_iter()
+        .filter(|v| v.data == EventData::Eval)
+        .map(|v| v.position)
+        .collect())
+}
+
+/// Exit code for a script whose comment tags are invalid, from the root `@meta exit-code`
+pub fn spec_error_exit_code(source: &str) -> i32 {
+    get_exit_code(find_root_meta(source, "exit-code").as_deref(), "spec-error")
+}
+
+/// Version from the root `@meta min-argc-version` and whether the running argc satisfies it
+pub fn check_min_version(source: &str) -> Result<Option<(String, bool)>> {
+    let Some(value) = find_root_meta(source, "min-argc-version") else {
+        return Ok(None);
+    };
+    let Some(min_version) = parse_min_version(&value) else {
+        bail!(
+            "min-argc-version '{}' is not a version like '1.8' or '1.8.2'",
+            value
+        );
+    };
+    Ok(Some((value, argc_version() >= min_version)))
+}
+
+/// Interpreter command from the root `@meta shell`, `None` means bash
+pub fn script_shell(source: &str) -> Option<String> {
+    find_root_meta(source, "shell")
+}
+
+/// Whether the root `@meta inherit-cwd` asks Argcfile tasks to run in the caller's directory
+pub fn script_inherits_cwd(source: &str) -> bool {
+    find_root_meta(source, "inherit-cwd").is_some()
+}
+
+/// Script info from the root `@describe`, `@version`, `@author` and `@name` tags
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    pub describe: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    pub name: Option<String>,
+}
+
+/// Read the root info tags without building the whole command tree
+pub fn extract_metadata(source: &str) -> Result<Metadata> {
+    let events = parse(source)?;
+    let mut metadata = Metadata::default();
+    for event in events {
+        match event.data {
+            EventData::Describe(value) => metadata.describe = value,
+            EventData::Version(value) => metadata.version = Some(value),
+            EventData::Author(value) => metadata.author = Some(value),
+            EventData::Name(value) => metadata.name = Some(value),
+            EventData::Cmd(_) | EventData::Func(_) => break,
+            _ => {}
+        }
+    }
+    Ok(metadata)
+}
+
+/// A flag/option or positional of a command by index, `param_slots` keeps them in script order
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ParamSlot {
+    FlagOption(usize),
+    Positional(usize),
+}
+
 #[derive(Default)]
 pub struct Command {
     pub(crate) name: Option<String>,
     pub(crate) fn_name: Option<String>,
     pub(crate) describe: String,
+    pub(crate) describe_locales: Locales,
     pub(crate) flag_option_params: Vec<FlagOptionParam>,
     pub(crate) positional_params: Vec<PositionalParam>,
     pub(crate) positional_pos: Vec<Position>,
+    pub(crate) param_slots: Vec<ParamSlot>,
     pub(crate) subcommands: Vec<Command>,
     pub(crate) author: Option<String>,
     pub(crate) version: Option<String>,
     pub(crate) subcommand_fns: HashMap<String, Position>,
-    pub(crate) alias_pos: usize,
     pub(crate) names_checker: NamesChecker,
     pub(crate) root: Arc<RefCell<RootData>>,
-    pub(crate) aliases: Vec<String>,
+    pub(crate) aliases: Vec<(String, bool, Position)>,
+    pub(crate) metadata: Vec<(String, String, Position)>,
+    pub(crate) group: Option<String>,
+    /// Lines of `@output`, what the command prints
+    pub(crate) outputs: Vec<String>,
+    /// Positions of the `eval "$(argc --argc-eval ...)"` lines, only set on the root command
+    pub(crate) eval_lines: Vec<Position>,
 }
 
 impl Command {
+    /// Build the command tree without reading the files the script refers to,
+    /// so `[@path]` choices and `@meta config` are left unapplied
     pub fn new(source: &str) -> Result<Self> {
         let events = parse(source)?;
-        Command::new_from_events(&events)
+        Command::new_from_events(events)
+    }
+
+    /// Like `new`, also reading the files the script refers to, relative to `script_path`
+    pub fn new_with_path(source: &str, script_path: Option<&str>) -> Result<Self> {
+        let events = parse(source)?;
+        Command::new_from_events_with_path(events, script_path)
+    }
+
+    /// Like `new_with_path`, but reuse the cached parse of the script when caching is turned on
+    pub(crate) fn new_cached(source: &str, script_path: Option<&str>) -> Result<Self> {
+        let events = parse_cached(source, script_path)?;
+        Command::new_from_events_with_path(events, script_path)
+    }
+
+    /// Build the command tree for completing `args`, from the cache or a lazy parse
+    pub(crate) fn new_lazy(
+        source: &str,
+        script_path: Option<&str>,
+        args: &[String],
+    ) -> Result<Self> {
+        let events = match read_cached(source, script_path) {
+            Some(events) => events,
+            None => parse_lazy(source, args)?,
+        };
+        Command::new_from_events_with_path(events, script_path)
+    }
+
+    fn new_from_events_with_path(events: Vec<Event>, script_path: Option<&str>) -> Result<Self> {
+        let mut cmd = Command::new_from_events(events)?;
+        cmd.load_choices_files(script_path)?;
+        cmd.load_config_files(script_path)?;
+        Ok(cmd)
     }
 
     pub fn eval(
@@ -61,11 +287,42 @@ impl Command {
         script_path: Option<&str>,
         term_width: Option<usize>,
     ) -> Result<Vec<ArgcValue>> {
+        match self.eval_inner(args, script_path, term_width, false)? {
+            EvalOutcome::Values(values) => Ok(values),
+            EvalOutcome::HelpRequested(help) => Ok(vec![ArgcValue::Error((help, 0))]),
+        }
+    }
+
+    pub fn eval_outcome(
+        &mut self,
+        args: &[String],
+        script_path: Option<&str>,
+        term_width: Option<usize>,
+    ) -> Result<EvalOutcome> {
+        self.eval_inner(args, script_path, term_width, true)
+    }
+
+    fn eval_inner(
+        &mut self,
+        args: &[String],
+        script_path: Option<&str>,
+        term_width: Option<usize>,
+        return_help: bool,
+    ) -> Result<EvalOutcome> {
         if args.is_empty() {
             bail!("Invalid args");
         }
         if args.len() >= 2 && self.root.borrow().exist_param_fn(args[1].as_str()) {
             let mut arg_values = vec![];
+            if let Some(prefix) = self.get_metadata("var-prefix") {
+                arg_values.push(ArgcValue::VarPrefix(prefix.to_string()));
+            }
+            if self
+                .get_metadata("shell")
+                .is_some_and(|v| !shell_has_arrays(v))
+            {
+                arg_values.push(ArgcValue::NoArrays);
+            }
             let words = &args[2..];
             if words.len() > 1 {
                 let matcher = Matcher::new(self, words);
@@ -73,7 +330,7 @@ impl Command {
             }
             arg_values.push(ArgcValue::Multiple("_words".into(), words.to_vec()));
             arg_values.push(ArgcValue::ParamFn(args[1].clone()));
-            return Ok(arg_values);
+            return Ok(EvalOutcome::Values(arg_values));
         }
         let mut matcher = Matcher::new(self, args);
         if let Some(script_path) = script_path {
@@ -82,7 +339,103 @@ impl Command {
         if let Some(term_width) = term_width {
             matcher.set_term_width(term_width)
         }
-        Ok(matcher.to_arg_values())
+        if return_help {
+            if let Some(help) = matcher.to_help_text() {
+                return Ok(EvalOutcome::HelpRequested(help));
+            }
+        }
+        let mut arg_values: Vec<ArgcValue> = self
+            .root
+            .borrow()
+            .warnings
+            .iter()
+            .map(|v| ArgcValue::Warning(v.clone()))
+            .collect();
+        arg_values.extend(matcher.to_arg_values());
+        Ok(EvalOutcome::Values(arg_values))
+    }
+
+    pub fn eval_fn(
+        &mut self,
+        fn_name: &str,
+        args: &[String],
+        script_path: Option<&str>,
+        term_width: Option<usize>,
+    ) -> Result<Vec<ArgcValue>> {
+        if args.is_empty() {
+            bail!("Invalid args");
+        }
+        if let Some(cmd_paths) = self.find_fn_paths(fn_name) {
+            let mut new_args = vec![args[0].clone()];
+            new_args.extend(cmd_paths);
+            new_args.extend(args[1..].iter().cloned());
+            return self.eval(&new_args, script_path, term_width);
+        }
+        if !self.root.borrow().fns.contains_key(fn_name) {
+            bail!("Function '{}' not found", fn_name);
+        }
+        let mut arg_values = vec![];
+        if let Some(prefix) = self.get_metadata("var-prefix") {
+            arg_values.push(ArgcValue::VarPrefix(prefix.to_string()));
+        }
+        if self
+            .get_metadata("shell")
+            .is_some_and(|v| !shell_has_arrays(v))
+        {
+            arg_values.push(ArgcValue::NoArrays);
+        }
+        arg_values.push(ArgcValue::ExtraPositionalMultiple(args[1..].to_vec()));
+        arg_values.push(ArgcValue::CmdFn(fn_name.to_string()));
+        Ok(arg_values)
+    }
+
+    /// Subcommand names leading to the command bound to `fn_name`
+    fn find_fn_paths(&self, fn_name: &str) -> Option<Vec<String>> {
+        for subcmd in self.subcommands.iter() {
+            if subcmd.fn_name.as_deref() == Some(fn_name) {
+                if subcmd.is_main() {
+                    return Some(vec![]);
+                }
+                return Some(vec![subcmd.name.clone().unwrap_or_default()]);
+            }
+            if let Some(mut paths) = subcmd.find_fn_paths(fn_name) {
+                paths.insert(0, subcmd.name.clone().unwrap_or_default());
+                return Some(paths);
+            }
+        }
+        None
+    }
+
+    /// Replace subcommand aliases in `args` with the subcommand names, without running anything.
+    /// `args` don't include the program name. Options and positionals are kept as they are
+    pub fn resolve_path(&self, args: &[String]) -> StdResult<Vec<String>, ResolveError> {
+        let mut new_args = vec![self.name.clone().unwrap_or_default()];
+        new_args.extend(args.iter().cloned());
+        Matcher::new(self, &new_args).to_resolved_path()
+    }
+
+    pub(crate) fn load_choices_files(&mut self, script_path: Option<&str>) -> Result<()> {
+        let choices_files = self.root.borrow().load_choices_files(script_path)?;
+        if !choices_files.is_empty() {
+            self.set_choices_from_files(&choices_files);
+        }
+        Ok(())
+    }
+
+    fn set_choices_from_files(&mut self, choices_files: &HashMap<String, Vec<String>>) {
+        for param in self.flag_option_params.iter_mut() {
+            if let Some(choices_file) = param.choices_file.as_ref() {
+                param.choices = choices_files.get(choices_file).cloned();
+            }
+        }
+        for param in self.positional_params.iter_mut() {
+            if let Some(choices_file) = param.choices_file.as_ref() {
+                param.choices = choices_files.get(choices_file).cloned();
+            }
+        }
+        for subcmd in self.subcommands.iter_mut() {
+            subcmd.set_choices_from_files(choices_files);
+        }
     }
 
     pub fn to_json(&self) -> StdResult<serde_json::Value, serde_json::Error> {
@@ -91,36 +444,114 @@ impl Command {
         let flag_option_params: StdResult<Vec<serde_json::Value>, _> = self
             .flag_option_params
             .iter()
-            .map(serde_json::to_value)
-            .collect();
-        let positional_params: StdResult<Vec<serde_json::Value>, _> = self
-            .positional_params
-            .iter()
-            .map(serde_json::to_value)
+            .map(|v| v.to_json())
             .collect();
-        let value = serde_json::json!({
+        let positional_params: StdResult<Vec<serde_json::Value>, _> =
+            self.positional_params.iter().map(|v| v.to_json()).collect();
+        let mut value = serde_json::json!({
             "describe": self.describe,
             "name": self.name,
             "author": self.author,
             "version": self.version,
             "options": flag_option_params?,
             "positionals": positional_params?,
-            "aliases": self.aliases,
+            "aliases": self.list_visible_aliases(),
             "subcommands": subcommands?,
         });
+        if !self.metadata.is_empty() {
+            // Repeated keys like `notation-complete` keep one value per line
+            let mut meta: serde_json::Map<String, serde_json::Value> = serde_json::Map::new();
+            for (k, v, _) in self.metadata.iter() {
+                let value = match meta.get(k).and_then(|v| v.as_str()) {
+                    Some(exist) => format!("{exist}\n{v}"),
+                    None => v.clone(),
+                };
+                meta.insert(k.clone(), value.into());
+            }
+            value["meta"] = meta.into();
+        }
+        if let Some(group) = &self.group {
+            value["group"] = group.clone().into();
+        }
+        if !self.outputs.is_empty() {
+            value["output"] = self.outputs.clone().into();
+        }
+        if !self.eval_lines.is_empty() {
+            value["eval_lines"] = self.eval_lines.clone().into();
+        }
+        if !self.describe_locales.is_empty() {
+            value["describe_locales"] = serde_json::to_value(&self.describe_locales)?;
+        }
+        Ok(value)
+    }
+
+    /// Like `to_json`, plus the options of the parent commands that can be given before it
+    pub(crate) fn to_node_json(
+        &self,
+        parents: &[&Command],
+    ) -> StdResult<serde_json::Value, serde_json::Error> {
+        let mut value = self.to_json()?;
+        let parent_options: StdResult<Vec<serde_json::Value>, _> = parents
+            .iter()
+            .flat_map(|v| v.flag_option_params.iter())
+            .filter(|v| !v.env_only)
+            .map(|v| v.to_json())
+            .collect();
+        let parent_options = parent_options?;
+        if !parent_options.is_empty() {
+            value["parent_options"] = parent_options.into();
+        }
         Ok(value)
     }
 
-    pub(crate) fn new_from_events(events: &[Event]) -> Result<Self> {
+    pub fn to_dot(&self, name: &str) -> String {
+        let name = self.name.as_deref().unwrap_or(name);
+        let mut output = vec!["digraph {".to_string()];
+        self.render_dot(&[name], &mut output);
+        output.push("}".to_string());
+        output.join("\n")
+    }
+
+    pub(crate) fn new_from_events(events: Vec<Event>) -> Result<Self> {
         let mut root_cmd = Command::default();
         let root_data = root_cmd.root.clone();
-        for event in events {
-            let Event { data, position } = event.clone();
+        let mut first_cmd_pos = None;
+        let mut group: Option<String> = None;
+        let mut cmd_group: Option<String> = None;
+        root_data.borrow_mut().anchors = events
+            .iter()
+            .filter_map(|event| match event.data {
+                EventData::Cmd(_) => Some((event.position, false)),
+                EventData::Func(_) => Some((event.position, true)),
+                _ => None,
+            })
+            .collect();
+        let mut events = events.into_iter().peekable();
+        while let Some(Event { data, position }) = events.next() {
             match data {
                 EventData::Describe(value) => {
                     let cmd = Self::get_cmd(&mut root_cmd, "@describe", position)?;
                     cmd.describe = value;
                 }
+                EventData::DescribeLocales(value) => {
+                    let cmd = Self::get_cmd(&mut root_cmd, "@describe", position)?;
+                    cmd.describe_locales.extend(value);
+                }
+                EventData::DescribeLocale(locale, value) => {
+                    let tag_name = format!("@describe[{locale}]");
+                    check_default_lang(&root_cmd, &tag_name, &locale, position)?;
+                    let cmd = Self::get_cmd(&mut root_cmd, &tag_name, position)?;
+                    cmd.describe_locales.insert(locale, value);
+                }
+                EventData::CmdLocale(locale, value) => {
+                    let tag_name = format!("@cmd[{locale}]");
+                    if root_data.borrow().scope != EventScope::CmdStart {
+                        bail!("{}(line {}) must follow a @cmd", tag_name, position);
+                    }
+                    check_default_lang(&root_cmd, &tag_name, &locale, position)?;
+                    let cmd = Self::get_cmd(&mut root_cmd, &tag_name, position)?;
+                    cmd.describe_locales.insert(locale, value);
+                }
                 EventData::Version(value) => {
                     let cmd = Self::get_cmd(&mut root_cmd, "@version", position)?;
                     cmd.version = Some(value);
@@ -129,42 +560,164 @@ impl Command {
                     let cmd = Self::get_cmd(&mut root_cmd, "@author", position)?;
                     cmd.author = Some(value);
                 }
+                EventData::Name(value) => {
+                    if first_cmd_pos.is_some() {
+                        bail!(
+                            "@name(line {}) can only be used on the root command",
+                            position
+                        );
+                    }
+                    if value.is_empty() || value.contains(char::is_whitespace) {
+                        bail!("@name(line {}) must be a single word", position);
+                    }
+                    root_cmd.name = Some(value);
+                }
                 EventData::Cmd(value) => {
                     if root_data.borrow().scope == EventScope::CmdStart {
-                        bail!("@cmd(line {}) miss function?", root_data.borrow().cmd_pos)
+                        bail!(
+                            "@cmd(line {}) is not attached to any function",
+                            root_data.borrow().cmd_pos
+                        )
                     }
+                    if let Some(eval_pos) = root_cmd.eval_lines.first() {
+                        root_data.borrow_mut().warnings.push(format!(
+                            "@cmd(line {}) comes after the eval at line {}, so its function is not defined yet when argc calls it",
+                            position, eval_pos
+                        ));
+                    }
+                    first_cmd_pos.get_or_insert(position);
+                    group = None;
                     root_data.borrow_mut().cmd_pos = position;
                     root_data.borrow_mut().scope = EventScope::CmdStart;
-                    let mut subcmd = root_cmd.create_cmd();
+                    let subcmd = root_cmd.create_cmd();
+                    subcmd.group.clone_from(&cmd_group);
                     if !value.is_empty() {
                         subcmd.describe = value.clone();
                     }
                 }
                 EventData::Aliases(values) => {
                     let cmd = Self::get_cmd(&mut root_cmd, "@alias", position)?;
-                    cmd.alias_pos = position;
-                    cmd.aliases = values.to_vec();
+                    cmd.aliases
+                        .extend(values.iter().map(|v| (v.clone(), true, position)));
+                }
+                EventData::CompletionAliases(values) => {
+                    let cmd = Self::get_cmd(&mut root_cmd, "@completion-alias", position)?;
+                    cmd.aliases
+                        .extend(values.iter().map(|v| (v.clone(), false, position)));
                 }
-                EventData::FlagOption(param) => {
+                EventData::Meta(key, value) => {
+                    let cmd = Self::get_cmd(&mut root_cmd, "@meta", position)?;
+                    check_meta(&key, &value, position)?;
+                    let repeatable = key == "notation-complete";
+                    if let Some((_, _, exist_pos)) = cmd.metadata.iter().find(|(k, v, _)| {
+                        k == &key
+                            && (!repeatable
+                                || parse_notation_complete(v).map(|v| v.0)
+                                    == parse_notation_complete(&value).map(|v| v.0))
+                    }) {
+                        bail!(
+                            "@meta(line {}) has '{}' already exists at line {}",
+                            position,
+                            key,
+                            exist_pos
+                        );
+                    }
+                    if let Some((_, fn_name)) =
+                        parse_notation_complete(&value).filter(|_| repeatable)
+                    {
+                        root_data
+                            .borrow_mut()
+                            .choices_fns
+                            .push((fn_name.to_string(), position));
+                    }
+                    cmd.metadata.push((key, value, position));
+                }
+                EventData::Output(value) => {
+                    let cmd = Self::get_cmd(&mut root_cmd, "@output", position)?;
+                    if !value.is_empty() {
+                        cmd.outputs.push(value);
+                    }
+                }
+                EventData::Group(value) => {
+                    let value = Some(value).filter(|v| !v.is_empty());
+                    // A group right before `@cmd` sorts the following subcommands
+                    if let Some(EventData::Cmd(_)) = events.peek().map(|v| &v.data) {
+                        cmd_group = value;
+                    } else {
+                        Self::get_cmd(&mut root_cmd, "@group", position)?;
+                        group = value;
+                    }
+                }
+                EventData::FlagOption(mut param) => {
                     let cmd = Self::get_cmd(&mut root_cmd, param.tag_name(), position)?;
+                    param.group.clone_from(&group);
+                    if param.glob {
+                        bail!(
+                            "{}(line {}) can't use expand:glob, only multiple @arg can",
+                            param.tag_name(),
+                            position
+                        )
+                    }
+                    check_default_range(param.tag_name(), &param.default, &param.range, position)?;
+                    if param.env_only && param.env.is_none() {
+                        bail!(
+                            "{}(line {}) has env:only but no env:NAME binding",
+                            param.tag_name(),
+                            position
+                        )
+                    }
+                    root_data
+                        .borrow_mut()
+                        .add_terminal_fn(position, &param.terminal_fn);
                     if param.is_option() {
+                        root_data
+                            .borrow_mut()
+                            .add_choices_file(position, &param.choices_file);
                         root_data.borrow_mut().add_param_fn(
                             position,
                             &param.default_fn,
                             &param.choices_fn,
+                            &param.validate_fn,
                         );
                     }
+                    if let Some(last) = cmd.positional_params.last().filter(|v| v.greedy) {
+                        bail!(
+                            "{}(line {}) is unexpected after the greedy arg '{}~*'",
+                            param.tag_name(),
+                            position,
+                            last.name
+                        )
+                    }
                     cmd.names_checker.check_flag_option(&param, position)?;
-                    cmd.flag_option_params.push(param);
+                    cmd.param_slots
+                        .push(ParamSlot::FlagOption(cmd.flag_option_params.len()));
+                    cmd.flag_option_params.push(*param);
                 }
                 EventData::Positional(param) => {
                     let cmd = Self::get_cmd(&mut root_cmd, param.tag_name(), position)?;
+                    if param.glob && !param.multiple {
+                        bail!(
+                            "@arg(line {}) can't use expand:glob, it takes a single value",
+                            position
+                        )
+                    }
+                    check_default_range("@arg", &param.default, &param.range, position)?;
+                    if param.glob && param.value_name.is_none() {
+                        bail!(
+                            "@arg(line {}) can't use expand:glob without a value notation like <FILE>",
+                            position
+                        )
+                    }
+                    root_data
+                        .borrow_mut()
+                        .add_choices_file(position, &param.choices_file);
                     root_data.borrow_mut().add_param_fn(
                         position,
                         &param.default_fn,
                         &param.choices_fn,
+                        &param.validate_fn,
                     );
-                    cmd.add_positional_param(param, position)?;
+                    cmd.add_positional_param(*param, position)?;
                 }
                 EventData::Func(name) => {
                     if let Some(pos) = root_data.borrow_mut().cmd_fns.get(&name) {
@@ -176,6 +729,15 @@ impl Command {
                         )
                     }
                     root_data.borrow_mut().fns.insert(name.clone(), position);
+                    let is_cmd_fn = root_data.borrow().scope == EventScope::CmdStart;
+                    if let Some(eval_pos) = root_cmd.eval_lines.first() {
+                        if name == "main" && !is_cmd_fn {
+                            root_data.borrow_mut().warnings.push(format!(
+                                "main(line {}) comes after the eval at line {}, so it is not defined yet when argc calls it",
+                                position, eval_pos
+                            ));
+                        }
+                    }
                     if root_data.borrow().scope == EventScope::CmdStart {
                         root_data
                             .borrow_mut()
@@ -184,24 +746,25 @@ impl Command {
 
                         let parts: Vec<&str> = name.split("::").collect();
                         let parts_len = parts.len();
-                        if parts_len == 0 {
-                            bail!("{}(line {}) invalid command name", name, position);
-                        } else if parts_len == 1 {
+                        for part in parts.iter() {
+                            check_cmd_name(&name, position, part)?;
+                        }
+                        if parts_len == 1 {
                             let cmd = root_cmd.subcommands.last_mut().unwrap();
                             cmd.name = Some(parts[0].to_string());
                             cmd.fn_name = Some(name.to_string());
-                            for name in &cmd.aliases {
+                            for (name, _, alias_pos) in &cmd.aliases {
                                 if let Some(pos) = root_data.borrow().cmd_fns.get(name) {
                                     bail!(
 										"@alias(line {}) is conflicted with cmd or alias at line {}",
-										cmd.alias_pos,
+										alias_pos,
 										pos
 									);
                                 }
                                 root_data
                                     .borrow_mut()
                                     .cmd_fns
-                                    .insert(name.clone(), cmd.alias_pos);
+                                    .insert(name.clone(), *alias_pos);
                             }
                         } else {
                             let mut cmd = root_cmd.subcommands.pop().unwrap();
@@ -210,20 +773,26 @@ impl Command {
                             cmd.fn_name = Some(name.to_string());
                             match retrive_cmd(&mut root_cmd, parents) {
                                 Some(parent_cmd) => {
+                                    if let Some(pos) = parent_cmd.subcommand_fns.get(*child) {
+                                        bail!(
+                                            "{}(line {}) is conflicted with cmd or alias at line {}",
+                                            name,
+                                            position,
+                                            pos
+                                        )
+                                    }
                                     parent_cmd
                                         .subcommand_fns
                                         .insert(child.to_string(), position);
-                                    for name in &cmd.aliases {
+                                    for (name, _, alias_pos) in &cmd.aliases {
                                         if let Some(pos) = parent_cmd.subcommand_fns.get(name) {
                                             bail!(
 												"@alias(line {}) is conflicted with cmd or alias at line {}",
-												cmd.alias_pos,
+												alias_pos,
 												pos
 											);
                                         }
-                                        parent_cmd
-                                            .subcommand_fns
-                                            .insert(name.clone(), cmd.alias_pos);
+                                        parent_cmd.subcommand_fns.insert(name.clone(), *alias_pos);
                                     }
                                     parent_cmd.subcommands.push(cmd);
                                 }
@@ -238,13 +807,85 @@ impl Command {
                 EventData::Unknown(name) => {
                     bail!("@{}(line {}) is unknown", name, position);
                 }
+                EventData::Eval => {
+                    if let Some(eval_pos) = root_cmd.eval_lines.first() {
+                        root_data.borrow_mut().warnings.push(format!(
+                            "eval(line {}) parses the args again after the eval at line {}",
+                            position, eval_pos
+                        ));
+                    }
+                    root_cmd.eval_lines.push(position);
+                }
+                EventData::Warning(message) => {
+                    root_data.borrow_mut().warnings.push(message);
+                }
             }
         }
+        if root_data.borrow().scope == EventScope::CmdStart {
+            bail!(
+                "@cmd(line {}) is not attached to any function",
+                root_data.borrow().cmd_pos
+            )
+        }
+        if let (Some(arg_pos), Some(cmd_pos)) = (root_cmd.positional_pos.first(), first_cmd_pos) {
+            bail!(
+                "@arg(line {}) is conflicted with @cmd(line {}), root positionals can't be used together with subcommands",
+                arg_pos,
+                cmd_pos
+            )
+        }
+        root_cmd.check_positionals()?;
         root_cmd.root.borrow().check_param_fn()?;
         Ok(root_cmd)
     }
 
-    pub(crate) fn render_help(&self, cmd_paths: &[&str], term_width: Option<usize>) -> String {
+    /// Required positionals come before optional ones and only the last one takes many values.
+    /// Two `*` args split by `--` and `src* dest!` (the trailing ones are filled first) still work
+    fn check_positionals(&self) -> Result<()> {
+        let params = &self.positional_params;
+        let positions = &self.positional_pos;
+        let split_by_dashdash = params.len() == 2 && params.iter().all(|v| v.multiple);
+        for (i, param) in params.iter().enumerate() {
+            if param.required {
+                if let Some(j) = params[..i]
+                    .iter()
+                    .position(|v| !v.required && (!v.multiple || param.multiple))
+                {
+                    bail!(
+                        "@arg(line {}) is required but follows the optional @arg(line {})",
+                        positions[i],
+                        positions[j]
+                    )
+                }
+            }
+            if split_by_dashdash {
+                continue;
+            }
+            if let Some(j) = params[..i].iter().position(|v| v.multiple) {
+                if param.multiple || !param.required {
+                    bail!(
+                        "@arg(line {}) is unexpected after the multiple @arg(line {}), only the last arg can take many values",
+                        positions[i],
+                        positions[j]
+                    )
+                }
+            }
+        }
+        for subcmd in self.subcommands.iter() {
+            subcmd.check_positionals()?;
+        }
+        Ok(())
+    }
+
+    /// `parents` are the commands above this one, from the root, whose `global` options apply
+    pub(crate) fn render_help(
+        &self,
+        cmd_paths: &[&str],
+        parents: &[&Command],
+        term_width: Option<usize>,
+        annotate: bool,
+        preserve_order: bool,
+    ) -> String {
         let mut output = vec![];
         if self.version.is_some() {
             output.push(self.render_version(cmd_paths));
@@ -252,17 +893,22 @@ impl Command {
         if let Some(author) = &self.author {
             output.push(author.to_string());
         }
-        if !&self.describe.is_empty() {
-            output.push(wrap_render_block("", &self.describe, term_width));
+        let describe = render_plain_text(self.describe());
+        if !describe.is_empty() {
+            output.push(wrap_render_block("", &describe, term_width));
         }
         if !output.is_empty() {
             output.push(String::new());
         }
         output.push(self.render_usage(cmd_paths));
         output.push(String::new());
-        output.extend(self.render_positionals(term_width));
-        output.extend(self.render_flag_options(term_width));
+        if !preserve_order {
+            output.extend(self.render_positionals(term_width, annotate));
+        }
+        output.extend(self.render_flag_options(term_width, annotate, preserve_order));
+        output.extend(self.render_global_options(parents, term_width, annotate));
         output.extend(self.render_subcommands(term_width));
+        output.extend(self.render_outputs(term_width));
         if output.is_empty() {
             return "\n".to_string();
         }
@@ -270,23 +916,52 @@ impl Command {
     }
 
     pub(crate) fn render_version(&self, cmd_paths: &[&str]) -> String {
-        format!(
-            "{} {}",
-            cmd_paths.join("-"),
-            self.version.clone().unwrap_or_else(|| "0.0.0".to_string())
-        )
+        let version = match self.version_expansion() {
+            Some(expansion) => expansion,
+            None => self.version.clone().unwrap_or_else(|| "0.0.0".to_string()),
+        };
+        format!("{} {}", cmd_paths.join("-"), version)
+    }
+
+    /// Shell expansion for `@version ${VAR}` or `@version ${VAR:-1.0.0}`, resolved when printed
+    pub(crate) fn version_expansion(&self) -> Option<String> {
+        let value = self
+            .version
+            .as_ref()?
+            .strip_prefix("${")?
+            .strip_suffix('}')?;
+        let (name, fallback) = match value.split_once(":-") {
+            Some((name, fallback)) => (name, fallback),
+            None => (value, "unknown"),
+        };
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            || fallback.contains(['$', '`', '\\', '}'])
+        {
+            return None;
+        }
+        Some(format!("${{{name}:-{fallback}}}"))
     }
 
     pub(crate) fn render_usage(&self, cmd_paths: &[&str]) -> String {
+        // Only the command's own `@meta usage`, a parent's doesn't describe its subcommands
+        if let Some(usage) = self.get_metadata("usage") {
+            return format!("USAGE: {usage}");
+        }
         let mut output = vec!["USAGE:".to_string()];
         output.extend(cmd_paths.iter().map(|v| v.to_string()));
-        let required_options: Vec<String> = self
+        let params: Vec<&FlagOptionParam> = self
             .flag_option_params
+            .iter()
+            .filter(|v| !v.env_only)
+            .collect();
+        let required_options: Vec<String> = params
             .iter()
             .filter(|v| v.required)
             .map(|v| v.render_name_values())
             .collect();
-        if self.flag_option_params.len() != required_options.len() {
+        if params.len() != required_options.len() {
             output.push("[OPTIONS]".to_string());
         }
         output.extend(required_options);
@@ -297,7 +972,11 @@ impl Command {
         output.join(" ")
     }
 
-    pub(crate) fn render_positionals(&self, term_width: Option<usize>) -> Vec<String> {
+    pub(crate) fn render_positionals(
+        &self,
+        term_width: Option<usize>,
+        annotate: bool,
+    ) -> Vec<String> {
         let mut output = vec![];
         if self.positional_params.is_empty() {
             return output;
@@ -306,8 +985,8 @@ impl Command {
         let mut value_size = 0;
         for param in self.positional_params.iter() {
             let value = param.render_value();
-            value_size = value_size.max(value.len());
-            list.push((value, param.render_describe()));
+            value_size = value_size.max(display_width(&value));
+            list.push((value, param.render_describe(annotate)));
         }
         output.push("ARGS:".to_string());
         value_size += 2;
@@ -315,7 +994,7 @@ impl Command {
             if describe.is_empty() {
                 output.push(format!("  {value}"));
             } else {
-                let spaces = " ".repeat(value_size - value.len());
+                let spaces = " ".repeat(value_size - display_width(&value));
                 output.push(wrap_render_block(
                     &format!("  {value}{spaces}"),
                     &describe,
@@ -327,34 +1006,127 @@ impl Command {
         output
     }
 
-    pub(crate) fn render_flag_options(&self, term_width: Option<usize>) -> Vec<String> {
+    /// With `with_positionals`, positionals are listed among the flags and options in script order
+    pub(crate) fn render_flag_options(
+        &self,
+        term_width: Option<usize>,
+        annotate: bool,
+        with_positionals: bool,
+    ) -> Vec<String> {
         let mut output = vec![];
         if self.flag_option_params.is_empty() {
+            if with_positionals {
+                return self.render_positionals(term_width, annotate);
+            }
             return output;
         }
         let mut list = vec![];
+        let mut groups: IndexMap<&str, Vec<(String, String)>> = IndexMap::new();
         let mut any_describe = false;
         let mut double_dash = true;
-        for param in self.flag_option_params.iter() {
+        for slot in self.param_slots.iter() {
+            let param = match *slot {
+                ParamSlot::FlagOption(idx) => &self.flag_option_params[idx],
+                ParamSlot::Positional(idx) => {
+                    if with_positionals {
+                        let param = &self.positional_params[idx];
+                        let describe = param.render_describe(annotate);
+                        if !describe.is_empty() {
+                            any_describe = true;
+                        }
+                        list.push((param.render_value(), describe));
+                    }
+                    continue;
+                }
+            };
+            if param.env_only {
+                continue;
+            }
             if param.dashes == "-" {
                 double_dash = false;
             }
             let value = param.render_body();
-            let describe = param.render_describe();
+            let describe = param.render_describe(annotate);
             if !describe.is_empty() {
                 any_describe = true;
             }
-            list.push((value, describe));
+            match &param.group {
+                Some(group) => groups.entry(group).or_default().push((value, describe)),
+                None => list.push((value, describe)),
+            }
         }
         self.add_help_flag(&mut list, double_dash, any_describe);
         self.add_version_flag(&mut list, double_dash, any_describe);
-        output.push("OPTIONS:".to_string());
-        let value_size = list.iter().map(|v| v.0.len()).max().unwrap_or_default() + 2;
+        let value_size = list
+            .iter()
+            .chain(groups.values().flatten())
+            .map(|v| display_width(&v.0))
+            .max()
+            .unwrap_or_default()
+            + 2;
+        let heading = if with_positionals && !self.positional_params.is_empty() {
+            "ARGS AND OPTIONS"
+        } else {
+            "OPTIONS"
+        };
+        let sections = std::iter::once((heading, list)).chain(groups);
+        for (heading, list) in sections {
+            output.push(format!("{heading}:"));
+            for (value, describe) in list {
+                if describe.is_empty() {
+                    output.push(format!("  {value}"));
+                } else {
+                    let spaces = " ".repeat(value_size - display_width(&value));
+                    output.push(wrap_render_block(
+                        &format!("  {value}{spaces}"),
+                        &describe,
+                        term_width,
+                    ));
+                }
+            }
+            output.push("".to_string());
+        }
+        output
+    }
+
+    /// The `global` options of `parents` that this command doesn't shadow
+    pub(crate) fn render_global_options(
+        &self,
+        parents: &[&Command],
+        term_width: Option<usize>,
+        annotate: bool,
+    ) -> Vec<String> {
+        let mut output = vec![];
+        let mut list = vec![];
+        for (i, parent) in parents.iter().enumerate() {
+            for param in parent.flag_option_params.iter() {
+                if !param.global || param.env_only {
+                    continue;
+                }
+                let shadowed = parents[i + 1..]
+                    .iter()
+                    .chain(std::iter::once(&self))
+                    .any(|cmd| cmd.flag_option_params.iter().any(|v| v.name == param.name));
+                if !shadowed {
+                    list.push((param.render_body(), param.render_describe(annotate)));
+                }
+            }
+        }
+        if list.is_empty() {
+            return output;
+        }
+        let value_size = list
+            .iter()
+            .map(|v| display_width(&v.0))
+            .max()
+            .unwrap_or_default()
+            + 2;
+        output.push("GLOBAL OPTIONS:".to_string());
         for (value, describe) in list {
             if describe.is_empty() {
                 output.push(format!("  {value}"));
             } else {
-                let spaces = " ".repeat(value_size - value.len());
+                let spaces = " ".repeat(value_size - display_width(&value));
                 output.push(wrap_render_block(
                     &format!("  {value}{spaces}"),
                     &describe,
@@ -372,68 +1144,144 @@ impl Command {
             return output;
         }
         let mut list = vec![];
+        let mut groups: IndexMap<&str, Vec<(String, String)>> = IndexMap::new();
         let mut value_size = 0;
-        for cmd in self.subcommands.iter() {
-            let value = cmd.name.clone().unwrap_or_default();
-            let describe = cmd.render_subcommand_describe();
-            value_size = value_size.max(value.len());
-            list.push((value, describe));
+        for cmd in self.subcommands.iter().filter(|v| !v.is_main()) {
+            let value = cmd.render_subcommand_names();
+            let describe = render_plain_text(cmd.describe_head());
+            value_size = value_size.max(display_width(&value));
+            match &cmd.group {
+                Some(group) => groups.entry(group).or_default().push((value, describe)),
+                None => list.push((value, describe)),
+            }
         }
-        output.push("COMMANDS:".to_string());
         value_size += 2;
-        for (value, describe) in list {
-            if describe.is_empty() {
-                output.push(format!("  {value}"));
-            } else {
-                let spaces = " ".repeat(value_size - value.len());
-                output.push(wrap_render_block(
-                    &format!("  {value}{spaces}"),
-                    &describe,
-                    term_width,
-                ));
+        let sections = std::iter::once(("COMMANDS", list))
+            .chain(groups)
+            .filter(|(_, list)| !list.is_empty());
+        for (heading, list) in sections {
+            output.push(format!("{heading}:"));
+            for (value, describe) in list {
+                if describe.is_empty() {
+                    output.push(format!("  {value}"));
+                } else {
+                    let spaces = " ".repeat(value_size - display_width(&value));
+                    output.push(wrap_render_block(
+                        &format!("  {value}{spaces}"),
+                        &describe,
+                        term_width,
+                    ));
+                }
             }
+            output.push("".to_string());
         }
-        output.push("".to_string());
         output
     }
 
-    pub(crate) fn render_subcommand_describe(&self) -> String {
-        let mut output = self.describe_head().to_string();
-        if self.aliases.is_empty() {
+    pub(crate) fn render_outputs(&self, term_width: Option<usize>) -> Vec<String> {
+        let mut output = vec![];
+        if self.outputs.is_empty() {
             return output;
-        } else {
-            if !output.is_empty() {
-                output.push(' ')
-            }
-            output.push_str(&format!("[aliases: {}]", self.aliases.join(", ")));
-        };
+        }
+        output.push("OUTPUT:".to_string());
+        for text in self.outputs.iter() {
+            output.push(wrap_render_block(
+                "  ",
+                &render_plain_text(text),
+                term_width,
+            ));
+        }
+        output.push("".to_string());
         output
     }
 
+    pub(crate) fn render_subcommand_names(&self) -> String {
+        let mut names = vec![self.name.clone().unwrap_or_default()];
+        names.extend(self.list_visible_aliases());
+        names.join(", ")
+    }
+
+    pub(crate) fn render_dot(&self, cmd_paths: &[&str], output: &mut Vec<String>) {
+        let id = cmd_paths.join(" ");
+        let mut label = id.clone();
+        let options_len = self.flag_option_params.len();
+        let positionals_len = self.positional_params.len();
+        if options_len > 0 || positionals_len > 0 {
+            label.push_str(&format!(
+                "\\n{options_len} options, {positionals_len} positionals"
+            ));
+        }
+        output.push(format!(
+            "  {} [label={}];",
+            dot_quote(&id),
+            dot_quote(&label)
+        ));
+        let mut subcommands: Vec<&Command> = self.subcommands.iter().collect();
+        subcommands.sort_by(|a, b| a.name.cmp(&b.name));
+        for subcmd in subcommands {
+            let name = subcmd.name.clone().unwrap_or_default();
+            let mut child_paths = cmd_paths.to_vec();
+            child_paths.push(&name);
+            output.push(format!(
+                "  {} -> {};",
+                dot_quote(&id),
+                dot_quote(&child_paths.join(" "))
+            ));
+            subcmd.render_dot(&child_paths, output);
+        }
+    }
+
+    pub(crate) fn describe(&self) -> &str {
+        localize(&self.describe, &self.describe_locales)
+    }
+
     pub(crate) fn describe_head(&self) -> &str {
-        match self.describe.split_once('\n') {
+        let describe = self.describe();
+        match describe.split_once('\n') {
             Some((v, _)) => v,
-            None => self.describe.as_str(),
+            None => describe,
         }
     }
 
+    /// The `main` function is the handler of its parent command, not a subcommand
+    pub(crate) fn is_main(&self) -> bool {
+        self.name.as_deref() == Some("main")
+    }
+
+    /// All names of the command, including completion-only aliases
     pub(crate) fn list_names(&self) -> Vec<String> {
         let mut output = vec![self.name.clone().unwrap_or_default()];
-        output.extend(self.aliases.to_vec());
+        output.extend(self.aliases.iter().map(|(v, _, _)| v.clone()));
         output
     }
 
+    pub(crate) fn list_visible_aliases(&self) -> Vec<String> {
+        self.aliases
+            .iter()
+            .filter(|(_, visible, _)| *visible)
+            .map(|(v, _, _)| v.clone())
+            .collect()
+    }
+
     pub(crate) fn list_subcommand_names(&self) -> Vec<String> {
         let mut output = vec![];
         for subcmd in self.subcommands.iter() {
             if let Some(name) = subcmd.name.clone() {
                 output.push(name);
             }
-            output.extend(subcmd.aliases.to_vec());
+            output.extend(subcmd.list_visible_aliases());
         }
         output
     }
 
+    /// Returns the canonical name of the subcommand if `name` is one of its aliases
+    pub(crate) fn resolve_subcommand_alias(&self, name: &str) -> Option<&str> {
+        self.subcommands
+            .iter()
+            .find(|subcmd| subcmd.aliases.iter().any(|(v, _, _)| v == name))
+            .and_then(|subcmd| subcmd.name.as_deref())
+    }
+
     pub(crate) fn find_subcommand(&self, name: &str) -> Option<&Self> {
         self.subcommands.iter().find(|subcmd| {
             if let Some(subcmd_name) = &subcmd.name {
@@ -441,10 +1289,27 @@ impl Command {
                     return true;
                 }
             }
-            return subcmd.aliases.iter().any(|v| v == name);
+            subcmd.aliases.iter().any(|(v, _, _)| v == name)
         })
     }
 
+    /// Function from `@meta notation-complete` that completes values of the notation
+    pub(crate) fn get_notation_complete_fn(&self, value_name: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .filter(|(k, _, _)| k == "notation-complete")
+            .filter_map(|(_, v, _)| parse_notation_complete(v))
+            .find(|(notation, _)| to_cobol_case(notation) == value_name)
+            .map(|(_, fn_name)| fn_name)
+    }
+
+    pub(crate) fn get_metadata(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _, _)| k == key)
+            .map(|(_, v, _)| v.as_str())
+    }
+
     pub(crate) fn find_flag_option(&self, name: &str) -> Option<&FlagOptionParam> {
         self.flag_option_params
             .iter()
@@ -491,6 +1356,17 @@ impl Command {
         }
     }
 
+    pub(crate) fn get_fallback_fn(&self, cmd_paths: &[&str]) -> Option<String> {
+        let mut parts: Vec<&str> = cmd_paths.iter().skip(1).copied().collect();
+        parts.push("_argc_fallback");
+        let name = parts.join("::");
+        if self.root.borrow().fns.contains_key(&name) {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
     pub(crate) fn exist_main_fn(&self, cmd_paths: &[&str]) -> bool {
         self.get_cmd_fn(cmd_paths)
             .map(|v| v.ends_with("main"))
@@ -498,7 +1374,19 @@ impl Command {
     }
 
     fn add_positional_param(&mut self, param: PositionalParam, pos: Position) -> Result<()> {
+        if let Some(last) = self.positional_params.last() {
+            if last.terminated {
+                bail!(
+                    "@arg(line {}) is unexpected after the trailing arg '{}{}'",
+                    pos,
+                    last.name,
+                    if last.greedy { "~*" } else { "~" }
+                )
+            }
+        }
         self.names_checker.check_positional(&param, pos)?;
+        self.param_slots
+            .push(ParamSlot::Positional(self.positional_params.len()));
         self.positional_params.push(param);
         self.positional_pos.push(pos);
         Ok(())
@@ -506,6 +1394,15 @@ impl Command {
 
     fn get_cmd<'a>(cmd: &'a mut Self, tag_name: &str, position: usize) -> Result<&'a mut Self> {
         if cmd.root.borrow().scope == EventScope::FnEnd {
+            let root = cmd.root.borrow();
+            let index = root.anchors.partition_point(|(pos, _)| *pos <= position);
+            if !matches!(root.anchors.get(index), Some((_, true))) {
+                bail!(
+                    "{}(line {}) is not attached to any function",
+                    tag_name,
+                    position
+                )
+            }
             bail!(
                 "{}(line {}) is unexpected, maybe miss @cmd?",
                 tag_name,
@@ -581,6 +1478,51 @@ impl Command {
     }
 }
 
+/// Reject a variant in the language that `@meta lang` gives to the text without `[lang]`
+fn check_default_lang(
+    root_cmd: &Command,
+    tag_name: &str,
+    locale: &str,
+    position: Position,
+) -> Result<()> {
+    if let Some(lang) = root_cmd.get_metadata("lang") {
+        if lang
+            .replace('_', "-")
+            .eq_ignore_ascii_case(&locale.replace('_', "-"))
+        {
+            bail!(
+                "{}(line {}) is in '{}', the language of the default text set by @meta lang",
+                tag_name,
+                position,
+                lang
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reject a default like `=20[1..10]` that the range would reject when used
+fn check_default_range(
+    tag_name: &str,
+    default: &Option<String>,
+    range: &Option<(i64, i64)>,
+    position: Position,
+) -> Result<()> {
+    if let (Some(default), Some(range)) = (default, range) {
+        if !in_range(range, default) {
+            bail!(
+                "{}(line {}) has default '{}' outside its range {}..{}",
+                tag_name,
+                position,
+                default,
+                range.0,
+                range.1
+            );
+        }
+    }
+    Ok(())
+}
+
 fn retrive_cmd<'a>(cmd: &'a mut Command, cmd_paths: &[&str]) -> Option<&'a mut Command> {
     if cmd_paths.is_empty() {
         return Some(cmd);
@@ -592,9 +1534,14 @@ fn retrive_cmd<'a>(cmd: &'a mut Command, cmd_paths: &[&str]) -> Option<&'a mut C
     retrive_cmd(child, &cmd_paths[1..])
 }
 
+fn dot_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
 fn wrap_render_block(name: &str, describe: &str, term_width: Option<usize>) -> String {
-    let size = term_width.unwrap_or(999) - name.len();
-    let empty = " ".repeat(name.len());
+    let width = display_width(name);
+    let size = term_width.unwrap_or(999).saturating_sub(width).max(1);
+    let empty = " ".repeat(width);
     describe
         .split('\n')
         .flat_map(|v| textwrap::wrap(v, size))
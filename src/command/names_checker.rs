@@ -1,13 +1,55 @@
 use crate::param::{FlagOptionParam, PositionalParam};
 use crate::parser::Position;
+use crate::utils::to_var_name;
 
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 
+/// Variables argc sets itself, e.g. `argc__fn`
+const RESERVED_VAR_NAMES: [&str; 5] = ["_args", "_fn", "_words", "_dashdash", "_unknown_args"];
+
+/// Built-in flags a `@flag` may override, but an option can't take over
+const BUILTIN_FLAG_NAMES: [&str; 2] = ["help", "version"];
+
+/// Characters a command name may have besides letters and digits, others need quoting on the
+/// command line or are glob and history characters
+const CMD_NAME_CHARS: [char; 6] = ['_', '-', '.', ':', '@', '+'];
+
+/// Check a command name from a `@cmd` function, `part` is one `::` separated piece of `fn_name`
+pub(crate) fn check_cmd_name(fn_name: &str, pos: Position, part: &str) -> Result<()> {
+    if part.is_empty() {
+        bail!("{}(line {}) has an empty command name", fn_name, pos);
+    }
+    if part.starts_with('-') {
+        bail!(
+            "{}(line {}) has command name '{}' that looks like an option",
+            fn_name,
+            pos,
+            part
+        );
+    }
+    if let Some(c) = part
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !CMD_NAME_CHARS.contains(c))
+    {
+        bail!(
+            "{}(line {}) has command name '{}' with '{}', only letters, digits and {} are allowed",
+            fn_name,
+            pos,
+            part,
+            c,
+            CMD_NAME_CHARS.map(|v| v.to_string()).join(" ")
+        );
+    }
+    Ok(())
+}
+
 #[derive(Debug, Default, Clone)]
 pub(crate) struct NamesChecker {
     pub(crate) flag_options: HashMap<String, (Position, String)>,
     pub(crate) positionals: HashMap<String, Position>,
+    /// Param names by the variable they set, `--foo.bar` and `--foo-bar` both set `argc_foo_bar`
+    pub(crate) var_names: HashMap<String, (Position, String)>,
 }
 
 impl NamesChecker {
@@ -17,7 +59,18 @@ impl NamesChecker {
         pos: Position,
     ) -> Result<()> {
         let tag_name = param.tag_name();
-        let names = param.list_names();
+        Self::check_var_name(tag_name, pos, &param.name)?;
+        self.check_var_name_conflict(tag_name, pos, &param.name)?;
+        if !param.is_flag() && BUILTIN_FLAG_NAMES.contains(&param.name.as_str()) {
+            bail!(
+                "{}(line {}) has '{}' that collides with the built-in --{} flag, use @flag to override it",
+                tag_name,
+                pos,
+                param.name,
+                param.name
+            );
+        }
+        let names = param.list_all_names();
         for name in names.iter() {
             if let Some((exist_pos, _)) = self.flag_options.get(name) {
                 bail!("{}", Self::conflict_error(tag_name, pos, name, *exist_pos));
@@ -34,16 +87,52 @@ impl NamesChecker {
         pos: Position,
     ) -> Result<()> {
         let name = &param.name;
+        Self::check_var_name(param.tag_name(), pos, name)?;
         if let Some(exist_pos) = self.positionals.get(name) {
             bail!(
                 "{}",
                 Self::conflict_error(param.tag_name(), pos, name, *exist_pos)
             );
         }
+        self.check_var_name_conflict(param.tag_name(), pos, name)?;
         self.positionals.insert(name.to_string(), pos);
         Ok(())
     }
 
+    fn check_var_name(tag_name: &str, pos: Position, name: &str) -> Result<()> {
+        let var_name = to_var_name(name);
+        if RESERVED_VAR_NAMES.contains(&var_name.as_str()) {
+            bail!(
+                "{}(line {}) has '{}' that collides with the argc_{} variable",
+                tag_name,
+                pos,
+                name,
+                var_name
+            );
+        }
+        Ok(())
+    }
+
+    /// Reject a different name that sets the same variable as an earlier param
+    fn check_var_name_conflict(&mut self, tag_name: &str, pos: Position, name: &str) -> Result<()> {
+        let var_name = to_var_name(name);
+        if let Some((exist_pos, exist_name)) = self.var_names.get(&var_name) {
+            if exist_name != name {
+                bail!(
+                    "{}(line {}) has '{}' that sets the argc_{} variable like '{}' at line {}",
+                    tag_name,
+                    pos,
+                    name,
+                    var_name,
+                    exist_name,
+                    exist_pos
+                );
+            }
+        }
+        self.var_names.insert(var_name, (pos, name.to_string()));
+        Ok(())
+    }
+
     fn conflict_error(
         tag_name: &str,
         pos: Position,
@@ -1,7 +1,7 @@
 use crate::parser::{EventScope, Position};
 
 use anyhow::{bail, Result};
-use std::collections::HashMap;
+use std::{collections::HashMap, fs, path::Path};
 
 #[derive(Default)]
 pub(crate) struct RootData {
@@ -9,8 +9,14 @@ pub(crate) struct RootData {
     pub(crate) fns: HashMap<String, Position>,
     pub(crate) cmd_fns: HashMap<String, Position>,
     pub(crate) cmd_pos: usize,
+    /// Positions of `@cmd` (false) and function (true) events, in script order
+    pub(crate) anchors: Vec<(Position, bool)>,
     pub(crate) default_fns: Vec<(String, Position)>,
     pub(crate) choices_fns: Vec<(String, Position)>,
+    pub(crate) validate_fns: Vec<(String, Position)>,
+    pub(crate) terminal_fns: Vec<(String, Position)>,
+    pub(crate) choices_files: Vec<(String, Position)>,
+    pub(crate) warnings: Vec<String>,
 }
 
 impl RootData {
@@ -19,6 +25,7 @@ impl RootData {
         position: usize,
         default_fn: &Option<String>,
         choices_fn: &Option<(String, bool)>,
+        validate_fn: &Option<String>,
     ) {
         if let Some(default_fn) = default_fn.as_ref() {
             self.default_fns.push((default_fn.to_string(), position));
@@ -26,6 +33,57 @@ impl RootData {
         if let Some((choices_fn, _)) = choices_fn.as_ref() {
             self.choices_fns.push((choices_fn.to_string(), position));
         }
+        if let Some(validate_fn) = validate_fn.as_ref() {
+            self.validate_fns.push((validate_fn.to_string(), position));
+        }
+    }
+
+    pub(crate) fn add_terminal_fn(&mut self, position: usize, terminal_fn: &Option<String>) {
+        if let Some(terminal_fn) = terminal_fn.as_ref() {
+            self.terminal_fns.push((terminal_fn.to_string(), position));
+        }
+    }
+
+    pub(crate) fn add_choices_file(&mut self, position: usize, choices_file: &Option<String>) {
+        if let Some(choices_file) = choices_file.as_ref() {
+            self.choices_files
+                .push((choices_file.to_string(), position));
+        }
+    }
+
+    /// Read each choices file once, paths are relative to the script
+    pub(crate) fn load_choices_files(
+        &self,
+        script_path: Option<&str>,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut output = HashMap::new();
+        let Some((file, pos)) = self.choices_files.first() else {
+            return Ok(output);
+        };
+        let Some(base_dir) = script_path.and_then(|v| Path::new(v).parent()) else {
+            bail!(
+                "{}(line {}) is relative to the script, which has no path",
+                file,
+                pos
+            )
+        };
+        for (file, pos) in self.choices_files.iter() {
+            if output.contains_key(file) {
+                continue;
+            }
+            let content = match fs::read_to_string(base_dir.join(file)) {
+                Ok(v) => v,
+                Err(_) => bail!("{}(line {}) is missing", file, pos),
+            };
+            let choices: Vec<String> = content
+                .lines()
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty() && !v.starts_with('#'))
+                .map(|v| v.to_string())
+                .collect();
+            output.insert(file.clone(), choices);
+        }
+        Ok(output)
     }
 
     pub(crate) fn check_param_fn(&self) -> Result<()> {
@@ -39,6 +97,16 @@ impl RootData {
                 bail!("{}(line {}) is missing", name, pos,)
             }
         }
+        for (name, pos) in self.validate_fns.iter() {
+            if !self.fns.contains_key(name) {
+                bail!("{}(line {}) is missing", name, pos,)
+            }
+        }
+        for (name, pos) in self.terminal_fns.iter() {
+            if !self.fns.contains_key(name) {
+                bail!("{}(line {}) is missing", name, pos,)
+            }
+        }
         Ok(())
     }
 
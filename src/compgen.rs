@@ -17,7 +17,7 @@ pub fn compgen(
         return Ok(String::new());
     }
     let (mut last, _) = unbalance_quote(&args[args.len() - 1]);
-    let cmd = Command::new(script_content)?;
+    let cmd = Command::new_lazy(script_content, Some(script_path), args)?;
     let args: Vec<String> = args
         .iter()
         .enumerate()
@@ -58,10 +58,14 @@ pub fn compgen(
         let mut envs = HashMap::new();
         let with_description = shell.with_description();
         envs.insert("ARGC_DESCRIBE".into(), with_description.to_string());
+        envs.insert("ARGC_CWORD".into(), last.to_string());
         if let Some(cwd) = get_current_dir() {
             envs.insert("ARGC_PWD".into(), escape_shell_words(&cwd));
         }
-        if let Some(outputs) = run_param_fns(script_path, &[fn_name.as_str()], &args, envs) {
+        let meta_shell = cmd.get_metadata("shell");
+        if let Some(outputs) =
+            run_param_fns(script_path, &[fn_name.as_str()], &args, envs, meta_shell)
+        {
             for line in outputs[0]
                 .trim()
                 .split('\n')
@@ -114,7 +118,7 @@ pub fn compgen(
                 .value
                 .chars()
                 .enumerate()
-                .skip(last.len() + 1)
+                .skip(last.chars().count() + 1)
                 .find(|(_, c)| parts_chars.contains(c))
             {
                 let parted_value: String = candicate.value.chars().take(i + 1).collect();
@@ -257,12 +261,13 @@ impl Shell {
                         if i == 0 && patch_first {
                             value = format!(" {}", value);
                         };
-                        if candicate.nospace {
+                        if !candicate.nospace {
+                            value.push(' ');
+                        }
+                        if candicate.description.is_empty() || !self.with_description() {
                             value
-                        } else if candicate.description.is_empty() || !self.with_description() {
-                            format!("{value} ")
                         } else {
-                            format!("{value} ({})", candicate.truncate_description())
+                            format!("{value}\t{}", candicate.truncate_description())
                         }
                     })
                     .collect::<Vec<String>>()
@@ -446,18 +451,11 @@ fn unbalance_quote(arg: &str) -> (&str, Option<char>) {
 }
 
 fn split_equal_sign(word: &str) -> Option<(&str, &str)> {
-    let chars: Vec<char> = word
-        .chars()
-        .skip_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
-        .collect();
-    if let Some('=') = chars.first() {
-        let idx = word.len() - chars.len() + 1;
-        if idx == 1 {
-            return None;
-        }
-        return Some((&word[..idx], &word[idx..]));
+    let idx = word.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))?;
+    if idx == 0 || !word[idx..].starts_with('=') {
+        return None;
     }
-    None
+    Some((&word[..idx + 1], &word[idx + 1..]))
 }
 
 fn common_prefix(strings: &[&str]) -> Option<String> {
@@ -467,7 +465,7 @@ fn common_prefix(strings: &[&str]) -> Option<String> {
     let mut prefix = String::new();
     for (i, c) in strings[0].chars().enumerate() {
         for s in &strings[1..] {
-            if i >= s.len() || s.chars().nth(i) != Some(c) {
+            if s.chars().nth(i) != Some(c) {
                 if prefix.is_empty() {
                     return None;
                 }
@@ -518,4 +516,17 @@ mod tests {
         assert_eq!(split_equal_sign("a:"), None);
         assert_eq!(split_equal_sign("=a"), None);
     }
+
+    #[test]
+    fn test_bash_description() {
+        std::env::set_var("ARGC_COMPGEN_DESCRIPTION", "1");
+        let candicates = vec![
+            Candicate::new("--foo".into(), "Foo".into(), false),
+            Candicate::new("--foo-bar=".into(), "Foo bar".into(), true),
+            Candicate::new("--qux".into(), String::new(), false),
+        ];
+        let output = Shell::Bash.output_candicates(&candicates, "--", "", &[]);
+        std::env::remove_var("ARGC_COMPGEN_DESCRIPTION");
+        assert_eq!(output, " --foo \tFoo\n--foo-bar=\tFoo bar\n--qux ");
+    }
 }
@@ -0,0 +1,153 @@
+use crate::parser::{parse, parse_line, skip_comment_lines, EventData};
+use crate::utils::display_width;
+use crate::Result;
+
+/// A line of the script, either kept as is or a tag rendered in canonical form
+enum Line<'a> {
+    Text(&'a str),
+    /// A tag without its description, which is aligned within a block of params
+    Tag {
+        head: String,
+        describe: String,
+        param: bool,
+        original: &'a str,
+    },
+}
+
+/// Rewrite the comment tags of a script in canonical form, other lines are kept byte for byte
+pub fn format_script(source: &str) -> Result<String> {
+    parse(source)?;
+    let (bom, source) = match source.strip_prefix('\u{feff}') {
+        Some(v) => ("\u{feff}", v),
+        None => ("", source),
+    };
+    let raw_lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let contents: Vec<&str> = raw_lines
+        .iter()
+        .map(|v| v.trim_end_matches('\n').trim_end_matches('\r'))
+        .collect();
+    let mut lines: Vec<Line> = contents.iter().map(|v| Line::Text(v)).collect();
+    let mut iter = contents.iter().copied().enumerate().peekable();
+    while let Some((idx, content)) = iter.next() {
+        let Ok((_, Some(Some(data)))) = parse_line(content) else {
+            continue;
+        };
+        let takes_comments = matches!(
+            data,
+            EventData::Describe(_)
+                | EventData::Cmd(_)
+                | EventData::DescribeLocale(..)
+                | EventData::CmdLocale(..)
+                | EventData::FlagOption(_)
+                | EventData::Positional(_)
+        );
+        if let Some((head, describe, param)) = render_tag(data) {
+            lines[idx] = Line::Tag {
+                head,
+                describe,
+                param,
+                original: content,
+            };
+        }
+        if takes_comments {
+            skip_comment_lines(&mut iter);
+        }
+    }
+    let mut output = String::from(bom);
+    let mut block_width = None;
+    for (idx, line) in lines.iter().enumerate() {
+        let content = match line {
+            Line::Text(text) => {
+                block_width = None;
+                text.to_string()
+            }
+            Line::Tag {
+                head,
+                describe,
+                param,
+                original,
+            } => {
+                let width = if *param {
+                    *block_width.get_or_insert_with(|| param_block_width(&lines[idx..]))
+                } else {
+                    block_width = None;
+                    0
+                };
+                let content = if describe.is_empty() {
+                    head.clone()
+                } else {
+                    let spaces = " ".repeat(width.saturating_sub(display_width(head)) + 1);
+                    format!("{head}{spaces}{describe}")
+                };
+                // Keep the line when the canonical form would not mean the same
+                if parse_line(&content).ok().map(|v| v.1) == parse_line(original).ok().map(|v| v.1)
+                {
+                    content
+                } else {
+                    original.to_string()
+                }
+            }
+        };
+        output.push_str(&content);
+        output.push_str(&raw_lines[idx][contents[idx].len()..]);
+    }
+    Ok(output)
+}
+
+/// Head, description and whether it is a param, for a tag that has a canonical form
+fn render_tag(data: EventData) -> Option<(String, String, bool)> {
+    let text_tag = |name: &str, text: String| (format!("# @{name}"), text, false);
+    let output = match data {
+        EventData::Describe(text) => text_tag("describe", text),
+        EventData::Version(text) => text_tag("version", text),
+        EventData::Author(text) => text_tag("author", text),
+        EventData::Name(text) => text_tag("name", text),
+        EventData::Cmd(text) => text_tag("cmd", text),
+        EventData::DescribeLocale(locale, text) => text_tag(&format!("describe[{locale}]"), text),
+        EventData::CmdLocale(locale, text) => text_tag(&format!("cmd[{locale}]"), text),
+        EventData::Group(text) => text_tag("group", text),
+        EventData::Output(text) => text_tag("output", text),
+        EventData::Aliases(list) => text_tag("alias", list.join(",")),
+        EventData::CompletionAliases(list) => text_tag("completion-alias", list.join(",")),
+        EventData::Meta(key, value) => text_tag("meta", [key, value].join(" ").trim().into()),
+        EventData::FlagOption(mut param) => {
+            let describe = std::mem::take(&mut param.describe);
+            (
+                format!("# {} {}", param.tag_name(), param.render()),
+                describe,
+                true,
+            )
+        }
+        EventData::Positional(mut param) => {
+            let describe = std::mem::take(&mut param.describe);
+            (
+                format!("# {} {}", param.tag_name(), param.render()),
+                describe,
+                true,
+            )
+        }
+        _ => return None,
+    };
+    Some(output)
+}
+
+/// Width of the widest head with a description in the run of param lines that starts the slice
+fn param_block_width(lines: &[Line]) -> usize {
+    lines
+        .iter()
+        .map_while(|v| match v {
+            Line::Tag {
+                head,
+                describe,
+                param: true,
+                ..
+            } => Some(if describe.is_empty() {
+                0
+            } else {
+                display_width(head)
+            }),
+            _ => None,
+        })
+        .max()
+        .unwrap_or_default()
+}
@@ -1,14 +1,38 @@
 mod argc_value;
+mod cache;
 mod command;
 mod compgen;
+mod formatter;
 mod matcher;
+mod migrate;
 mod param;
 mod parser;
 pub mod utils;
 
 use anyhow::Error;
 pub use argc_value::ArgcValue;
-pub use command::{eval, export};
+pub use command::{
+    check, check_min_version, diff_specs, eval, eval_fn, eval_outcome, export, export_clap,
+    export_command, export_dot, extract_metadata, script_eval_lines, script_inherits_cwd,
+    script_shell, spec_error_exit_code, trace, Command, EvalOutcome, Metadata, ResolveError,
+    SpecChange, SpecChangeKind,
+};
 pub use compgen::{compgen, Shell};
+pub use formatter::format_script;
+pub use migrate::migrate_script;
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Entry points for the targets in `fuzz/`, built by `cargo fuzz` only
+#[cfg(fuzzing)]
+pub mod fuzzing {
+    pub fn parse_line(line: &str) {
+        let _ = crate::parser::parse_line(line);
+    }
+
+    pub fn parse(source: &str) {
+        let _ = crate::parser::parse(source);
+        let words: Vec<String> = source.split_whitespace().map(|v| v.to_string()).collect();
+        let _ = crate::parser::parse_lazy(source, &words);
+    }
+}
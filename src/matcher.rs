@@ -1,9 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+};
 
 use crate::{
-    command::Command,
-    param::{FlagOptionParam, PositionalParam},
-    utils::run_param_fns,
+    command::{get_exit_code, Command, ResolveError},
+    param::{
+        find_duplicate, in_range, range_sample, FlagOptionParam, PositionalParam, UniqueMode,
+        ValueSource, ValueType,
+    },
+    utils::{did_you_mean, escape_heredoc, run_param_fns, shell_has_arrays},
     ArgcValue,
 };
 
@@ -18,6 +24,7 @@ pub(crate) struct Matcher<'a, 'b> {
     flag_option_args: Vec<Vec<FlagOptionArg<'a, 'b>>>,
     positional_args: Vec<&'b str>,
     dashdash: Vec<usize>,
+    fallback_fn: Option<String>,
     arg_comp: ArgComp,
     choices_fns: HashSet<&'a str>,
     choices_values: HashMap<&'a str, Vec<String>>,
@@ -33,12 +40,15 @@ pub(crate) enum ArgComp {
     FlagOrOptionCombine(String),
     CommandOrPositional,
     OptionValue(String, usize),
+    /// After `--` or within a `~` positional, where only positional values are left
+    Terminated,
     Any,
 }
 
 #[derive(Debug)]
 pub(crate) enum MatchError {
     DisplayHelp,
+    DisplayHelpJson,
     DisplaySubcommandHelp(String),
     DisplayVersion,
     InvalidSubcommand,
@@ -46,18 +56,26 @@ pub(crate) enum MatchError {
     MissingRequiredArgument(usize, Vec<String>),
     NotMultipleArgument(usize, String),
     InvalidValue(usize, String, String, Vec<String>),
+    OutOfRange(usize, String, String, (i64, i64)),
+    InvalidType(usize, String, String, ValueType),
+    InvalidMapValue(usize, String, String),
+    DuplicateValue(usize, String, String),
     MismatchValues(usize, String),
+    MissingValue(usize, String),
     NoMoreValue(usize, String, String),
 }
 
 impl<'a, 'b> Matcher<'a, 'b> {
     pub(crate) fn new(root: &'a Command, args: &'b [String]) -> Self {
-        let mut cmds = vec![(args[0].as_str(), root, args[0].clone())];
+        let root_name = root.name.clone().unwrap_or_else(|| args[0].clone());
+        let mut cmds = vec![(args[0].as_str(), root, root_name)];
         let mut cmd_level = 0;
         let mut arg_index = 1;
         let mut flag_option_args = vec![vec![]];
         let mut positional_args = vec![];
         let mut dashdash = vec![];
+        let mut options_stopped = false;
+        let mut fallback_fn = None;
         let mut arg_comp = ArgComp::Any;
         let mut choices_fns = HashSet::new();
         let args_len = args.len();
@@ -71,15 +89,62 @@ impl<'a, 'b> Matcher<'a, 'b> {
         while arg_index < args_len {
             let cmd = cmds[cmd_level].1;
             let arg = args[arg_index].as_str();
-            if arg == "--" {
+            if options_stopped {
+                positional_args.push(arg);
+            } else if dashdash.is_empty()
+                && cmd.find_subcommand(arg).is_none()
+                && cmd
+                    .positional_params
+                    .iter()
+                    .position(|v| v.greedy)
+                    .is_some_and(|index| positional_args.len() >= index)
+            {
+                positional_args.extend(args[arg_index..].iter().map(|v| v.as_str()));
+                arg_comp = ArgComp::Terminated;
+                break;
+            } else if arg == "--" || is_args_separator(&cmds, arg) {
                 dashdash.push(positional_args.len());
-            } else if !dashdash.is_empty()
-                || (cmd.no_flags_options_subcommands() && !KNOWN_OPTIONS.contains(&arg))
+            } else if !dashdash.is_empty() {
+                if arg_index == args_len - 1 {
+                    arg_comp = ArgComp::Terminated;
+                }
+                positional_args.push(arg);
+            } else if cmd.no_flags_options_subcommands()
+                && !KNOWN_OPTIONS.contains(&arg)
+                && find_scoped_flag_option(&cmds, |v| {
+                    v.find_flag_option(arg.split_once('=').map(|v| v.0).unwrap_or(arg))
+                })
+                .is_none()
             {
+                options_stopped = !is_option_arg(arg) && is_posix_mode(&cmds);
+                if arg_index == args_len - 1
+                    && cmd
+                        .positional_params
+                        .iter()
+                        .position(|v| v.terminated || v.greedy)
+                        .is_some_and(|index| positional_args.len() > index)
+                {
+                    arg_comp = ArgComp::Terminated;
+                }
                 positional_args.push(arg);
-            } else if arg.starts_with('-') {
-                if let Some((k, v)) = arg.split_once('=') {
-                    let param = cmd.find_flag_option(k);
+            } else if let Some((level, param)) =
+                find_scoped_flag_option(&cmds, |v| find_plus_flag(v, arg))
+            {
+                flag_option_args[level].push((arg, vec![], Some(param.name.as_str())));
+            } else if is_option_arg(arg) {
+                // A cluster ending with an option like `-ao=x` is left to `match_combine_shorts`
+                if let Some((k, v)) = arg.split_once('=').filter(|(k, _)| {
+                    find_scoped_flag_option(&cmds, |v| v.find_flag_option(k)).is_some()
+                        || !match_combine_shorts(&cmds, k)
+                            .and_then(|list| list.last().map(|(level, item)| (*level, item.2)))
+                            .and_then(|(level, name)| cmds[level].1.find_flag_option(name?))
+                            .is_some_and(|param| param.is_option())
+                }) {
+                    let (level, param) =
+                        match find_scoped_flag_option(&cmds, |v| v.find_flag_option(k)) {
+                            Some((level, param)) => (level, Some(param)),
+                            None => (cmd_level, None),
+                        };
                     if arg_index == args_len - 1 {
                         if let Some(param) = param {
                             arg_comp = ArgComp::OptionValue(param.name.clone(), 0)
@@ -91,36 +156,45 @@ impl<'a, 'b> Matcher<'a, 'b> {
                             choices_fns.insert(choices_fn.as_str());
                         }
                     }
-                    flag_option_args[cmd_level].push((k, vec![v], param.map(|v| v.name.as_str())));
-                } else if let Some(param) = cmd.find_flag_option(arg) {
+                    flag_option_args[level].push((k, vec![v], param.map(|v| v.name.as_str())));
+                } else if let Some((level, param)) =
+                    find_scoped_flag_option(&cmds, |v| v.find_flag_option(arg))
+                {
                     if let Some((choices_fn, validate)) = param.choices_fn.as_ref() {
                         if *validate {
                             choices_fns.insert(choices_fn.as_str());
                         }
                     }
                     match_flag_option(
-                        &mut flag_option_args[cmd_level],
+                        &mut flag_option_args[level],
                         args,
                         &mut arg_index,
                         param,
                         &mut arg_comp,
                     );
-                } else if let Some(mut list) = match_combine_shorts(cmd, arg) {
-                    let name = list.pop().and_then(|v| v.2).unwrap();
-                    let param = cmd.find_flag_option(name).unwrap();
+                } else if let Some(mut list) = match_combine_shorts(&cmds, arg) {
+                    let (level, last) = list.pop().unwrap();
+                    let param = cmds[level].1.find_flag_option(last.2.unwrap()).unwrap();
                     if let Some((choices_fn, validate)) = param.choices_fn.as_ref() {
                         if *validate {
                             choices_fns.insert(choices_fn.as_str());
                         }
                     }
-                    flag_option_args[cmd_level].extend(list);
-                    match_flag_option(
-                        &mut flag_option_args[cmd_level],
-                        args,
-                        &mut arg_index,
-                        param,
-                        &mut arg_comp,
-                    );
+                    for (level, item) in list {
+                        flag_option_args[level].push(item);
+                    }
+                    if !last.1.is_empty() {
+                        // The option took the rest of the cluster as its value
+                        flag_option_args[level].push(last);
+                    } else {
+                        match_flag_option(
+                            &mut flag_option_args[level],
+                            args,
+                            &mut arg_index,
+                            param,
+                            &mut arg_comp,
+                        );
+                    }
                 } else {
                     flag_option_args[cmd_level].push((arg, vec![], None));
                 }
@@ -133,10 +207,29 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 ));
                 flag_option_args.push(vec![]);
             } else {
+                if positional_args.is_empty() && !cmd.subcommands.is_empty() {
+                    let cmd_paths: Vec<&str> = cmds.iter().map(|v| v.2.as_str()).collect();
+                    if let Some(name) = cmd.get_fallback_fn(&cmd_paths) {
+                        positional_args.extend(args[arg_index..].iter().map(|v| v.as_str()));
+                        fallback_fn = Some(name);
+                        break;
+                    }
+                }
+                if let Some(index) = cmd.positional_params.iter().position(|v| v.terminated) {
+                    if positional_args.len() >= index {
+                        positional_args.extend(args[arg_index..].iter().map(|v| v.as_str()));
+                        arg_comp = ArgComp::Terminated;
+                        break;
+                    }
+                }
+                options_stopped = is_posix_mode(&cmds);
                 positional_args.push(arg);
             }
             arg_index += 1;
         }
+        if options_stopped && matches!(arg_comp, ArgComp::FlagOrOption) {
+            arg_comp = ArgComp::CommandOrPositional;
+        }
         let last_cmd = cmds.last().unwrap().1;
         choices_fns.extend(last_cmd.positional_params.iter().filter_map(|v| {
             if let Some((choices_fn, validate)) = v.choices_fn.as_ref() {
@@ -152,6 +245,7 @@ impl<'a, 'b> Matcher<'a, 'b> {
             flag_option_args,
             positional_args,
             dashdash,
+            fallback_fn,
             arg_comp,
             choices_fns,
             choices_values: HashMap::new(),
@@ -163,7 +257,10 @@ impl<'a, 'b> Matcher<'a, 'b> {
     pub(crate) fn set_script_path(&mut self, script_path: &str) {
         self.script_path = Some(script_path.to_string());
         let fns: Vec<&str> = self.choices_fns.iter().copied().collect();
-        if let Some(outputs) = run_param_fns(script_path, &fns, self.args, HashMap::new()) {
+        let meta_shell = self.cmds[0].1.get_metadata("shell");
+        if let Some(outputs) =
+            run_param_fns(script_path, &fns, self.args, HashMap::new(), meta_shell)
+        {
             for (i, output) in outputs.into_iter().enumerate() {
                 let choices = output
                     .split('\n')
@@ -187,21 +284,52 @@ impl<'a, 'b> Matcher<'a, 'b> {
 
     pub(crate) fn to_arg_values(&self) -> Vec<ArgcValue> {
         if let Some(err) = self.validate() {
-            return vec![ArgcValue::Error(self.stringify_match_error(&err))];
+            let (message, exit) = self.stringify_match_error(&err);
+            if let Some(expansion) = self.get_version_expansion(&err) {
+                let message =
+                    escape_heredoc(&message).replace(&escape_heredoc(&expansion), &expansion);
+                return vec![ArgcValue::ErrorExpand((message, exit))];
+            }
+            return vec![ArgcValue::Error((message, exit))];
         }
         let (cmd, cmd_paths) = self.get_cmd_and_paths(self.cmds.len() - 1);
-        let mut output = self.to_arg_values_base();
+        let mut output = vec![];
+        if find_metadata(&self.cmds, "strict-shell").is_some() {
+            output.push(ArgcValue::StrictShell);
+        }
+        output.extend(self.to_arg_values_base());
         if cmd.positional_params.is_empty() && !self.positional_args.is_empty() {
             output.push(ArgcValue::ExtraPositionalMultiple(
                 self.positional_args.iter().map(|v| v.to_string()).collect(),
             ));
         }
-        if let Some(cmd_fn) = cmd.get_cmd_fn(&cmd_paths) {
+        let terminal = self.find_terminal_param();
+        if terminal.is_none() {
+            output.extend(self.to_validate_fn_values());
+        }
+        if let Some(cmd_fn) = terminal
+            .and_then(|v| v.terminal_fn.clone())
+            .or_else(|| self.fallback_fn.clone())
+            .or_else(|| cmd.get_cmd_fn(&cmd_paths))
+        {
+            if find_metadata(&self.cmds, "no-forward-positionals").is_some() {
+                output.push(ArgcValue::NoForwardPositionals);
+            }
             output.push(ArgcValue::CmdFn(cmd_fn));
         }
         output
     }
 
+    /// Help text when the args ask for help, for hosts that print it themselves instead of exiting
+    pub(crate) fn to_help_text(&self) -> Option<String> {
+        match self.validate()? {
+            err @ (MatchError::DisplayHelp
+            | MatchError::DisplayHelpJson
+            | MatchError::DisplaySubcommandHelp(_)) => Some(self.stringify_match_error(&err).0),
+            _ => None,
+        }
+    }
+
     pub(crate) fn to_arg_values_for_choice_fn(&self) -> Vec<ArgcValue> {
         let mut output: Vec<ArgcValue> = self.to_arg_values_base();
         if !self.dashdash.is_empty() {
@@ -218,6 +346,29 @@ impl<'a, 'b> Matcher<'a, 'b> {
     }
 
     pub(crate) fn compgen(&self) -> Vec<(String, String)> {
+        self.comp_arg()
+            .into_iter()
+            .map(|(value, describe)| {
+                let fn_name = value
+                    .strip_prefix("__argc_value:")
+                    .and_then(|name| self.find_notation_complete_fn(name));
+                match fn_name {
+                    Some(fn_name) => (format!("__argc_fn:{fn_name}"), describe),
+                    None => (value, describe),
+                }
+            })
+            .collect()
+    }
+
+    /// Function from `@meta notation-complete` of the command or its nearest ancestor
+    fn find_notation_complete_fn(&self, value_name: &str) -> Option<&str> {
+        self.cmds
+            .iter()
+            .rev()
+            .find_map(|(_, cmd, _)| cmd.get_notation_complete_fn(value_name))
+    }
+
+    fn comp_arg(&self) -> Vec<(String, String)> {
         match &self.arg_comp {
             ArgComp::FlagOrOption => self.comp_flag_options(),
             ArgComp::FlagOrOptionCombine(value) => {
@@ -253,13 +404,40 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 comp_subcommands_positional(cmd, &values, self.positional_args.len() < 2)
             }
             ArgComp::OptionValue(name, index) => {
-                let cmd = self.cmds[self.cmds.len() - 1].1;
-                if let Some(param) = cmd.flag_option_params.iter().find(|v| &v.name == name) {
+                if let Some((_, param)) = find_scoped_flag_option(&self.cmds, |cmd| {
+                    cmd.flag_option_params.iter().find(|v| &v.name == name)
+                }) {
                     comp_flag_option(param, *index)
                 } else {
                     vec![]
                 }
             }
+            ArgComp::Terminated => {
+                let cmd = self.cmds[self.cmds.len() - 1].1;
+                let values = self.match_positionals();
+                let param = values
+                    .len()
+                    .checked_sub(1)
+                    .and_then(|i| cmd.positional_params.get(i));
+                let Some(param) = param else {
+                    return vec![("__argc_value:file".into(), String::new())];
+                };
+                // Past `--` a value without its own completion is most likely a path
+                comp_positional(param)
+                    .into_iter()
+                    .map(
+                        |(value, describe)| match value.strip_prefix("__argc_value:") {
+                            Some(name)
+                                if !is_dir_value_name(name)
+                                    && self.find_notation_complete_fn(name).is_none() =>
+                            {
+                                ("__argc_value:file".into(), describe)
+                            }
+                            _ => (value, describe),
+                        },
+                    )
+                    .collect()
+            }
             ArgComp::Any => {
                 let cmd = self.cmds[self.cmds.len() - 1].1;
                 if self.positional_args.len() == 2 && self.positional_args[0] == "help" {
@@ -285,13 +463,32 @@ impl<'a, 'b> Matcher<'a, 'b> {
 
     fn to_arg_values_base(&self) -> Vec<ArgcValue> {
         let mut output = vec![];
+        if let Some(prefix) = find_metadata(&self.cmds, "var-prefix") {
+            output.push(ArgcValue::VarPrefix(prefix.to_string()));
+        }
+        if self.cmds[0]
+            .1
+            .get_metadata("shell")
+            .is_some_and(|v| !shell_has_arrays(v))
+        {
+            output.push(ArgcValue::NoArrays);
+        }
+        // Under `set -u` an unset variable is an error, so `strict-shell` always needs them
+        let set_unset = find_metadata(&self.cmds, "sparse-vars").is_none()
+            || find_metadata(&self.cmds, "strict-shell").is_some();
         let cmds_len = self.cmds.len();
         let level = cmds_len - 1;
         let last_cmd = self.cmds[level].1;
+        // Variables already emitted at an earlier level, the empty initializer must not clear them
+        let mut set_names: HashSet<&str> = HashSet::new();
         for level in 0..cmds_len {
             let args = &self.flag_option_args[level];
             let cmd = self.cmds[level].1;
             for param in cmd.flag_option_params.iter() {
+                // The subcommand's own definition sets the variable
+                if param.global && is_shadowed(&self.cmds[level + 1..], param) {
+                    continue;
+                }
                 let values: Vec<&[&str]> = args
                     .iter()
                     .filter_map(|(_, value, name)| {
@@ -302,11 +499,31 @@ impl<'a, 'b> Matcher<'a, 'b> {
                         }
                     })
                     .collect();
-                if let Some(value) = param.get_arg_value(&values) {
+                if let Some((value, source)) = param.get_arg_value(&values) {
                     output.push(value);
+                    output.push(source_value(&param.name, source));
+                    set_names.insert(param.name.as_str());
+                } else if set_unset && set_names.insert(param.name.as_str()) {
+                    output.push(param.get_unset_value());
+                    output.push(unset_source_value(&param.name));
                 }
             }
         }
+        if find_metadata(&self.cmds, "pass-unknown").is_some() {
+            let unknown_args: Vec<String> = self
+                .flag_option_args
+                .iter()
+                .flatten()
+                .filter(|(_, _, name)| name.is_none())
+                .map(|(key, values, _)| match values.first() {
+                    Some(value) => format!("{key}={value}"),
+                    None => key.to_string(),
+                })
+                .collect();
+            if !unknown_args.is_empty() || set_unset {
+                output.push(ArgcValue::Multiple("_unknown_args".into(), unknown_args));
+            }
+        }
 
         let positional_values = self.match_positionals();
         for (i, param) in last_cmd.positional_params.iter().enumerate() {
@@ -314,8 +531,52 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 .get(i)
                 .map(|v| v.as_slice())
                 .unwrap_or_default();
-            if let Some(value) = param.get_arg_value(values) {
+            if let Some((value, source)) = param.get_arg_value(values) {
                 output.push(value);
+                output.push(source_value(&param.name, source));
+            } else if set_unset && !set_names.contains(param.name.as_str()) {
+                output.push(param.get_unset_value());
+                output.push(unset_source_value(&param.name));
+            }
+        }
+        output
+    }
+
+    /// Validate the values the params end up with, whether from the args, env or a default
+    fn to_validate_fn_values(&self) -> Vec<ArgcValue> {
+        let mut output = vec![];
+        for level in 0..self.cmds.len() {
+            let args = &self.flag_option_args[level];
+            let cmd = self.cmds[level].1;
+            for param in cmd.flag_option_params.iter().filter(|v| v.is_option()) {
+                let Some(validate_fn) = param.validate_fn.as_ref() else {
+                    continue;
+                };
+                if param.global && is_shadowed(&self.cmds[level + 1..], param) {
+                    continue;
+                }
+                let values: Vec<&[&str]> = args
+                    .iter()
+                    .filter(|(_, _, name)| *name == Some(param.name.as_str()))
+                    .map(|(_, values, _)| values.as_slice())
+                    .collect();
+                if let Some((value, _)) = param.get_arg_value(&values) {
+                    output.extend(to_validate_fn_value(validate_fn, value));
+                }
+            }
+        }
+        let last_cmd = self.cmds[self.cmds.len() - 1].1;
+        let positional_values = self.match_positionals();
+        for (i, param) in last_cmd.positional_params.iter().enumerate() {
+            let Some(validate_fn) = param.validate_fn.as_ref() else {
+                continue;
+            };
+            let values = positional_values
+                .get(i)
+                .map(|v| v.as_slice())
+                .unwrap_or_default();
+            if let Some((value, _)) = param.get_arg_value(values) {
+                output.extend(to_validate_fn_value(validate_fn, value));
             }
         }
         output
@@ -335,6 +596,10 @@ impl<'a, 'b> Matcher<'a, 'b> {
                         || *key == "-help"
                         || (last_cmd.match_help_short_name() && *key == "-h")
                     {
+                        let next = self.args.iter().skip_while(|v| v != key).nth(1);
+                        if next.map(|v| v.as_str()) == Some("json") {
+                            return Some(MatchError::DisplayHelpJson);
+                        }
                         return Some(MatchError::DisplayHelp);
                     } else if *key == "--version"
                         || *key == "-version"
@@ -346,6 +611,9 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 _ => {}
             }
         }
+        if self.find_terminal_param().is_some() {
+            return None;
+        }
         if let Some(&"help") = self.positional_args.first() {
             if self.positional_args.len() < 2 {
                 return Some(MatchError::DisplayHelp);
@@ -364,11 +632,15 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 ));
             }
         }
-        if !last_cmd.subcommands.is_empty() {
-            if self.positional_args.is_empty() && last_args.is_empty() {
-                if !last_cmd.exist_main_fn(&cmd_paths) {
-                    return Some(MatchError::DisplayHelp);
-                }
+        if !last_cmd.subcommands.is_empty()
+            && self.fallback_fn.is_none()
+            && !last_cmd.exist_main_fn(&cmd_paths)
+        {
+            if self.positional_args.is_empty()
+                && last_args.is_empty()
+                && find_metadata(&self.cmds, "require-subcommand").is_none()
+            {
+                return Some(MatchError::DisplayHelp);
             } else {
                 return Some(MatchError::InvalidSubcommand);
             }
@@ -395,12 +667,44 @@ impl<'a, 'b> Matcher<'a, 'b> {
             vec![]
         };
         for (i, param) in last_cmd.positional_params.iter().enumerate() {
+            if let (Some(values), Some(UniqueMode::Reject)) =
+                (positional_values.get(i), param.unique)
+            {
+                if let Some(value) = find_duplicate(values, |v| param.normalize_choice(v)) {
+                    return Some(MatchError::DuplicateValue(
+                        level,
+                        value.to_string(),
+                        param.render_value(),
+                    ));
+                }
+            }
+            if let (Some(values), Some(value_type)) = (positional_values.get(i), param.value_type) {
+                if let Some(value) = values.iter().find(|v| !value_type.is_valid(v)) {
+                    return Some(MatchError::InvalidType(
+                        level,
+                        value.to_string(),
+                        param.render_value(),
+                        value_type,
+                    ));
+                }
+            }
+            if let (Some(values), Some(range)) = (positional_values.get(i), param.range.as_ref()) {
+                if let Some(value) = values.iter().find(|v| !in_range(range, v)) {
+                    return Some(MatchError::OutOfRange(
+                        level,
+                        value.to_string(),
+                        param.render_value(),
+                        *range,
+                    ));
+                }
+            }
             if let (Some(values), Some(choices)) = (
                 positional_values.get(i),
-                get_param_choices(&param.choices, &param.choices_fn, &self.choices_values),
+                get_param_choices(&param.choices, &param.choices_fn, &self.choices_values)
+                    .filter(|_| !param.choices_any),
             ) {
                 for value in values.iter() {
-                    if !choices.contains(&value.to_string()) {
+                    if !choices.contains(&param.normalize_choice(value)) {
                         return Some(MatchError::InvalidValue(
                             level,
                             value.to_string(),
@@ -411,6 +715,7 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 }
             }
         }
+        let pass_unknown = find_metadata(&self.cmds, "pass-unknown").is_some();
         for level in (0..cmds_len).rev() {
             let args = &self.flag_option_args[level];
             let cmd = self.cmds[level].1;
@@ -418,7 +723,11 @@ impl<'a, 'b> Matcher<'a, 'b> {
             let mut missing_flag_options: IndexSet<&str> = cmd
                 .flag_option_params
                 .iter()
-                .filter(|v| v.required)
+                .filter(|v| {
+                    v.required
+                        && v.get_env_value().is_none()
+                        && v.get_default_file_value().is_none()
+                })
                 .map(|v| v.name.as_str())
                 .collect();
             for (i, (key, _, name)) in args.iter().enumerate() {
@@ -427,13 +736,22 @@ impl<'a, 'b> Matcher<'a, 'b> {
                         missing_flag_options.remove(name);
                         flag_option_map.entry(name).or_insert(vec![]).push(i);
                     }
+                    None if pass_unknown => {}
                     None => return Some(MatchError::UnknownArgument(level, key.to_string())),
                 }
             }
             if !missing_flag_options.is_empty() {
                 let missing_flag_options: Vec<String> = missing_flag_options
                     .iter()
-                    .filter_map(|v| cmd.find_flag_option(v).map(|v| v.render_name_values()))
+                    .filter_map(|v| {
+                        cmd.find_flag_option(v).map(|v| {
+                            if v.env_only {
+                                v.render_env()
+                            } else {
+                                v.render_name_values()
+                            }
+                        })
+                    })
                     .collect();
                 missing_params.extend(missing_flag_options)
             }
@@ -444,14 +762,32 @@ impl<'a, 'b> Matcher<'a, 'b> {
                     if !param.multiple && values_list.len() > 1 {
                         return Some(MatchError::NotMultipleArgument(level, param.render_name()));
                     }
+                    if param.unique == Some(UniqueMode::Reject) {
+                        let values: Vec<&str> = values_list.concat();
+                        if let Some(value) = find_duplicate(&values, |v| param.normalize_choice(v))
+                        {
+                            return Some(MatchError::DuplicateValue(
+                                level,
+                                value.to_string(),
+                                param.render_name(),
+                            ));
+                        }
+                    }
                     for values in values_list.iter() {
-                        if values.len() != param.values_size() {
+                        if values.len() != param.values_size()
+                            && !(param.optional_value && values.len() == 1)
+                        {
                             if param.is_flag() {
                                 return Some(MatchError::NoMoreValue(
                                     level,
                                     param.render_name(),
                                     values[0].to_string(),
                                 ));
+                            } else if values.is_empty() {
+                                return Some(MatchError::MissingValue(
+                                    level,
+                                    param.render_name_values(),
+                                ));
                             } else if !param.multiple {
                                 return Some(MatchError::MismatchValues(
                                     level,
@@ -459,13 +795,47 @@ impl<'a, 'b> Matcher<'a, 'b> {
                                 ));
                             }
                         }
+                        if let Some((value, value_type)) =
+                            values.iter().enumerate().find_map(|(i, v)| {
+                                let value_type = param.value_type(i)?;
+                                (!value_type.is_valid(v)).then_some((v, value_type))
+                            })
+                        {
+                            return Some(MatchError::InvalidType(
+                                level,
+                                value.to_string(),
+                                param.render_name(),
+                                value_type,
+                            ));
+                        }
+                        if param.map {
+                            if let Some(value) = values.iter().find(|v| !is_map_value(v)) {
+                                return Some(MatchError::InvalidMapValue(
+                                    level,
+                                    value.to_string(),
+                                    param.render_name(),
+                                ));
+                            }
+                        }
+                        if let Some(range) = param.range.as_ref() {
+                            if let Some(value) = values.iter().find(|v| !in_range(range, v)) {
+                                return Some(MatchError::OutOfRange(
+                                    level,
+                                    value.to_string(),
+                                    param.render_name(),
+                                    *range,
+                                ));
+                            }
+                        }
                         if let Some(choices) = get_param_choices(
                             &param.choices,
                             &param.choices_fn,
                             &self.choices_values,
-                        ) {
+                        )
+                        .filter(|_| !param.choices_any)
+                        {
                             for value in values.iter() {
-                                if !choices.contains(&value.to_string()) {
+                                if !choices.contains(&param.normalize_choice(value)) {
                                     return Some(MatchError::InvalidValue(
                                         level,
                                         value.to_string(),
@@ -506,12 +876,16 @@ impl<'a, 'b> Matcher<'a, 'b> {
             let param = &cmd.positional_params[param_index];
             if param.multiple {
                 let dashdash_idx = self.dashdash.first().cloned().unwrap_or_default();
+                let rest = &cmd.positional_params[param_index + 1..];
                 let takes = if param_index == 0
                     && dashdash_idx > 0
                     && params_len == 2
                     && cmd.positional_params[1].multiple
                 {
                     dashdash_idx
+                } else if !param.required && rest.iter().all(|v| !v.multiple) {
+                    // `src* dest!`, the args after the multiple one are filled first
+                    (args_len - arg_index).saturating_sub(rest.len())
                 } else {
                     (args_len - arg_index).saturating_sub(params_len - param_index) + 1
                 };
@@ -531,123 +905,334 @@ impl<'a, 'b> Matcher<'a, 'b> {
     }
 
     fn stringify_match_error(&self, err: &MatchError) -> (String, i32) {
-        let mut exit = 0;
-        let footer = "For more information, try '--help'.";
-        let message = match err {
+        let (level, class, message) = match err {
             MatchError::DisplayHelp => {
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(self.cmds.len() - 1);
-                cmd.render_help(&cmd_paths, self.term_width)
+                let level = self.cmds.len() - 1;
+                let (cmd, cmd_paths) = self.get_cmd_and_paths(level);
+                let parents = self.get_parents(level);
+                let annotate = find_metadata(&self.cmds, "no-auto-annotations").is_none();
+                let preserve_order = find_metadata(&self.cmds, "preserve-order").is_some();
+                let help = cmd.render_help(
+                    &cmd_paths,
+                    &parents,
+                    self.term_width,
+                    annotate,
+                    preserve_order,
+                );
+                return (help, 0);
+            }
+            MatchError::DisplayHelpJson => {
+                let level = self.cmds.len() - 1;
+                let parents = self.get_parents(level);
+                let json = self.cmds[level]
+                    .1
+                    .to_node_json(&parents)
+                    .and_then(|v| serde_json::to_string_pretty(&v))
+                    .unwrap_or_default();
+                return (json, 0);
             }
             MatchError::DisplaySubcommandHelp(name) => {
-                let (cmd, mut cmd_paths) = self.get_cmd_and_paths(self.cmds.len() - 1);
+                let level = self.cmds.len() - 1;
+                let (cmd, mut cmd_paths) = self.get_cmd_and_paths(level);
+                let parents = self.get_parents(level + 1);
                 let cmd = cmd.find_subcommand(name).unwrap();
                 cmd_paths.push(name.as_str());
-                cmd.render_help(&cmd_paths, self.term_width)
+                let annotate = cmd.get_metadata("no-auto-annotations").is_none()
+                    && find_metadata(&self.cmds, "no-auto-annotations").is_none();
+                let preserve_order = cmd.get_metadata("preserve-order").is_some()
+                    || find_metadata(&self.cmds, "preserve-order").is_some();
+                let help = cmd.render_help(
+                    &cmd_paths,
+                    &parents,
+                    self.term_width,
+                    annotate,
+                    preserve_order,
+                );
+                return (help, 0);
             }
             MatchError::DisplayVersion => {
                 let (cmd, cmd_paths) = self.get_cmd_and_paths(self.cmds.len() - 1);
-                cmd.render_version(&cmd_paths)
+                return (cmd.render_version(&cmd_paths), 0);
             }
             MatchError::InvalidSubcommand => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(self.cmds.len() - 1);
+                let level = self.cmds.len() - 1;
+                let (cmd, cmd_paths) = self.get_cmd_and_paths(level);
                 let cmd_str = cmd_paths.join("-");
-                let usage = cmd.render_usage(&cmd_paths);
-                let names = cmd.list_subcommand_names().join(", ");
-                format!(
-                    r###"error: `{cmd_str}` requires a subcommand but one was not provided
-  [subcommands: {names}]
-
-{usage}
-
-{footer}
-"###
+                let names = cmd.list_subcommand_names();
+                let tip = self
+                    .positional_args
+                    .first()
+                    .and_then(|v| did_you_mean(v, &names))
+                    .map(|v| match cmd.resolve_subcommand_alias(&v) {
+                        Some(name) => format!(
+                            "\n  tip: a similar subcommand exists: '{v}' (alias of '{name}')"
+                        ),
+                        None => format!("\n  tip: a similar subcommand exists: '{v}'"),
+                    })
+                    .unwrap_or_default();
+                let names = names.join(", ");
+                (
+                    level,
+                    "unknown-subcommand",
+                    format!(
+                        r###"error: `{cmd_str}` requires a subcommand but one was not provided
+  [subcommands: {names}]{tip}"###
+                    ),
                 )
             }
             MatchError::UnknownArgument(level, name) => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(*level);
-                let usage = cmd.render_usage(&cmd_paths);
-                format!(
-                    r###"error: unexpected argument `{name}` found
-
-{usage}
-
-{footer}
-"###
+                let class = if name.starts_with('-') {
+                    "unknown-option"
+                } else {
+                    "unexpected-argument"
+                };
+                (
+                    *level,
+                    class,
+                    format!("error: unexpected argument `{name}` found"),
                 )
             }
             MatchError::MissingRequiredArgument(level, values) => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(*level);
-                let usage = cmd.render_usage(&cmd_paths);
                 let list = values
                     .iter()
                     .map(|v| format!("  {v}"))
                     .collect::<Vec<String>>()
                     .join("\n");
-                format!(
-                    r###"error: the following required arguments were not provided:
-{list}
-
-{usage}
-
-{footer}
-"###
+                (
+                    *level,
+                    "missing-required",
+                    format!("error: the following required arguments were not provided:\n{list}"),
                 )
             }
-            MatchError::NotMultipleArgument(level, name) => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(*level);
-                let usage = cmd.render_usage(&cmd_paths);
-                format!(
-                    r###"error: the argument `{name}` cannot be used multiple times
-
-{usage}
-
-{footer}
-"###
+            MatchError::NotMultipleArgument(level, name) => (
+                *level,
+                "invalid-value",
+                format!("error: the argument `{name}` cannot be used multiple times"),
+            ),
+            MatchError::InvalidValue(level, value, name, choices) => {
+                let list = choices.join(", ");
+                (
+                    *level,
+                    "invalid-value",
+                    format!(
+                        "error: invalid value `{value}` for `{name}`\n  [possible values: {list}]"
+                    ),
                 )
             }
-            MatchError::InvalidValue(_level, value, name, choices) => {
-                exit = 1;
-                let list = choices.join(", ");
-                format!(
-                    r###"error: invalid value `{value}` for `{name}`
-  [possible values: {list}]
-
-{footer}
-"###
+            MatchError::OutOfRange(level, value, name, (start, end)) => (
+                *level,
+                "invalid-value",
+                format!("error: value `{value}` for `{name}` is out of range {start}..{end}"),
+            ),
+            MatchError::InvalidType(level, value, name, value_type) => {
+                let expected = value_type.expected();
+                (
+                    *level,
+                    "invalid-value",
+                    format!("error: invalid value `{value}` for `{name}`, expected {expected}"),
                 )
             }
-            MatchError::MismatchValues(level, value) => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(*level);
-                let usage = cmd.render_usage(&cmd_paths);
+            MatchError::InvalidMapValue(level, value, name) => (
+                *level,
+                "invalid-value",
+                format!("error: invalid value `{value}` for `{name}`, expected key=value"),
+            ),
+            MatchError::DuplicateValue(level, value, name) => (
+                *level,
+                "invalid-value",
+                format!("error: the value `{value}` for `{name}` cannot be used multiple times"),
+            ),
+            MatchError::MismatchValues(level, value) => (
+                *level,
+                "invalid-value",
+                format!("error: invalid values for `{value}`"),
+            ),
+            MatchError::MissingValue(level, value) => (
+                *level,
+                "invalid-value",
+                format!("error: a value is required for `{value}` but none was supplied"),
+            ),
+            MatchError::NoMoreValue(level, name, value) => (
+                *level,
+                "invalid-value",
                 format!(
-                    r###"error: invalid values for `{value}`
+                    "error: unexpected value `{value}` for `{name}` found; no more were expected "
+                ),
+            ),
+        };
+        let (cmd, cmd_paths) = self.get_cmd_and_paths(level);
+        let cmds = &self.cmds[..=level];
+        let exit = get_exit_code(find_metadata(cmds, "exit-code"), class);
+        let message = if find_metadata(cmds, "full-help-on-error").is_some() {
+            let annotate = find_metadata(cmds, "no-auto-annotations").is_none();
+            let preserve_order = find_metadata(cmds, "preserve-order").is_some();
+            let parents = self.get_parents(level);
+            let help = cmd.render_help(
+                &cmd_paths,
+                &parents,
+                self.term_width,
+                annotate,
+                preserve_order,
+            );
+            format!("{message}\n\n{help}")
+        } else {
+            let usage = cmd.render_usage(&cmd_paths);
+            let cmd_str = cmd_paths.join(" ");
+            format!("{message}\n\n{usage}\n\nTry '{cmd_str} --help' for more information.\n")
+        };
+        (message, exit)
+    }
 
-{usage}
+    fn get_version_expansion(&self, err: &MatchError) -> Option<String> {
+        let cmd = self.cmds[self.cmds.len() - 1].1;
+        match err {
+            MatchError::DisplayHelp | MatchError::DisplayVersion => cmd.version_expansion(),
+            MatchError::DisplaySubcommandHelp(name) => cmd
+                .find_subcommand(name)
+                .and_then(|v| v.version_expansion()),
+            _ => None,
+        }
+    }
 
-{footer}
-"###
-                )
+    pub(crate) fn to_resolved_path(&self) -> Result<Vec<String>, ResolveError> {
+        let mut output: Vec<String> = self.args[1..].to_vec();
+        for (arg, _, name) in self.cmds.iter().skip(1) {
+            output[self.arg_index(arg) - 1].clone_from(name);
+        }
+        let level = self.cmds.len() - 1;
+        let (cmd, cmd_paths) = self.get_cmd_and_paths(level);
+        if let Some(arg) = self.positional_args.first() {
+            if *arg != "help"
+                && !cmd.subcommands.is_empty()
+                && self.fallback_fn.is_none()
+                && !cmd.exist_main_fn(&cmd_paths)
+            {
+                return Err(ResolveError {
+                    index: self.arg_index(arg) - 1,
+                    token: arg.to_string(),
+                    path: cmd_paths[1..].iter().map(|v| v.to_string()).collect(),
+                    candidates: cmd
+                        .subcommands
+                        .iter()
+                        .flat_map(|v| v.list_names())
+                        .collect(),
+                });
             }
-            MatchError::NoMoreValue(level, name, value) => {
-                exit = 1;
-                let (cmd, cmd_paths) = self.get_cmd_and_paths(*level);
-                let usage = cmd.render_usage(&cmd_paths);
-                format!(
-                    r###"error: unexpected value `{value}` for `{name}` found; no more were expected 
-
-{usage}
+        }
+        Ok(output)
+    }
 
-{footer}
-"###
-                )
+    /// What each arg was matched as and the param it is bound to, for `--argc-trace`
+    pub(crate) fn to_trace(&self) -> Vec<serde_json::Value> {
+        let mut output: Vec<serde_json::Value> = self.args[1..]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| serde_json::json!({ "index": i + 1, "token": v, "kind": "unmatched" }))
+            .collect();
+        for (arg, _, name) in self.cmds.iter().skip(1) {
+            let index = self.arg_index(arg);
+            set_trace(&mut output, index, "subcommand", None);
+            output[index - 1]["name"] = name.as_str().into();
+        }
+        for (index, arg) in self.args.iter().enumerate().skip(1) {
+            if arg == "--" || is_args_separator(&self.cmds, arg) {
+                set_trace(&mut output, index, "terminator", None);
             }
-        };
-        (message, exit)
+        }
+        for (level, list) in self.flag_option_args.iter().enumerate() {
+            let cmd = self.cmds[level].1;
+            for (arg, values, name) in list {
+                let index = self.token_index(arg);
+                let param = name.and_then(|v| cmd.flag_option_params.iter().find(|p| p.name == v));
+                // Shorts combined like `-abc` bind one token to several params
+                let combined = list
+                    .iter()
+                    .filter(|(v, _, _)| std::ptr::eq(*v, *arg))
+                    .count()
+                    > 1;
+                match param {
+                    Some(param) if combined => {
+                        set_trace(&mut output, index, "combined", None);
+                        if let Some(item) = index.checked_sub(1).and_then(|v| output.get_mut(v)) {
+                            match item["params"].as_array_mut() {
+                                Some(params) => params.push(param.name.as_str().into()),
+                                None => item["params"] = serde_json::json!([param.name]),
+                            }
+                        }
+                    }
+                    Some(param) if param.is_flag() => {
+                        set_trace(&mut output, index, "flag", Some(&param.name))
+                    }
+                    Some(param) => set_trace(&mut output, index, "option", Some(&param.name)),
+                    None if KNOWN_OPTIONS.contains(arg) => {
+                        set_trace(&mut output, index, "builtin", None)
+                    }
+                    None => set_trace(&mut output, index, "unknown", None),
+                }
+                if let Some(param) = param {
+                    for value in values {
+                        let value_index = self.token_index(value);
+                        if value_index != index {
+                            set_trace(&mut output, value_index, "option-value", Some(&param.name));
+                        }
+                    }
+                }
+            }
+        }
+        let cmd = self.cmds[self.cmds.len() - 1].1;
+        let values = self.match_positionals();
+        for arg in self.positional_args.iter() {
+            let param = values
+                .iter()
+                .position(|list| list.iter().any(|v| std::ptr::eq(*v, *arg)))
+                .map(|i| cmd.positional_params[i].name.as_str());
+            set_trace(&mut output, self.arg_index(arg), "positional", param);
+        }
+        output
+    }
+
+    /// Index of the arg that a token is part of, e.g. the value of `--name=value`
+    fn token_index(&self, token: &str) -> usize {
+        let start = token.as_ptr() as usize;
+        self.args
+            .iter()
+            .position(|v| std::ptr::eq(v.as_str(), token))
+            .or_else(|| {
+                self.args.iter().position(|v| {
+                    let v_start = v.as_ptr() as usize;
+                    v_start <= start && start + token.len() <= v_start + v.len()
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    /// Index of a matched token, compared by address since tokens are slices of the args
+    fn arg_index(&self, arg: &str) -> usize {
+        self.args
+            .iter()
+            .position(|v| std::ptr::eq(v.as_str(), arg))
+            .unwrap_or_default()
+    }
+
+    /// The first `terminal` flag/option given, the other args are then left unchecked
+    fn find_terminal_param(&self) -> Option<&'a FlagOptionParam> {
+        self.flag_option_args
+            .iter()
+            .enumerate()
+            .find_map(|(level, args)| {
+                let cmd = self.cmds[level].1;
+                args.iter().find_map(|(_, _, name)| {
+                    let name = (*name)?;
+                    cmd.flag_option_params
+                        .iter()
+                        .find(|v| v.terminal && v.name == name)
+                })
+            })
+    }
+
+    /// The commands above `level`, from the root
+    fn get_parents(&self, level: usize) -> Vec<&'a Command> {
+        self.cmds[..level].iter().map(|v| v.1).collect()
     }
 
     fn get_cmd_and_paths(&self, level: usize) -> (&Command, Vec<&str>) {
@@ -669,7 +1254,7 @@ impl<'a, 'b> Matcher<'a, 'b> {
             .iter()
             .filter_map(|v| v.2)
             .collect();
-        for param in cmd.flag_option_params.iter() {
+        for param in cmd.flag_option_params.iter().filter(|v| !v.env_only) {
             let exist = args.contains(param.name.as_str());
             if !exist || param.multiple {
                 let describe = param.describe_head();
@@ -678,18 +1263,122 @@ impl<'a, 'b> Matcher<'a, 'b> {
                 }
             }
         }
+        for parent_level in (0..level).rev() {
+            let parent = self.cmds[parent_level].1;
+            for param in parent.flag_option_params.iter() {
+                if !param.global
+                    || param.env_only
+                    || is_shadowed(&self.cmds[parent_level + 1..], param)
+                {
+                    continue;
+                }
+                let exist = self.flag_option_args[parent_level]
+                    .iter()
+                    .any(|v| v.2 == Some(param.name.as_str()));
+                if !exist || param.multiple {
+                    let describe = param.describe_head();
+                    for v in param.list_names() {
+                        output.push((v, describe.to_string()))
+                    }
+                }
+            }
+        }
         output
     }
 }
 
-fn take_value_args(args: &[String], start: usize, len: usize) -> Vec<&str> {
+/// Look up a meta of the command or its nearest ancestor
+fn find_metadata<'a>(cmds: &[(&str, &'a Command, String)], key: &str) -> Option<&'a str> {
+    cmds.iter()
+        .rev()
+        .find_map(|(_, cmd, _)| cmd.get_metadata(key))
+}
+
+fn set_trace(output: &mut [serde_json::Value], index: usize, kind: &str, param: Option<&str>) {
+    let Some(item) = index.checked_sub(1).and_then(|v| output.get_mut(v)) else {
+        return;
+    };
+    item["kind"] = kind.into();
+    if let Some(param) = param {
+        item["param"] = param.into();
+    }
+}
+
+/// Find a flag/option of the last command, or else a `global` one of an ancestor. The nearest
+/// command that declares it wins, so a local definition shadows an inherited one
+fn find_scoped_flag_option<'a>(
+    cmds: &[(&str, &'a Command, String)],
+    find: impl Fn(&'a Command) -> Option<&'a FlagOptionParam>,
+) -> Option<(usize, &'a FlagOptionParam)> {
+    let level = cmds.len() - 1;
+    cmds.iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, (_, cmd, _))| find(cmd).map(|v| (i, v)))
+        .filter(|(i, v)| *i == level || v.global)
+}
+
+/// A global flag/option is shadowed when a subcommand in `cmds` declares one with the same name
+fn is_shadowed(cmds: &[(&str, &Command, String)], param: &FlagOptionParam) -> bool {
+    cmds.iter()
+        .any(|(_, cmd, _)| cmd.flag_option_params.iter().any(|v| v.name == param.name))
+}
+
+/// A `+name` arg that sets a flag declared with `@flag +name`
+fn find_plus_flag<'a>(cmd: &'a Command, arg: &str) -> Option<&'a FlagOptionParam> {
+    if !arg.starts_with('+') {
+        return None;
+    }
+    cmd.flag_option_params
+        .iter()
+        .find(|v| v.plus.as_deref() == arg.get(1..))
+}
+
+fn is_args_separator(cmds: &[(&str, &Command, String)], arg: &str) -> bool {
+    find_metadata(cmds, "args-separator") == Some(arg)
+}
+
+/// Stop option parsing at the first positional, forced by the `parse-mode` meta,
+/// otherwise enabled by `POSIXLY_CORRECT`
+fn is_posix_mode(cmds: &[(&str, &Command, String)]) -> bool {
+    match find_metadata(cmds, "parse-mode") {
+        Some(mode) => mode == "posix",
+        None => env::var_os("POSIXLY_CORRECT").is_some(),
+    }
+}
+
+/// A map item needs a non-empty key before the `=`
+fn is_map_value(value: &str) -> bool {
+    value
+        .split_once('=')
+        .map(|(k, _)| !k.is_empty())
+        .unwrap_or_default()
+}
+
+/// A bare `-` conventionally means stdin, so it is a value rather than an option
+fn is_option_arg(arg: &str) -> bool {
+    arg.starts_with('-') && arg != "-"
+}
+
+/// Numeric typed values like `-3` are taken as values rather than options
+fn take_value_args<'b>(
+    args: &'b [String],
+    start: usize,
+    len: usize,
+    param: &FlagOptionParam,
+) -> Vec<&'b str> {
     let mut output = vec![];
     if len == 0 {
         return output;
     }
     let end = (start + len).min(args.len());
-    for arg in args.iter().take(end).skip(start) {
-        if arg.starts_with('-') {
+    for (i, arg) in args.iter().take(end).skip(start).enumerate() {
+        if is_option_arg(arg)
+            && !param
+                .value_type(i)
+                .map(|v| v != ValueType::Bool && v.is_valid(arg))
+                .unwrap_or_default()
+        {
             break;
         }
         output.push(arg.as_str());
@@ -697,19 +1386,46 @@ fn take_value_args(args: &[String], start: usize, len: usize) -> Vec<&str> {
     output
 }
 
+/// The call of `validate_fn` on a resolved value, none for an empty list
+fn to_validate_fn_value(validate_fn: &str, value: ArgcValue) -> Option<ArgcValue> {
+    let validate_fn = validate_fn.to_string();
+    match value {
+        ArgcValue::Single(_, value) | ArgcValue::PositionalSingle(_, value) => {
+            Some(ArgcValue::ValidateFn(validate_fn, vec![value]))
+        }
+        ArgcValue::Multiple(_, values)
+        | ArgcValue::Map(_, values)
+        | ArgcValue::PositionalMultiple(_, values)
+            if !values.is_empty() =>
+        {
+            Some(ArgcValue::ValidateFn(validate_fn, values))
+        }
+        ArgcValue::SingleFn(name, _) | ArgcValue::PositionalSingleFn(name, _) => {
+            Some(ArgcValue::ValidateFnVar(validate_fn, name))
+        }
+        _ => None,
+    }
+}
+
+/// Split a cluster like `-abc` into its shorts. An option in it takes the rest of the cluster
+/// as its value, so `-abofile` and `-abo=file` are `-a -b -o file`. The last short may be an
+/// option that takes the next args as values
 fn match_combine_shorts<'a, 'b>(
-    cmd: &'a Command,
+    cmds: &[(&str, &'a Command, String)],
     arg: &'b str,
-) -> Option<Vec<FlagOptionArg<'a, 'b>>> {
+) -> Option<Vec<(usize, FlagOptionArg<'a, 'b>)>> {
     if arg.len() > 2 && !arg.starts_with("--") {
         let mut output = vec![];
-        for ch in arg.chars().skip(1) {
+        for (i, ch) in arg.char_indices().skip(1) {
             let name: String = format!("-{ch}");
-            if let Some(param) = cmd.find_flag_option(&name) {
-                output.push((arg, vec![], Some(param.name.as_str())))
-            } else {
-                return None;
+            let (level, param) = find_scoped_flag_option(cmds, |v| v.find_flag_option(&name))?;
+            let rest = &arg[i + ch.len_utf8()..];
+            if param.is_option() && !rest.is_empty() {
+                let value = rest.strip_prefix('=').unwrap_or(rest);
+                output.push((level, (arg, vec![value], Some(param.name.as_str()))));
+                break;
             }
+            output.push((level, (arg, vec![], Some(param.name.as_str()))))
         }
         Some(output)
     } else {
@@ -726,7 +1442,7 @@ fn match_flag_option<'a, 'b>(
 ) {
     let values_len = param.values_size();
     let args_len = args.len();
-    let value_args = take_value_args(args, *arg_index + 1, values_len);
+    let value_args = take_value_args(args, *arg_index + 1, values_len, param);
     let arg = &args[*arg_index];
     *arg_index += value_args.len();
     if *arg_index == args_len - 1 {
@@ -762,7 +1478,7 @@ fn comp_subcommands_positional(
 
 fn comp_subcomands(cmd: &Command) -> Vec<(String, String)> {
     let mut output = vec![];
-    for subcmd in cmd.subcommands.iter() {
+    for subcmd in cmd.subcommands.iter().filter(|v| !v.is_main()) {
         let describe = subcmd.describe_head();
         for v in subcmd.list_names() {
             output.push((v, describe.to_string()))
@@ -777,28 +1493,61 @@ fn comp_flag_option(param: &FlagOptionParam, index: usize) -> Vec<(String, Strin
         .get(index)
         .map(|v| v.as_str())
         .unwrap_or_else(|| param.arg_value_names.last().unwrap());
+    let value_name = match param.value_type(index) {
+        Some(value_type) if value_type.is_path() => value_type.name(),
+        _ => value_name,
+    };
+    let choices = param
+        .choices
+        .clone()
+        .or_else(|| param.range.map(|v| range_sample(&v, &param.default)))
+        .or_else(|| param.value_type(index).and_then(|v| v.choices()));
     comp_param(
         param.describe_head(),
         value_name,
-        &param.choices,
+        &choices,
+        &param.choices_describe,
         &param.choices_fn,
+        &param.examples,
     )
 }
 
 fn comp_positional(param: &PositionalParam) -> Vec<(String, String)> {
+    if param.terminated {
+        return vec![("__argc_value:file".into(), param.describe_head().into())];
+    }
+    let choices = param
+        .choices
+        .clone()
+        .or_else(|| param.range.map(|v| range_sample(&v, &param.default)))
+        .or_else(|| param.value_type.and_then(|v| v.choices()));
+    let value_name = match param.value_type {
+        Some(value_type) if value_type.is_path() => value_type.name(),
+        _ => param.arg_value_name.as_str(),
+    };
     comp_param(
         param.describe_head(),
-        &param.arg_value_name,
-        &param.choices,
+        value_name,
+        &choices,
+        &param.choices_describe,
         &param.choices_fn,
+        &param.examples,
     )
 }
 
+/// A value notation that completion takes as a directory, like `<DIR>`
+fn is_dir_value_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("dir") || name.contains("folder")
+}
+
 fn comp_param(
     describe: &str,
     value_name: &str,
     choices: &Option<Vec<String>>,
+    choices_describe: &[String],
     choices_fn: &Option<(String, bool)>,
+    examples: &[String],
 ) -> Vec<(String, String)> {
     let choices: Option<Either<Vec<String>, String>> = if let Some(choices_fn) = choices_fn {
         Some(Either::Right(choices_fn.0.to_string()))
@@ -811,13 +1560,23 @@ fn comp_param(
         match choices {
             Either::Left(choices) => choices
                 .iter()
-                .map(|v| (v.to_string(), String::new()))
+                .enumerate()
+                .map(|(i, v)| {
+                    let describe = choices_describe.get(i).cloned().unwrap_or_default();
+                    (v.to_string(), describe)
+                })
                 .collect(),
             Either::Right(choices_fn) => vec![(format!("__argc_fn:{}", choices_fn), String::new())],
         }
     } else {
+        // Unlike choices, examples leave the value open, so the fallback for the notation stays
         let value = format!("__argc_value:{}", value_name);
-        vec![(value, describe.into())]
+        let mut output: Vec<(String, String)> = examples
+            .iter()
+            .map(|v| (v.to_string(), String::new()))
+            .collect();
+        output.push((value, describe.into()));
+        output
     }
 }
 
@@ -836,3 +1595,12 @@ fn get_param_choices<'a, 'b: 'a>(
         })
     })
 }
+
+/// `argc_<name>__source`, telling the script where the value of a param came from
+fn source_value(name: &str, source: ValueSource) -> ArgcValue {
+    ArgcValue::Single(format!("{name}__source"), source.as_str().into())
+}
+
+fn unset_source_value(name: &str) -> ArgcValue {
+    ArgcValue::Single(format!("{name}__source"), String::new())
+}
@@ -0,0 +1,41 @@
+use crate::parser::{find_choices_default_modifier, parse, parse_line, EventData};
+use crate::Result;
+
+/// Rewrite tag syntax that reads differently than it behaves, other bytes are kept as is
+pub fn migrate_script(source: &str) -> Result<String> {
+    parse(source)?;
+    let (bom, source) = match source.strip_prefix('\u{feff}') {
+        Some(v) => ("\u{feff}", v),
+        None => ("", source),
+    };
+    let mut output = String::from(bom);
+    for line in source.split_inclusive('\n') {
+        match migrate_line(line) {
+            Some(line) => output.push_str(&line),
+            None => output.push_str(line),
+        }
+    }
+    Ok(output)
+}
+
+/// A choice default makes the param optional, so `name![=a|b]` becomes `name[=a|b]`
+/// and `name+[=a|b]` becomes `name*[=a|b]`
+fn migrate_line(line: &str) -> Option<String> {
+    let content = line.trim_end_matches(['\n', '\r']);
+    let Ok((_, Some(Some(data)))) = parse_line(content) else {
+        return None;
+    };
+    if !matches!(data, EventData::FlagOption(_) | EventData::Positional(_)) {
+        return None;
+    }
+    let (idx, modifier) = find_choices_default_modifier(content)?;
+    let modifier = if modifier == '!' { "" } else { "*" };
+    let output = format!("{}{}{}", &content[..idx], modifier, &line[idx + 1..]);
+    // Keep the line when the rewrite would not mean the same
+    let migrated = &output[..output.len() - (line.len() - content.len())];
+    if parse_line(migrated).ok().map(|v| v.1) == Some(Some(Some(data))) {
+        Some(output)
+    } else {
+        None
+    }
+}
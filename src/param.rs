@@ -1,22 +1,92 @@
 use crate::{
+    parser::Locales,
     utils::{
-        escape_shell_words, is_choice_value_terminate, is_default_value_terminate, to_cobol_case,
+        escape_shell_words, is_choice_value_terminate, is_default_value_terminate, localize,
+        render_plain_text, to_cobol_case,
     },
     ArgcValue,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 
+/// Fields of `FlagOptionParam` that are exported only when set
+const FLAG_OPTION_OPTIONAL_KEYS: [&str; 23] = [
+    "describe_locales",
+    "aliases",
+    "plus",
+    "choices_describe",
+    "choices_ignore_case",
+    "choices_any",
+    "choices_file",
+    "range",
+    "unique",
+    "map",
+    "optional_value",
+    "bare_value",
+    "default_file",
+    "value_types",
+    "env",
+    "env_only",
+    "validate_fn",
+    "examples",
+    "group",
+    "glob",
+    "global",
+    "terminal",
+    "terminal_fn",
+];
+
+/// Fields of `PositionalParam` that are exported only when set
+const POSITIONAL_OPTIONAL_KEYS: [&str; 13] = [
+    "describe_locales",
+    "choices_describe",
+    "choices_ignore_case",
+    "choices_any",
+    "choices_file",
+    "range",
+    "unique",
+    "terminated",
+    "greedy",
+    "value_type",
+    "validate_fn",
+    "examples",
+    "glob",
+];
+
+/// Longer choice lists are cut short in help
+const MAX_CHOICES_IN_HELP: usize = 10;
+
 #[derive(Debug, Clone)]
 pub(crate) struct ParamData {
     pub(crate) name: String,
     pub(crate) choices: Option<Vec<String>>,
+    pub(crate) choices_describe: Vec<String>,
     pub(crate) choices_fn: Option<(String, bool)>,
+    pub(crate) choices_ignore_case: bool,
+    pub(crate) choices_any: bool,
+    pub(crate) choices_file: Option<String>,
+    pub(crate) range: Option<(i64, i64)>,
     pub(crate) multiple: bool,
+    pub(crate) unique: Option<UniqueMode>,
+    pub(crate) terminated: bool,
+    pub(crate) greedy: bool,
+    pub(crate) map: bool,
+    pub(crate) optional_value: bool,
+    pub(crate) bare_value: Option<String>,
     pub(crate) required: bool,
     pub(crate) default: Option<String>,
     pub(crate) default_fn: Option<String>,
+    pub(crate) default_file: Option<String>,
+    pub(crate) value_types: Vec<Option<ValueType>>,
+    pub(crate) env: Option<String>,
+    pub(crate) env_only: bool,
+    pub(crate) validate_fn: Option<String>,
+    pub(crate) examples: Vec<String>,
+    pub(crate) glob: bool,
+    pub(crate) global: bool,
+    pub(crate) terminal: bool,
+    pub(crate) terminal_fn: Option<String>,
 }
 
 impl ParamData {
@@ -24,30 +94,230 @@ impl ParamData {
         Self {
             name: name.to_string(),
             choices: None,
+            choices_describe: vec![],
             choices_fn: None,
+            choices_ignore_case: false,
+            choices_any: false,
+            choices_file: None,
+            range: None,
             multiple: false,
+            unique: None,
+            terminated: false,
+            greedy: false,
+            map: false,
+            optional_value: false,
+            bare_value: None,
             required: false,
             default: None,
             default_fn: None,
+            default_file: None,
+            value_types: vec![],
+            env: None,
+            env_only: false,
+            validate_fn: None,
+            examples: vec![],
+            glob: false,
+            global: false,
+            terminal: false,
+            terminal_fn: None,
+        }
+    }
+
+    /// Keep the notation types and return the notation names
+    pub(crate) fn set_notations<'a>(
+        &mut self,
+        notations: Vec<(&'a str, Option<ValueType>)>,
+    ) -> Vec<&'a str> {
+        if notations.iter().any(|(_, v)| v.is_some()) {
+            self.value_types = notations.iter().map(|(_, v)| *v).collect();
+        }
+        notations.into_iter().map(|(v, _)| v).collect()
+    }
+
+    /// Keep the choices, and their descriptions when any has one
+    pub(crate) fn set_choices(&mut self, choices: Vec<(&str, &str)>) {
+        if choices.iter().any(|(_, v)| !v.is_empty()) {
+            self.choices_describe = choices.iter().map(|(_, v)| v.to_string()).collect();
+        }
+        self.choices = Some(choices.into_iter().map(|(v, _)| v.to_string()).collect());
+    }
+
+    pub(crate) fn set_attrs(&mut self, attrs: Vec<ParamAttr>) {
+        for attr in attrs {
+            match attr {
+                ParamAttr::Env(name) => self.env = Some(name),
+                ParamAttr::EnvOnly => self.env_only = true,
+                ParamAttr::ValidateFn(name) => self.validate_fn = Some(name),
+                ParamAttr::Examples(values) => self.examples = values,
+                ParamAttr::Glob => self.glob = true,
+                ParamAttr::Global => self.global = true,
+                ParamAttr::Terminal(name) => {
+                    self.terminal = true;
+                    self.terminal_fn = name;
+                }
+            }
+        }
+    }
+}
+
+/// How a multiple param treats repeated values, `*^` drops them and `*^^` rejects them
+/// Where a param's value came from, set as `argc_<name>__source`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum ValueSource {
+    Cli,
+    Env,
+    Default,
+    DefaultFn,
+    DefaultFile,
+}
+
+impl ValueSource {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ValueSource::Cli => "cli",
+            ValueSource::Env => "env",
+            ValueSource::Default => "default",
+            ValueSource::DefaultFn => "default_fn",
+            ValueSource::DefaultFile => "default_file",
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum UniqueMode {
+    Dedup,
+    Reject,
+}
+
+/// Type hint from a value notation, e.g. `<N:int>`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ValueType {
+    Int,
+    Float,
+    Bool,
+    File,
+    Dir,
+    Path,
+}
+
+impl ValueType {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "bool" => Some(Self::Bool),
+            "file" => Some(Self::File),
+            "dir" => Some(Self::Dir),
+            "path" => Some(Self::Path),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::File => "file",
+            Self::Dir => "dir",
+            Self::Path => "path",
+        }
+    }
+
+    pub(crate) fn expected(&self) -> &'static str {
+        match self {
+            Self::Int => "an integer",
+            Self::Float => "a number",
+            Self::Bool => "true or false",
+            Self::File => "a file",
+            Self::Dir => "a directory",
+            Self::Path => "a path",
+        }
+    }
+
+    pub(crate) fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Self::Int => value.parse::<i64>().is_ok(),
+            Self::Float => value
+                .parse::<f64>()
+                .map(|v| v.is_finite())
+                .unwrap_or_default(),
+            Self::Bool => value == "true" || value == "false",
+            Self::File | Self::Dir | Self::Path => true,
+        }
+    }
+
+    pub(crate) fn choices(&self) -> Option<Vec<String>> {
+        match self {
+            Self::Bool => Some(vec!["true".into(), "false".into()]),
+            _ => None,
+        }
+    }
+
+    /// Only a completion hint, any value is accepted
+    pub(crate) fn is_path(&self) -> bool {
+        matches!(self, Self::File | Self::Dir | Self::Path)
+    }
+}
+
+/// Attributes placed after the value notations, e.g. `env:NAME`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum ParamAttr {
+    Env(String),
+    EnvOnly,
+    ValidateFn(String),
+    Examples(Vec<String>),
+    Glob,
+    Global,
+    Terminal(Option<String>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub(crate) struct FlagOptionParam {
     pub(crate) name: String,
     pub(crate) describe: String,
+    pub(crate) describe_locales: Locales,
     pub(crate) short: Option<char>,
     pub(crate) flag: bool,
     pub(crate) dashes: String,
+    pub(crate) aliases: Vec<String>,
+    /// Name of the `+name` form that also sets a flag, e.g. `foo` for `+foo`
+    pub(crate) plus: Option<String>,
     pub(crate) choices: Option<Vec<String>>,
+    /// Description of each choice, from `[a:Fast mode|b]`, empty when none has one
+    pub(crate) choices_describe: Vec<String>,
     pub(crate) choices_fn: Option<(String, bool)>,
+    pub(crate) choices_ignore_case: bool,
+    pub(crate) choices_any: bool,
+    pub(crate) choices_file: Option<String>,
+    pub(crate) range: Option<(i64, i64)>,
     pub(crate) multiple: bool,
+    pub(crate) unique: Option<UniqueMode>,
+    pub(crate) map: bool,
+    pub(crate) optional_value: bool,
+    pub(crate) bare_value: Option<String>,
     pub(crate) required: bool,
     pub(crate) default: Option<String>,
     pub(crate) default_fn: Option<String>,
+    /// Path from `=@path`, the option defaults to the trimmed contents of the file
+    pub(crate) default_file: Option<String>,
     pub(crate) value_names: Vec<String>,
-    #[serde(skip_serializing)]
+    pub(crate) value_types: Vec<Option<ValueType>>,
+    pub(crate) env: Option<String>,
+    pub(crate) env_only: bool,
+    pub(crate) validate_fn: Option<String>,
+    /// Values completion offers besides what the user types, e.g. `examples:main,develop`
+    pub(crate) examples: Vec<String>,
+    pub(crate) group: Option<String>,
+    pub(crate) glob: bool,
+    /// Also accepted after the names of the command's subcommands, from `scope:global`
+    pub(crate) global: bool,
+    /// Given, it skips the checks of the other args, like `--help`, from `terminal:true`
+    pub(crate) terminal: bool,
+    /// Function called instead of the command's, from ``terminal:`fn` ``
+    pub(crate) terminal_fn: Option<String>,
     pub(crate) arg_value_names: Vec<String>,
 }
 
@@ -58,6 +328,7 @@ impl FlagOptionParam {
         short: Option<char>,
         flag: bool,
         dashes: &str,
+        aliases: &[&str],
         value_names: &[&str],
     ) -> Self {
         let name = arg.name.clone();
@@ -75,20 +346,47 @@ impl FlagOptionParam {
         Self {
             name,
             describe: describe.to_string(),
+            describe_locales: Locales::new(),
             short,
             flag,
             dashes,
+            aliases: aliases.iter().map(|v| v.to_string()).collect(),
+            plus: None,
             choices: arg.choices,
+            choices_describe: arg.choices_describe,
             choices_fn: arg.choices_fn,
+            choices_ignore_case: arg.choices_ignore_case,
+            choices_any: arg.choices_any,
+            choices_file: arg.choices_file,
+            range: arg.range,
             multiple: arg.multiple,
+            unique: arg.unique,
+            map: arg.map,
+            optional_value: arg.optional_value,
+            bare_value: arg.bare_value,
             required: arg.required,
             default: arg.default,
             default_fn: arg.default_fn,
+            default_file: arg.default_file,
             value_names,
+            value_types: arg.value_types,
+            env: arg.env,
+            env_only: arg.env_only,
+            validate_fn: arg.validate_fn,
+            examples: arg.examples,
+            group: None,
+            glob: arg.glob,
+            global: arg.global,
+            terminal: arg.terminal,
+            terminal_fn: arg.terminal_fn,
             arg_value_names,
         }
     }
 
+    pub(crate) fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        to_export_json(self, &FLAG_OPTION_OPTIONAL_KEYS, "arg_value_names")
+    }
+
     pub(crate) fn is_flag(&self) -> bool {
         self.flag
     }
@@ -105,37 +403,67 @@ impl FlagOptionParam {
         }
     }
 
-    #[allow(unused)]
     pub(crate) fn render(&self) -> String {
         let mut output = vec![];
-        if self.dashes.is_empty() {
-            let name = render_name(
+        let mut name = if self.optional_value {
+            self.render_optional_name()
+        } else if let Some(path) = &self.default_file {
+            let modifer = if self.required { "!" } else { "" };
+            format!("{}{}=@{}", self.name, modifer, path)
+        } else {
+            render_name(
                 &self.name,
                 &self.choices,
+                &self.choices_describe,
+                &self.choices_file,
                 &self.choices_fn,
+                self.choices_ignore_case,
+                self.choices_any,
                 self.multiple,
+                self.unique,
                 self.required,
                 &self.default,
                 &self.default_fn,
-            );
+            )
+        };
+        name.push_str(&render_range(&self.range));
+        if self.map {
+            name.push_str("[map]");
+        }
+        if let Some(plus) = &self.plus {
+            output.push(format!("+{}", plus));
+        }
+        if self.dashes.is_empty() {
             output.push(format!("-{}", name));
         } else {
             if let Some(ch) = self.short {
                 output.push(format!("-{}", ch));
             };
-            let name = render_name(
-                &self.name,
-                &self.choices,
-                &self.choices_fn,
-                self.multiple,
-                self.required,
-                &self.default,
-                &self.default_fn,
-            );
             output.push(format!("{}{}", self.dashes, name));
+            output.extend(self.aliases.iter().cloned());
+        }
+        for (i, value_name) in self.value_names.iter().enumerate() {
+            output.push(render_notation(value_name, self.value_type(i)));
+        }
+        if let Some(env) = &self.env {
+            output.push(format!("env:{}", env));
+        }
+        if self.env_only {
+            output.push("env:only".into());
+        }
+        if let Some(validate_fn) = &self.validate_fn {
+            output.push(format!("validate:`{}`", validate_fn));
+        }
+        if !self.examples.is_empty() {
+            output.push(format!("examples:{}", self.examples.join(",")));
         }
-        for value_name in &self.value_names {
-            output.push(format!("<{}>", value_name));
+        if self.global {
+            output.push("scope:global".into());
+        }
+        match &self.terminal_fn {
+            Some(terminal_fn) => output.push(format!("terminal:`{}`", terminal_fn)),
+            None if self.terminal => output.push("terminal:true".into()),
+            None => {}
         }
         if !self.describe.is_empty() {
             output.push(self.describe.clone());
@@ -143,6 +471,41 @@ impl FlagOptionParam {
         output.join(" ")
     }
 
+    /// Type of the value at `index`, the last notation covers the rest
+    pub(crate) fn value_type(&self, index: usize) -> Option<ValueType> {
+        let index = index.min(self.value_types.len().saturating_sub(1));
+        self.value_types.get(index).copied().flatten()
+    }
+
+    fn render_optional_name(&self) -> String {
+        let mut output = format!("{}?", self.name);
+        if let Some(value) = &self.bare_value {
+            if is_ambiguous_value(value) || value.contains('[') {
+                let _ = write!(output, "={}", quote_value(value));
+            } else {
+                let _ = write!(output, "={}", value);
+            }
+        }
+        if let Some(choices) = &self.choices {
+            let prefix = if self.choices_ignore_case { "~" } else { "" };
+            let _ = write!(
+                output,
+                "[{}{}]",
+                prefix,
+                render_choices(choices, &self.choices_describe, self.choices_any)
+            );
+        }
+        output
+    }
+
+    /// Value used when an optional-value option is given without `=value`
+    pub(crate) fn get_bare_value(&self) -> String {
+        self.bare_value
+            .clone()
+            .or_else(|| self.choices.as_ref().and_then(|v| v.first().cloned()))
+            .unwrap_or_else(|| "1".into())
+    }
+
     pub(crate) fn render_name(&self) -> String {
         if self.dashes.is_empty() {
             format!("-{}", self.name)
@@ -178,10 +541,15 @@ impl FlagOptionParam {
                 format!("    {dashes}{}", self.name)
             }
         };
+        if let Some(plus) = &self.plus {
+            let _ = write!(output, ", +{plus}");
+        }
         if self.is_flag() {
             if self.multiple {
                 output.push_str("...")
             }
+        } else if self.optional_value {
+            let _ = write!(output, "[=<{}>]", self.arg_value_names[0]);
         } else {
             output.push_str(&self.render_arg_values());
         }
@@ -219,47 +587,143 @@ impl FlagOptionParam {
         output
     }
 
-    pub(crate) fn render_describe(&self) -> String {
-        render_describe(&self.describe, &self.default, &self.choices)
+    /// Generated help notes, e.g. `[default: 8080]` or `[env: PORT]`
+    pub(crate) fn render_annotations(&self) -> Vec<String> {
+        let mut output = render_annotations(&self.default, &self.choices, &self.range);
+        if let Some(path) = &self.default_file {
+            output.insert(0, format!("[default: @{path}]"));
+        }
+        if let Some(env) = &self.env {
+            output.push(format!("[env: {env}]"));
+        }
+        if self.required {
+            output.push("[required]".into());
+        }
+        output
     }
 
-    pub(crate) fn get_arg_value(&self, values: &[&[&str]]) -> Option<ArgcValue> {
+    pub(crate) fn render_describe(&self, annotate: bool) -> String {
+        let annotations = if annotate {
+            self.render_annotations()
+        } else {
+            vec![]
+        };
+        let mut output = render_describe(&render_plain_text(self.describe()), &annotations);
+        if !self.aliases.is_empty() {
+            if !output.is_empty() {
+                output.push(' ')
+            }
+            output.push_str(&format!("(aliases: {})", self.aliases.join(", ")));
+        }
+        output
+    }
+
+    pub(crate) fn get_arg_value(&self, values: &[&[&str]]) -> Option<(ArgcValue, ValueSource)> {
         let name = self.name.clone();
+        if values.is_empty() {
+            if let Some(value) = self.get_env_value() {
+                let value = self.normalize_choice(&value);
+                let value = if self.flag {
+                    ArgcValue::Single(name, "1".into())
+                } else if self.multiple {
+                    ArgcValue::Multiple(name, vec![value])
+                } else {
+                    ArgcValue::Single(name, value)
+                };
+                return Some((value, ValueSource::Env));
+            }
+        }
         if self.flag {
             if values.is_empty() {
                 None
             } else {
-                Some(ArgcValue::Single(name, values.len().to_string()))
+                let value = ArgcValue::Single(name, values.len().to_string());
+                Some((value, ValueSource::Cli))
             }
         } else {
             if values.is_empty() {
                 if let Some(value) = self.default.as_ref() {
-                    return Some(ArgcValue::Single(name, value.clone()));
+                    let value = ArgcValue::Single(name, value.clone());
+                    return Some((value, ValueSource::Default));
                 }
                 if let Some(value) = self.default_fn.as_ref() {
-                    return Some(ArgcValue::SingleFn(name, value.clone()));
+                    let value = ArgcValue::SingleFn(name, value.clone());
+                    return Some((value, ValueSource::DefaultFn));
+                }
+                if self.default_file.is_some() {
+                    let value = self.get_default_file_value().unwrap_or_default();
+                    return Some((ArgcValue::Single(name, value), ValueSource::DefaultFile));
                 }
                 return None;
             }
-            if self.multiple {
+            let value = if self.optional_value {
+                let value = match values[0].first() {
+                    Some(value) => self.normalize_choice(value),
+                    None => self.get_bare_value(),
+                };
+                ArgcValue::Single(name, value)
+            } else if self.multiple {
                 let values: Vec<String> = values
                     .iter()
-                    .flat_map(|v| v.iter().map(|v| v.to_string()))
+                    .flat_map(|v| v.iter().map(|v| self.normalize_choice(v)))
                     .collect();
-                Some(ArgcValue::Multiple(name, values))
+                let values = dedup_values(values, self.unique);
+                if self.map {
+                    ArgcValue::Map(name, values)
+                } else {
+                    ArgcValue::Multiple(name, values)
+                }
             } else if self.values_size() > 1 {
-                Some(ArgcValue::Multiple(
+                ArgcValue::Multiple(
                     name,
-                    values[0].iter().map(|v| v.to_string()).collect(),
-                ))
+                    values[0].iter().map(|v| self.normalize_choice(v)).collect(),
+                )
             } else {
-                Some(ArgcValue::Single(name, must_get_first(values[0])))
-            }
+                ArgcValue::Single(name, self.normalize_choice(&must_get_first(values[0])))
+            };
+            Some((value, ValueSource::Cli))
         }
     }
 
+    /// An empty value for when the args don't set the param, so the variable exists under `set -u`
+    pub(crate) fn get_unset_value(&self) -> ArgcValue {
+        let name = self.name.clone();
+        if self.flag || self.optional_value {
+            ArgcValue::Single(name, String::new())
+        } else if self.multiple && self.map {
+            ArgcValue::Map(name, vec![])
+        } else if self.values_size() > 1 {
+            ArgcValue::Multiple(name, vec![])
+        } else {
+            ArgcValue::Single(name, String::new())
+        }
+    }
+
+    pub(crate) fn normalize_choice(&self, value: &str) -> String {
+        normalize_choice(&self.choices, self.choices_ignore_case, value)
+    }
+
     pub(crate) fn is_match(&self, name: &str) -> bool {
-        self.list_names().iter().any(|v| v == name)
+        !self.env_only && self.list_all_names().iter().any(|v| v == name)
+    }
+
+    /// Read the value of the bound env var, flags treat `0`/`false` as unset
+    pub(crate) fn get_env_value(&self) -> Option<String> {
+        let value = std::env::var(self.env.as_ref()?).ok()?;
+        if value.is_empty() || (self.flag && (value == "0" || value == "false")) {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Trimmed contents of the `=@path` file, `None` when it can't be read
+    pub(crate) fn get_default_file_value(&self) -> Option<String> {
+        let content = std::fs::read_to_string(self.default_file.as_ref()?).ok()?;
+        Some(content.trim().to_string())
+    }
+
+    pub(crate) fn render_env(&self) -> String {
+        format!("${}", self.env.clone().unwrap_or_default())
     }
 
     pub(crate) fn list_names(&self) -> Vec<String> {
@@ -275,8 +739,16 @@ impl FlagOptionParam {
         output
     }
 
+    /// Names including aliases, which are accepted but not offered by completion
+    pub(crate) fn list_all_names(&self) -> Vec<String> {
+        let mut output = self.list_names();
+        output.extend(self.aliases.iter().cloned());
+        output.extend(self.plus.iter().map(|v| format!("+{v}")));
+        output
+    }
+
     pub(crate) fn values_size(&self) -> usize {
-        if self.is_flag() {
+        if self.is_flag() || self.optional_value {
             0
         } else if self.multiple {
             9999
@@ -285,26 +757,44 @@ impl FlagOptionParam {
         }
     }
 
+    pub(crate) fn describe(&self) -> &str {
+        localize(&self.describe, &self.describe_locales)
+    }
+
     pub(crate) fn describe_head(&self) -> &str {
-        match self.describe.split_once('\n') {
+        let describe = self.describe();
+        match describe.split_once('\n') {
             Some((v, _)) => v,
-            None => self.describe.as_str(),
+            None => describe,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub(crate) struct PositionalParam {
     pub(crate) name: String,
     pub(crate) describe: String,
+    pub(crate) describe_locales: Locales,
     pub(crate) choices: Option<Vec<String>>,
+    /// Description of each choice, from `[a:Fast mode|b]`, empty when none has one
+    pub(crate) choices_describe: Vec<String>,
     pub(crate) choices_fn: Option<(String, bool)>,
+    pub(crate) choices_ignore_case: bool,
+    pub(crate) choices_any: bool,
+    pub(crate) choices_file: Option<String>,
+    pub(crate) range: Option<(i64, i64)>,
     pub(crate) multiple: bool,
+    pub(crate) unique: Option<UniqueMode>,
+    pub(crate) terminated: bool,
+    pub(crate) greedy: bool,
     pub(crate) required: bool,
     pub(crate) default: Option<String>,
     pub(crate) default_fn: Option<String>,
     pub(crate) value_name: Option<String>,
-    #[serde(skip_serializing)]
+    pub(crate) value_type: Option<ValueType>,
+    pub(crate) validate_fn: Option<String>,
+    pub(crate) examples: Vec<String>,
+    pub(crate) glob: bool,
     pub(crate) arg_value_name: String,
 }
 
@@ -313,13 +803,26 @@ impl PositionalParam {
         PositionalParam {
             name: arg.name.clone(),
             describe: describe.to_string(),
+            describe_locales: Locales::new(),
             choices: arg.choices,
+            choices_describe: arg.choices_describe,
             choices_fn: arg.choices_fn,
+            choices_ignore_case: arg.choices_ignore_case,
+            choices_any: arg.choices_any,
+            choices_file: arg.choices_file,
+            range: arg.range,
             multiple: arg.multiple,
+            unique: arg.unique,
+            terminated: arg.terminated,
+            greedy: arg.greedy,
             required: arg.required,
             default: arg.default,
             default_fn: arg.default_fn,
             value_name: value_name.map(|v| v.to_string()),
+            value_type: arg.value_types.first().copied().flatten(),
+            validate_fn: arg.validate_fn,
+            examples: arg.examples,
+            glob: arg.glob,
             arg_value_name: value_name
                 .or(Some(&arg.name))
                 .map(to_cobol_case)
@@ -331,21 +834,44 @@ impl PositionalParam {
         "@arg"
     }
 
-    #[allow(unused)]
+    pub(crate) fn to_json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        to_export_json(self, &POSITIONAL_OPTIONAL_KEYS, "arg_value_name")
+    }
+
     pub(crate) fn render(&self) -> String {
         let mut output = vec![];
-        let name = render_name(
-            &self.name,
-            &self.choices,
-            &self.choices_fn,
-            self.multiple,
-            self.required,
-            &self.default,
-            &self.default_fn,
-        );
-        output.push(name);
+        let name = if self.greedy {
+            format!("{}~*", self.name)
+        } else if self.terminated {
+            format!("{}~", self.name)
+        } else {
+            render_name(
+                &self.name,
+                &self.choices,
+                &self.choices_describe,
+                &self.choices_file,
+                &self.choices_fn,
+                self.choices_ignore_case,
+                self.choices_any,
+                self.multiple,
+                self.unique,
+                self.required,
+                &self.default,
+                &self.default_fn,
+            )
+        };
+        output.push(format!("{}{}", name, render_range(&self.range)));
         if let Some(value_name) = self.value_name.as_ref() {
-            output.push(format!("<{}>", value_name));
+            output.push(render_notation(value_name, self.value_type));
+        }
+        if let Some(validate_fn) = &self.validate_fn {
+            output.push(format!("validate:`{}`", validate_fn));
+        }
+        if !self.examples.is_empty() {
+            output.push(format!("examples:{}", self.examples.join(",")));
+        }
+        if self.glob {
+            output.push("expand:glob".into());
         }
         if !self.describe.is_empty() {
             output.push(self.describe.clone());
@@ -363,119 +889,368 @@ impl PositionalParam {
         }
     }
 
-    pub(crate) fn render_describe(&self) -> String {
-        render_describe(&self.describe, &self.default, &self.choices)
+    /// Generated help notes, e.g. `[default: a]` or `[required]`
+    pub(crate) fn render_annotations(&self) -> Vec<String> {
+        let mut output = render_annotations(&self.default, &self.choices, &self.range);
+        if self.required {
+            output.push("[required]".into());
+        }
+        output
     }
 
-    pub(crate) fn get_arg_value(&self, values: &[&str]) -> Option<ArgcValue> {
+    pub(crate) fn render_describe(&self, annotate: bool) -> String {
+        let annotations = if annotate {
+            self.render_annotations()
+        } else {
+            vec![]
+        };
+        render_describe(&render_plain_text(self.describe()), &annotations)
+    }
+
+    pub(crate) fn get_arg_value(&self, values: &[&str]) -> Option<(ArgcValue, ValueSource)> {
         let name = self.name.clone();
         if values.is_empty() {
             if let Some(value) = self.default.as_ref() {
-                return Some(ArgcValue::PositionalSingle(name, value.clone()));
+                let value = ArgcValue::PositionalSingle(name, value.clone());
+                return Some((value, ValueSource::Default));
             }
             if let Some(value) = self.default_fn.as_ref() {
-                return Some(ArgcValue::PositionalSingleFn(name, value.clone()));
+                let value = ArgcValue::PositionalSingleFn(name, value.clone());
+                return Some((value, ValueSource::DefaultFn));
             }
             return None;
         }
+        let value = if self.multiple {
+            let mut values: Vec<String> = values.iter().map(|v| self.normalize_choice(v)).collect();
+            if self.glob {
+                values = values.iter().flat_map(|v| expand_glob(v)).collect();
+            }
+            ArgcValue::PositionalMultiple(name, dedup_values(values, self.unique))
+        } else {
+            ArgcValue::PositionalSingle(name, self.normalize_choice(&must_get_first(values)))
+        };
+        Some((value, ValueSource::Cli))
+    }
+
+    /// An empty value for when the args don't set the param, so the variable exists under `set -u`
+    pub(crate) fn get_unset_value(&self) -> ArgcValue {
+        let name = self.name.clone();
         if self.multiple {
-            let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
-            Some(ArgcValue::PositionalMultiple(name, values))
+            ArgcValue::Multiple(name, vec![])
         } else {
-            Some(ArgcValue::PositionalSingle(name, must_get_first(values)))
+            ArgcValue::Single(name, String::new())
         }
     }
 
+    pub(crate) fn normalize_choice(&self, value: &str) -> String {
+        normalize_choice(&self.choices, self.choices_ignore_case, value)
+    }
+
+    pub(crate) fn describe(&self) -> &str {
+        localize(&self.describe, &self.describe_locales)
+    }
+
     pub(crate) fn describe_head(&self) -> &str {
-        match self.describe.split_once('\n') {
+        let describe = self.describe();
+        match describe.split_once('\n') {
             Some((v, _)) => v,
-            None => self.describe.as_str(),
+            None => describe,
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_name(
     name: &str,
     choices: &Option<Vec<String>>,
+    choices_describe: &[String],
+    choices_file: &Option<String>,
     choices_fn: &Option<(String, bool)>,
+    choices_ignore_case: bool,
+    choices_any: bool,
     multiple: bool,
+    unique: Option<UniqueMode>,
     required: bool,
     default: &Option<String>,
     default_fn: &Option<String>,
 ) -> String {
     let mut name = name.to_string();
-    if let Some(choices) = choices {
-        if let Some(ch) = get_modifer(required, multiple) {
-            name.push(ch)
-        }
+    let modifer = render_modifer(required, multiple, unique);
+    if let Some(choices_file) = choices_file {
+        name.push_str(&modifer);
+        let _ = write!(name, "[@{}]", choices_file);
+    } else if let Some(choices) = choices {
+        name.push_str(&modifer);
         let mut prefix = String::new();
+        if choices_ignore_case {
+            prefix.push('~');
+        }
         if default.is_some() {
             prefix.push('=');
         }
-        let values: Vec<String> = choices
-            .iter()
-            .map(|value| {
-                if value.chars().any(is_choice_value_terminate) {
-                    format!("\"{}\"", value)
-                } else {
-                    value.to_string()
-                }
-            })
-            .collect();
-        let choices_value = format!("[{}{}]", prefix, values.join("|"));
-        name.push_str(&choices_value);
+        let _ = write!(
+            name,
+            "[{}{}]",
+            prefix,
+            render_choices(choices, choices_describe, choices_any)
+        );
     } else if let Some((choices_fn, validate)) = choices_fn {
-        if let Some(ch) = get_modifer(required, multiple) {
-            name.push(ch)
-        }
+        name.push_str(&modifer);
         let validate_sign = if *validate { "" } else { "?" };
         let _ = write!(name, "[{}`{}`]", validate_sign, choices_fn);
     } else if let Some(default) = default {
-        let value = if default.chars().any(is_default_value_terminate) {
-            format!("\"{}\"", default)
+        // A bare `=@path` reads a file, so a literal `@` default is quoted
+        let value = if is_ambiguous_value(default) || default.starts_with('@') {
+            quote_value(default)
         } else {
             default.to_string()
         };
         let _ = write!(name, "={}", value);
     } else if let Some(default_fn) = default_fn {
         let _ = write!(name, "=`{}`", default_fn);
-    } else if let Some(ch) = get_modifer(required, multiple) {
-        name.push(ch)
+    } else {
+        name.push_str(&modifer);
     }
     name
 }
 
-fn get_modifer(required: bool, multiple: bool) -> Option<char> {
-    match (required, multiple) {
-        (true, true) => Some('+'),
-        (true, false) => Some('!'),
-        (false, true) => Some('*'),
-        (false, false) => None,
+/// Choices joined by `|`, quoting the ones that would end the list or read as `*`
+fn render_choices(choices: &[String], choices_describe: &[String], choices_any: bool) -> String {
+    let mut values: Vec<String> = choices
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let describe = choices_describe.get(i).filter(|v| !v.is_empty());
+            // Only a quoted choice can have a description
+            let mut value = if value.is_empty()
+                || describe.is_some()
+                || value.chars().any(is_choice_value_terminate)
+                || value == "*"
+                || value.starts_with(['=', '`', '"', '\''])
+            {
+                quote_value(value)
+            } else {
+                value.to_string()
+            };
+            match describe {
+                Some(describe)
+                    if describe.chars().any(is_choice_value_terminate)
+                        || describe.starts_with(['"', '\'']) =>
+                {
+                    let _ = write!(value, ":{}", quote_value(describe));
+                }
+                Some(describe) => {
+                    let _ = write!(value, ":{}", describe);
+                }
+                None => {}
+            }
+            value
+        })
+        .collect();
+    if choices_any {
+        values.push("*".into());
     }
+    values.join("|")
 }
 
-fn render_describe(
-    describe: &str,
-    default: &Option<String>,
-    choices: &Option<Vec<String>>,
-) -> String {
+/// A value that would read differently or not at all if written bare
+fn is_ambiguous_value(value: &str) -> bool {
+    value.is_empty()
+        || value.chars().any(is_default_value_terminate)
+        || value.starts_with(['`', '"', '\''])
+}
+
+/// Quote a value for a param definition, values are kept as written between the quotes
+fn quote_value(value: &str) -> String {
+    if value.contains('"') {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+fn render_notation(value_name: &str, value_type: Option<ValueType>) -> String {
+    match value_type {
+        Some(value_type) => format!("<{}:{}>", value_name, value_type.name()),
+        None => format!("<{}>", value_name),
+    }
+}
+
+fn render_modifer(required: bool, multiple: bool, unique: Option<UniqueMode>) -> String {
+    let mut output = match (required, multiple) {
+        (true, true) => "+",
+        (true, false) => "!",
+        (false, true) => "*",
+        (false, false) => "",
+    }
+    .to_string();
+    match unique {
+        Some(UniqueMode::Dedup) => output.push('^'),
+        Some(UniqueMode::Reject) => output.push_str("^^"),
+        None => {}
+    }
+    output
+}
+
+/// Append the annotations the describe doesn't already spell out
+fn render_describe(describe: &str, annotations: &[String]) -> String {
     let mut output = describe.to_string();
-    if let Some(default) = default.as_ref() {
+    for annotation in annotations {
+        let key = match annotation.split_once(':') {
+            Some((key, _)) => key,
+            None => annotation,
+        };
+        if describe.contains(key) {
+            continue;
+        }
         if !output.is_empty() {
             output.push(' ')
         }
-        output.push_str(&format!("[default: {}]", escape_shell_words(default)));
+        output.push_str(annotation);
+    }
+    output
+}
+
+fn render_annotations(
+    default: &Option<String>,
+    choices: &Option<Vec<String>>,
+    range: &Option<(i64, i64)>,
+) -> Vec<String> {
+    let mut output = vec![];
+    if let Some(default) = default.as_ref() {
+        output.push(format!("[default: {}]", escape_shell_words(default)));
     }
     if let Some(choices) = &choices.as_ref() {
-        if !output.is_empty() {
-            output.push(' ')
+        let mut values: Vec<String> = choices
+            .iter()
+            .take(MAX_CHOICES_IN_HELP)
+            .map(|v| escape_shell_words(v))
+            .collect();
+        if choices.len() > MAX_CHOICES_IN_HELP {
+            values.push("…".into());
+        }
+        output.push(format!("[possible values: {}]", values.join(", ")));
+    }
+    if let Some((start, end)) = range {
+        output.push(format!("[range: {start}..{end}]"));
+    }
+    output
+}
+
+fn render_range(range: &Option<(i64, i64)>) -> String {
+    match range {
+        Some((start, end)) => format!("[{start}..{end}]"),
+        None => String::new(),
+    }
+}
+
+/// Sample values offered by completion, the full list for small ranges
+pub(crate) fn range_sample(range: &(i64, i64), default: &Option<String>) -> Vec<String> {
+    let (start, end) = *range;
+    if end.abs_diff(start) < 20 {
+        return (start..=end).map(|v| v.to_string()).collect();
+    }
+    let mut output = vec![start.to_string()];
+    if let Some(default) = default {
+        output.push(default.clone());
+    }
+    output.push(end.to_string());
+    output
+}
+
+/// Check a value against an inclusive range
+pub(crate) fn in_range(range: &(i64, i64), value: &str) -> bool {
+    value
+        .parse::<i64>()
+        .map(|v| v >= range.0 && v <= range.1)
+        .unwrap_or_default()
+}
+
+/// Map a value to the declared casing of the choice it matches, e.g. `INFO` to `info`
+fn normalize_choice(choices: &Option<Vec<String>>, ignore_case: bool, value: &str) -> String {
+    if ignore_case {
+        if let Some(choice) = choices
+            .iter()
+            .flatten()
+            .find(|v| v.eq_ignore_ascii_case(value))
+        {
+            return choice.clone();
+        }
+    }
+    value.to_string()
+}
+
+/// Expand a pattern the shell left alone, keep it as is when nothing matches
+fn expand_glob(pattern: &str) -> Vec<String> {
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![pattern.to_string()];
+    }
+    let paths: Vec<String> = match glob::glob(pattern) {
+        Ok(paths) => paths
+            .filter_map(|v| v.ok())
+            .map(|v| v.display().to_string())
+            .collect(),
+        Err(_) => vec![],
+    };
+    if paths.is_empty() {
+        vec![pattern.to_string()]
+    } else {
+        paths
+    }
+}
+
+/// Keep the first occurrence of each value when the param is unique
+fn dedup_values(values: Vec<String>, unique: Option<UniqueMode>) -> Vec<String> {
+    if unique.is_none() {
+        return values;
+    }
+    let mut output: Vec<String> = vec![];
+    for value in values {
+        if !output.contains(&value) {
+            output.push(value);
         }
-        let values: Vec<String> = choices.iter().map(|v| escape_shell_words(v)).collect();
-        output.push_str(&format!("[possible values: {}]", values.join(", ")));
     }
     output
 }
 
+/// Find the first value repeated after choice normalization
+pub(crate) fn find_duplicate<'a>(
+    values: &[&'a str],
+    normalize: impl Fn(&str) -> String,
+) -> Option<&'a str> {
+    let mut seen = vec![];
+    for value in values {
+        let normalized = normalize(value);
+        if seen.contains(&normalized) {
+            return Some(value);
+        }
+        seen.push(normalized);
+    }
+    None
+}
+
+/// Serialize a param for export, dropping the internal field and the optional ones that are unset
+fn to_export_json<T: Serialize>(
+    param: &T,
+    optional_keys: &[&str],
+    internal_key: &str,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let mut value = serde_json::to_value(param)?;
+    if let Some(map) = value.as_object_mut() {
+        map.retain(|key, value| {
+            let unset = match value {
+                serde_json::Value::Null => true,
+                serde_json::Value::Bool(v) => !*v,
+                serde_json::Value::Array(v) => v.is_empty(),
+                serde_json::Value::Object(v) => v.is_empty(),
+                _ => false,
+            };
+            key != internal_key && !(unset && optional_keys.contains(&key.as_str()))
+        });
+    }
+    Ok(value)
+}
+
 fn must_get_first(value: &[&str]) -> String {
     if value.is_empty() {
         String::new()
@@ -1,20 +1,23 @@
-use crate::param::{FlagOptionParam, ParamData, PositionalParam};
+use crate::param::{FlagOptionParam, ParamAttr, ParamData, PositionalParam, UniqueMode, ValueType};
 use crate::utils::{is_choice_value_terminate, is_default_value_terminate};
 use crate::Result;
 use anyhow::bail;
+use indexmap::IndexMap;
 use nom::character::complete::one_of;
 use nom::{
     branch::alt,
-    bytes::complete::{escaped, tag, take_till, take_while1},
+    bytes::complete::{escaped, tag, take_till, take_till1, take_while1},
     character::{
-        complete::{anychar, char, satisfy, space0, space1},
+        complete::{char, digit1, satisfy, space0, space1},
         streaming::none_of,
     },
-    combinator::{eof, fail, map, not, opt, peek, rest, success},
+    combinator::{eof, fail, map, map_res, not, opt, peek, recognize, rest, success, verify},
     multi::{many0, many1, separated_list1},
     sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
 };
-#[derive(Debug, PartialEq, Eq, Clone)]
+use serde::{Deserialize, Serialize};
+use std::iter::Peekable;
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub(crate) struct Event {
     pub(crate) data: EventData,
     pub(crate) position: Position,
@@ -22,7 +25,16 @@ pub(crate) struct Event {
 
 pub(crate) type Position = usize;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Description variants keyed by locale, e.g. `ja` or `zh-CN`
+pub(crate) type Locales = IndexMap<String, String>;
+
+/// A value notation name with its optional type, e.g. `<N:int>`
+type Notation<'a> = (&'a str, Option<ValueType>);
+
+/// A choice with its description, empty when it has none, e.g. `"a":Fast mode`
+type Choice<'a> = (&'a str, &'a str);
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub(crate) enum EventData {
     /// Description
     Describe(String),
@@ -30,82 +42,383 @@ pub(crate) enum EventData {
     Version(String),
     /// Author info
     Author(String),
+    /// Program name shown in help and usage instead of the invoked one, e.g. `@name mycli`
+    Name(String),
     /// Define a subcommand, e.g. `@cmd A sub command`
     Cmd(String),
+    /// Translations of the preceding `@describe` or `@cmd` text, e.g. `#   @ja ...`
+    DescribeLocales(Locales),
+    /// Description in another language, e.g. `@describe[fr] Un outil`
+    DescribeLocale(String, String),
+    /// Subcommand description in another language, following its `@cmd`, e.g. `@cmd[fr] Déployer`
+    CmdLocale(String, String),
+    /// Start a help section for the following flags and options, or subcommands when placed before `@cmd`
+    Group(String),
+    /// What the command prints, shown in help, e.g. `@output json`
+    Output(String),
     /// Define alias for a subcommand, e.g. `@alias t,tst`
     Aliases(Vec<String>),
+    /// Define alias for a subcommand that is only offered by completion, e.g. `@completion-alias b`
+    CompletionAliases(Vec<String>),
+    /// Set a switch for the script or command, e.g. `@meta no-forward-positionals`
+    Meta(String, String),
     /// Define a flag or option parameter
-    FlagOption(FlagOptionParam),
+    FlagOption(Box<FlagOptionParam>),
     /// Define a positional parameter
-    Positional(PositionalParam),
+    Positional(Box<PositionalParam>),
     /// A shell function. e.g `function cmd()` or `cmd()`
     Func(String),
     /// Placeholder for unknown or invalid tag
     Unknown(String),
+    /// The `eval "$(argc --argc-eval ...)"` line, which runs the matched function right away
+    Eval,
+    /// A problem with the tag on the same line that doesn't stop the script from running
+    Warning(String),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Default)]
 pub(crate) enum EventScope {
+    #[default]
     Root,
     CmdStart,
     FnEnd,
 }
 
-impl Default for EventScope {
-    fn default() -> Self {
-        Self::Root
+/// Tokenize shell script
+pub(crate) fn parse(source: &str) -> Result<Vec<Event>> {
+    let mut result = vec![];
+    let lines = source_lines(source);
+    let doc_marker = find_root_meta(source, "doc-marker");
+    let mut lines = lines.iter().copied().enumerate().peekable();
+    while let Some((line_idx, line)) = lines.next() {
+        if is_eval_line(line) {
+            result.push(Event {
+                position: line_idx + 1,
+                data: EventData::Eval,
+            });
+        } else if let Some(events) =
+            parse_event(line, line_idx + 1, &mut lines, false, doc_marker.as_deref())?
+        {
+            result.extend(events);
+        }
     }
+    Ok(result)
 }
 
-/// Tokenize shell script
-pub(crate) fn parse(source: &str) -> Result<Vec<Event>> {
+/// A line like `eval "$(argc --argc-eval "$0" "$@")"`, with any spacing, quoting or path to argc
+fn is_eval_line(line: &str) -> bool {
+    line.trim_start()
+        .strip_prefix("eval")
+        .is_some_and(|v| v.starts_with(char::is_whitespace) && v.contains("--argc-eval"))
+}
+
+/// An event of the `@cmd` being read by `parse_lazy`, or the index of a param line left unparsed
+enum BlockItem {
+    Event(Box<Event>),
+    Param(usize),
+}
+
+/// Tokenize shell script for completion, which needs less than help or eval.
+/// Descriptions keep their first line only, unknown tags are dropped and the params
+/// of a subcommand are only parsed when one of its names is in `words`
+pub(crate) fn parse_lazy(source: &str, words: &[String]) -> Result<Vec<Event>> {
     let mut result = vec![];
-    let lines: Vec<&str> = source.lines().collect();
-    let mut line_idx = 0;
-    while line_idx < lines.len() {
-        let line = lines[line_idx];
-        let position = line_idx + 1;
-        match parse_line(line) {
-            Ok((_, maybe_token)) => {
-                if let Some(maybe_data) = maybe_token {
-                    if let Some(data) = maybe_data {
-                        let data = match data {
-                            EventData::Describe(mut text) => {
-                                line_idx += take_comment_lines(&lines, line_idx + 1, &mut text);
-                                EventData::Describe(text)
-                            }
-                            EventData::Cmd(mut text) => {
-                                line_idx += take_comment_lines(&lines, line_idx + 1, &mut text);
-                                EventData::Cmd(text)
-                            }
-                            EventData::FlagOption(mut param) => {
-                                line_idx +=
-                                    take_comment_lines(&lines, line_idx + 1, &mut param.describe);
-                                EventData::FlagOption(param)
-                            }
-                            EventData::Positional(mut param) => {
-                                line_idx +=
-                                    take_comment_lines(&lines, line_idx + 1, &mut param.describe);
-                                EventData::Positional(param)
-                            }
-                            v => v,
-                        };
-                        result.push(Event { position, data });
+    let mut pending: Option<Vec<BlockItem>> = None;
+    let all_lines = source_lines(source);
+    let doc_marker = find_root_meta(source, "doc-marker");
+    let doc_marker = doc_marker.as_deref();
+    let mut lines = all_lines.iter().copied().enumerate().peekable();
+    while let Some((line_idx, line)) = lines.next() {
+        if let Some(block) = pending.as_mut() {
+            if is_param_tag(line) {
+                block.push(BlockItem::Param(line_idx));
+                skip_comment_lines(&mut lines);
+                continue;
+            }
+        }
+        let Some(events) = parse_event(line, line_idx + 1, &mut lines, true, doc_marker)? else {
+            continue;
+        };
+        for event in events {
+            match &event.data {
+                EventData::Unknown(_) => continue,
+                EventData::Cmd(_) => {
+                    if let Some(block) = pending.take() {
+                        flush_block(&mut result, block, &all_lines, true, doc_marker)?;
+                    }
+                    pending = Some(vec![BlockItem::Event(Box::new(event))]);
+                }
+                EventData::Func(name) => {
+                    if let Some(mut block) = pending.take() {
+                        let needed = name == "main"
+                            || name.ends_with("::main")
+                            || is_block_named(&block, name, words);
+                        block.push(BlockItem::Event(Box::new(event)));
+                        flush_block(&mut result, block, &all_lines, needed, doc_marker)?;
                     } else {
-                        bail!("syntax error at line {}", position)
+                        result.push(event);
                     }
                 }
-            }
-            Err(err) => {
-                bail!("fail to parse at line {}, {}", position, err)
+                _ => match pending.as_mut() {
+                    Some(block) => block.push(BlockItem::Event(Box::new(event))),
+                    None => result.push(event),
+                },
             }
         }
-        line_idx += 1;
+    }
+    if let Some(block) = pending.take() {
+        flush_block(&mut result, block, &all_lines, true, doc_marker)?;
     }
     Ok(result)
 }
 
-fn parse_line(line: &str) -> nom::IResult<&str, Option<Option<EventData>>> {
+fn source_lines(source: &str) -> Vec<&str> {
+    // Editors on Windows may save the script with a byte order mark
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    source.lines().collect()
+}
+
+/// Turn a line into events, taking the comment lines that continue its description
+fn parse_event<'a>(
+    line: &str,
+    position: Position,
+    lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+    lazy: bool,
+    doc_marker: Option<&str>,
+) -> Result<Option<Vec<Event>>> {
+    if !maybe_event_line(line) {
+        return Ok(None);
+    }
+    let data = match parse_line(line) {
+        Ok((_, None)) => return Ok(None),
+        Ok((_, Some(Some(data)))) => data,
+        Ok((_, Some(None))) => bail!("syntax error at line {}", position),
+        Err(err) => bail!("fail to parse at line {}, {}", position, err),
+    };
+    let mut locales = Locales::new();
+    let data = match data {
+        EventData::Describe(mut text) => {
+            take_comment_lines(lines, &mut text, &mut locales, lazy, doc_marker);
+            EventData::Describe(text)
+        }
+        EventData::Cmd(mut text) => {
+            take_comment_lines(lines, &mut text, &mut locales, lazy, doc_marker);
+            EventData::Cmd(text)
+        }
+        EventData::DescribeLocale(locale, mut text) => {
+            take_comment_lines(lines, &mut text, &mut locales, lazy, doc_marker);
+            EventData::DescribeLocale(locale, text)
+        }
+        EventData::CmdLocale(locale, mut text) => {
+            take_comment_lines(lines, &mut text, &mut locales, lazy, doc_marker);
+            EventData::CmdLocale(locale, text)
+        }
+        EventData::FlagOption(mut param) => {
+            if param.choices.is_some() && !lazy {
+                check_choices(line, param.tag_name(), position)?;
+            }
+            take_comment_lines(
+                lines,
+                &mut param.describe,
+                &mut param.describe_locales,
+                lazy,
+                doc_marker,
+            );
+            EventData::FlagOption(param)
+        }
+        EventData::Positional(mut param) => {
+            if param.choices.is_some() && !lazy {
+                check_choices(line, param.tag_name(), position)?;
+            }
+            take_comment_lines(
+                lines,
+                &mut param.describe,
+                &mut param.describe_locales,
+                lazy,
+                doc_marker,
+            );
+            EventData::Positional(param)
+        }
+        v => v,
+    };
+    let warning = match &data {
+        EventData::FlagOption(param) if param.choices.is_some() && param.default.is_some() => {
+            check_choices_default_modifier(line, param.tag_name(), position)
+        }
+        EventData::Positional(param) if param.choices.is_some() && param.default.is_some() => {
+            check_choices_default_modifier(line, param.tag_name(), position)
+        }
+        _ => None,
+    };
+    let mut events = vec![Event { position, data }];
+    if let Some(message) = warning.filter(|_| !lazy) {
+        events.push(Event {
+            position,
+            data: EventData::Warning(message),
+        });
+    }
+    if !locales.is_empty() {
+        events.push(Event {
+            position,
+            data: EventData::DescribeLocales(locales),
+        });
+    }
+    Ok(Some(events))
+}
+
+/// Move the events of a `@cmd` to the result, parsing its params when they are needed
+fn flush_block(
+    result: &mut Vec<Event>,
+    block: Vec<BlockItem>,
+    all_lines: &[&str],
+    needed: bool,
+    doc_marker: Option<&str>,
+) -> Result<()> {
+    for item in block {
+        match item {
+            BlockItem::Event(event) => result.push(*event),
+            BlockItem::Param(line_idx) if needed => {
+                let mut lines = all_lines
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .skip(line_idx + 1)
+                    .peekable();
+                let line = all_lines[line_idx];
+                if let Some(events) = parse_event(line, line_idx + 1, &mut lines, true, doc_marker)?
+                {
+                    result.extend(events);
+                }
+            }
+            BlockItem::Param(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether the `@cmd` bound to the function `name` may be reached by `words`
+fn is_block_named(block: &[BlockItem], name: &str, words: &[String]) -> bool {
+    let name = name.rsplit("::").next().unwrap_or(name);
+    let mut names = vec![name];
+    for item in block {
+        if let BlockItem::Event(event) = item {
+            if let EventData::Aliases(list) | EventData::CompletionAliases(list) = &event.data {
+                names.extend(list.iter().map(|v| v.as_str()));
+            }
+        }
+    }
+    words.iter().any(|v| names.contains(&v.as_str()))
+}
+
+/// Cheap check for `@flag`, `@option` and `@arg` lines
+fn is_param_tag(line: &str) -> bool {
+    let Some(text) = line.strip_prefix('#') else {
+        return false;
+    };
+    let text = text.trim_start_matches('#').trim_start_matches([' ', '\t']);
+    text.strip_prefix('@')
+        .is_some_and(|v| v.starts_with("option") || v.starts_with("flag") || v.starts_with("arg"))
+}
+
+/// Find a root `@meta` value line by line, for when the whole script doesn't parse
+pub(crate) fn find_root_meta(source: &str, key: &str) -> Option<String> {
+    for line in source.lines().filter(|v| maybe_event_line(v)) {
+        match parse_line(line) {
+            Ok((_, Some(Some(EventData::Meta(name, value))))) if name == key => return Some(value),
+            Ok((_, Some(Some(EventData::Cmd(_) | EventData::Func(_))))) => break,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Cheap check to skip lines that can be neither a tag nor a function before running nom
+fn maybe_event_line(line: &str) -> bool {
+    if line.starts_with('#') {
+        line.contains('@')
+    } else {
+        line.contains('(') || line.trim_start().starts_with("function")
+    }
+}
+
+/// Warn that a choice default makes `!` and `+` act as no modifier and `*`
+fn check_choices_default_modifier(
+    line: &str,
+    tag_name: &str,
+    position: Position,
+) -> Option<String> {
+    let (_, modifier) = find_choices_default_modifier(line)?;
+    let effect = if modifier == '!' {
+        "is ignored"
+    } else {
+        "acts as `*`"
+    };
+    Some(format!(
+        "{}(line {}) has a choice default, so `{}` {} and the value stays optional",
+        tag_name, position, modifier, effect
+    ))
+}
+
+/// Index and char of the `!` or `+` written before a choice default like `[=a|b]`
+pub(crate) fn find_choices_default_modifier(line: &str) -> Option<(usize, char)> {
+    let idx = line.find('[')?;
+    let modifier = line[..idx]
+        .chars()
+        .last()
+        .filter(|v| matches!(v, '!' | '+'))?;
+    let choices = &line[idx + 1..];
+    if choices.starts_with('=') || choices.starts_with("~=") {
+        Some((idx - 1, modifier))
+    } else {
+        None
+    }
+}
+
+/// Reject empty and duplicate choices, pointing at their column in the line
+fn check_choices(line: &str, tag_name: &str, position: Position) -> Result<()> {
+    for (idx, _) in line.match_indices('[') {
+        let list = terminated(
+            preceded(
+                opt(char('~')),
+                alt((
+                    map(parse_choices_default, |(v, _, _)| v),
+                    map(parse_choices, |(v, _)| v),
+                )),
+            ),
+            char(']'),
+        )(&line[idx + 1..]);
+        let Ok((_, choices)) = list else {
+            continue;
+        };
+        let choices: Vec<&str> = choices.iter().map(|(v, _)| *v).collect();
+        for (i, choice) in choices.iter().enumerate() {
+            let offset = choice.as_ptr() as usize - line.as_ptr() as usize;
+            let column = line[..offset].chars().count() + 1;
+            let quoted = line[..offset].ends_with(['\'', '"']);
+            if choice.is_empty() && !quoted {
+                bail!(
+                    "{}(line {}) has empty choice at column {}",
+                    tag_name,
+                    position,
+                    column
+                );
+            }
+            if choices[..i].contains(choice) {
+                bail!(
+                    "{}(line {}) has duplicate choice '{}' at column {}",
+                    tag_name,
+                    position,
+                    choice,
+                    column
+                );
+            }
+        }
+        break;
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_line(line: &str) -> nom::IResult<&str, Option<Option<EventData>>> {
     alt((map(alt((parse_tag, parse_fn)), Some), success(None)))(line)
 }
 
@@ -130,11 +443,13 @@ fn parse_fn_no_keyword(input: &str) -> nom::IResult<&str, &str> {
 
 fn parse_tag(input: &str) -> nom::IResult<&str, Option<EventData>> {
     preceded(
-        tuple((many1(char('#')), space0, char('@'))),
+        tuple((parse_hashes, space0, char('@'))),
         alt((
+            parse_tag_text_locale,
             parse_tag_text,
             parse_tag_param,
             parse_tag_alias,
+            parse_tag_meta,
             parse_tag_unknown,
         )),
     )(input)
@@ -143,7 +458,15 @@ fn parse_tag(input: &str) -> nom::IResult<&str, Option<EventData>> {
 fn parse_tag_text(input: &str) -> nom::IResult<&str, Option<EventData>> {
     map(
         pair(
-            alt((tag("describe"), tag("version"), tag("author"), tag("cmd"))),
+            alt((
+                tag("describe"),
+                tag("version"),
+                tag("author"),
+                tag("name"),
+                tag("cmd"),
+                tag("group"),
+                tag("output"),
+            )),
             parse_tail,
         ),
         |(tag, text)| {
@@ -152,7 +475,29 @@ fn parse_tag_text(input: &str) -> nom::IResult<&str, Option<EventData>> {
                 "describe" => EventData::Describe(text),
                 "version" => EventData::Version(text),
                 "author" => EventData::Author(text),
+                "name" => EventData::Name(text),
                 "cmd" => EventData::Cmd(text),
+                "group" => EventData::Group(text),
+                "output" => EventData::Output(text),
+                _ => unreachable!(),
+            })
+        },
+    )(input)
+}
+
+// Parse `@describe[fr] text` and `@cmd[fr] text`
+fn parse_tag_text_locale(input: &str) -> nom::IResult<&str, Option<EventData>> {
+    map(
+        tuple((
+            alt((tag("describe"), tag("cmd"))),
+            delimited(char('['), parse_locale_name, char(']')),
+            parse_tail,
+        )),
+        |(tag, locale, text)| {
+            let (locale, text) = (locale.to_string(), text.to_string());
+            Some(match tag {
+                "describe" => EventData::DescribeLocale(locale, text),
+                "cmd" => EventData::CmdLocale(locale, text),
                 _ => unreachable!(),
             })
         },
@@ -164,15 +509,15 @@ fn parse_tag_param(input: &str) -> nom::IResult<&str, Option<EventData>> {
     let arg = alt((
         map(
             preceded(pair(tag("flag"), space1), parse_flag_param),
-            |param| Some(EventData::FlagOption(param)),
+            |param| Some(EventData::FlagOption(Box::new(param))),
         ),
         map(
             preceded(pair(tag("option"), space1), parse_option_param),
-            |param| Some(EventData::FlagOption(param)),
+            |param| Some(EventData::FlagOption(Box::new(param))),
         ),
         map(
             preceded(pair(tag("arg"), space1), parse_positional_param),
-            |param| Some(EventData::Positional(param)),
+            |param| Some(EventData::Positional(Box::new(param))),
         ),
     ));
     preceded(check, alt((arg, success(None))))(input)
@@ -180,16 +525,28 @@ fn parse_tag_param(input: &str) -> nom::IResult<&str, Option<EventData>> {
 
 fn parse_tag_alias(input: &str) -> nom::IResult<&str, Option<EventData>> {
     map(
-        pair(tag("alias"), preceded(space1, parse_name_list)),
+        pair(
+            alt((tag("alias"), tag("completion-alias"))),
+            preceded(space1, parse_name_list),
+        ),
         |(tag, list)| {
+            let list = list.iter().map(|v| v.to_string()).collect();
             Some(match tag {
-                "alias" => EventData::Aliases(list.iter().map(|v| v.to_string()).collect()),
+                "alias" => EventData::Aliases(list),
+                "completion-alias" => EventData::CompletionAliases(list),
                 _ => unreachable!(),
             })
         },
     )(input)
 }
 
+fn parse_tag_meta(input: &str) -> nom::IResult<&str, Option<EventData>> {
+    map(
+        preceded(pair(tag("meta"), space1), pair(parse_name, parse_tail)),
+        |(key, value)| Some(EventData::Meta(key.to_string(), value.to_string())),
+    )(input)
+}
+
 fn parse_tag_unknown(input: &str) -> nom::IResult<&str, Option<EventData>> {
     map(parse_name, |v| Some(EventData::Unknown(v.to_string())))(input)
 }
@@ -205,19 +562,16 @@ fn parse_with_long_option_param(input: &str) -> nom::IResult<&str, FlagOptionPar
         tuple((
             parse_short,
             preceded(space0, alt((tag("--"), tag("-")))),
-            alt((
-                parse_param_modifer_choices_default,
-                parse_param_modifer_choices_fn,
-                parse_param_modifer_choices,
-                parse_param_assign_fn,
-                parse_param_assign,
-                parse_param_modifer,
-            )),
+            parse_option_modifer,
+            parse_long_aliases,
             parse_zero_or_many_value_notations,
+            parse_param_attrs,
             parse_tail,
         )),
-        |(short, dashes, arg, value_names, describe)| {
-            FlagOptionParam::new(arg, describe, short, false, dashes, &value_names)
+        |(short, dashes, mut arg, aliases, notations, attrs, describe)| {
+            arg.set_attrs(attrs);
+            let value_names = arg.set_notations(notations);
+            FlagOptionParam::new(arg, describe, short, false, dashes, &aliases, &value_names)
         },
     )(input)
 }
@@ -228,24 +582,17 @@ fn parse_no_long_option_param(input: &str) -> nom::IResult<&str, FlagOptionParam
         tuple((
             preceded(
                 pair(space0, tag("-")),
-                preceded(
-                    verify_single_char,
-                    alt((
-                        parse_param_modifer_choices_default,
-                        parse_param_modifer_choices_fn,
-                        parse_param_modifer_choices,
-                        parse_param_assign_fn,
-                        parse_param_assign,
-                        parse_param_modifer,
-                    )),
-                ),
+                preceded(verify_single_char, parse_option_modifer),
             ),
             parse_zero_or_many_value_notations,
+            parse_param_attrs,
             parse_tail,
         )),
-        |(arg, value_names, describe)| {
+        |(mut arg, notations, attrs, describe)| {
+            arg.set_attrs(attrs);
+            let value_names = arg.set_notations(notations);
             let short = arg.name.chars().next();
-            FlagOptionParam::new(arg, describe, short, false, "", &value_names)
+            FlagOptionParam::new(arg, describe, short, false, "", &[], &value_names)
         },
     )(input)
 }
@@ -254,21 +601,69 @@ fn parse_no_long_option_param(input: &str) -> nom::IResult<&str, FlagOptionParam
 fn parse_positional_param(input: &str) -> nom::IResult<&str, PositionalParam> {
     map(
         tuple((
-            alt((
-                parse_param_modifer_choices_default,
-                parse_param_modifer_choices_fn,
-                parse_param_modifer_choices,
-                parse_param_assign_fn,
-                parse_param_assign,
-                parse_param_modifer,
-            )),
+            parse_positional_modifer,
             parse_zero_or_one_value_notation,
+            parse_param_attrs,
             parse_tail,
         )),
-        |(arg, value_name, describe)| PositionalParam::new(arg, describe, value_name),
+        |(mut arg, notation, attrs, describe)| {
+            arg.set_attrs(attrs);
+            let value_name = arg.set_notations(notation.into_iter().collect()).pop();
+            PositionalParam::new(arg, describe, value_name)
+        },
     )(input)
 }
 
+// Parse the name of an `@option` with its modifiers
+fn parse_option_modifer(input: &str) -> nom::IResult<&str, ParamData> {
+    // Choices, ranges and `[map]` all need a `[`, skip trying them on plain params
+    if !input.contains('[') {
+        return alt((
+            parse_param_modifer_optional_value,
+            parse_param_assign_fn,
+            parse_param_assign_file,
+            parse_param_assign,
+            parse_param_modifer,
+        ))(input);
+    }
+    alt((
+        parse_param_modifer_range,
+        parse_param_modifer_map,
+        parse_param_modifer_optional_value,
+        parse_param_modifer_choices_default,
+        parse_param_modifer_choices_fn,
+        parse_param_modifer_choices_file,
+        parse_param_modifer_choices,
+        parse_param_assign_fn,
+        parse_param_assign_file,
+        parse_param_assign,
+        parse_param_modifer,
+    ))(input)
+}
+
+// Parse the name of an `@arg` with its modifiers
+fn parse_positional_modifer(input: &str) -> nom::IResult<&str, ParamData> {
+    if !input.contains('[') {
+        return alt((
+            parse_param_assign_fn,
+            parse_param_assign,
+            parse_param_modifer_terminated,
+            parse_param_modifer,
+        ))(input);
+    }
+    alt((
+        parse_param_modifer_range,
+        parse_param_modifer_choices_default,
+        parse_param_modifer_choices_fn,
+        parse_param_modifer_choices_file,
+        parse_param_modifer_choices,
+        parse_param_assign_fn,
+        parse_param_assign,
+        parse_param_modifer_terminated,
+        parse_param_modifer,
+    ))(input)
+}
+
 // Parse `@flag`
 fn parse_flag_param(input: &str) -> nom::IResult<&str, FlagOptionParam> {
     alt((parse_with_long_flag_param, parse_no_long_flag_param))(input)
@@ -277,13 +672,19 @@ fn parse_flag_param(input: &str) -> nom::IResult<&str, FlagOptionParam> {
 fn parse_with_long_flag_param(input: &str) -> nom::IResult<&str, FlagOptionParam> {
     map(
         tuple((
+            parse_plus_name,
             parse_short,
             preceded(space0, alt((tag("--"), tag("-")))),
             parse_long_flag_and_asterisk,
+            parse_long_aliases,
+            parse_param_attrs,
             parse_tail,
         )),
-        |(short, dashes, arg, describe)| {
-            FlagOptionParam::new(arg, describe, short, true, dashes, &[])
+        |(plus, short, dashes, mut arg, aliases, attrs, describe)| {
+            arg.set_attrs(attrs);
+            let mut param = FlagOptionParam::new(arg, describe, short, true, dashes, &aliases, &[]);
+            param.plus = plus.map(|v| v.to_string());
+            param
         },
     )(input)
 }
@@ -293,11 +694,13 @@ fn parse_no_long_flag_param(input: &str) -> nom::IResult<&str, FlagOptionParam>
     map(
         tuple((
             preceded(pair(space0, tag("-")), parse_short_flag_and_asterisk),
+            parse_param_attrs,
             parse_tail,
         )),
-        |(arg, describe)| {
+        |(mut arg, attrs, describe)| {
+            arg.set_attrs(attrs);
             let short = arg.name.chars().next();
-            FlagOptionParam::new(arg, describe, short, true, "", &[])
+            FlagOptionParam::new(arg, describe, short, true, "", &[], &[])
         },
     )(input)
 }
@@ -326,24 +729,51 @@ fn parse_short_flag_and_asterisk(input: &str) -> nom::IResult<&str, ParamData> {
     })(input)
 }
 
-// Parse `str!` `str*` `str+` `str`
+// Parse `str!` `str*` `str+` `str`, multiple ones can be followed by `^` or `^^`
 fn parse_param_modifer(input: &str) -> nom::IResult<&str, ParamData> {
-    alt((
-        map(terminated(parse_param_name, tag("!")), |mut arg| {
-            arg.required = true;
-            arg
-        }),
-        map(terminated(parse_param_name, tag("*")), |mut arg| {
-            arg.multiple = true;
+    map(
+        pair(
+            parse_param_name,
+            opt(alt((
+                map(char('!'), |_| (true, false, None)),
+                map(preceded(char('*'), parse_unique), |unique| {
+                    (false, true, unique)
+                }),
+                map(preceded(char('+'), parse_unique), |unique| {
+                    (true, true, unique)
+                }),
+            ))),
+        ),
+        |(mut arg, modifier)| {
+            if let Some((required, multiple, unique)) = modifier {
+                arg.required = required;
+                arg.multiple = multiple;
+                arg.unique = unique;
+            }
             arg
-        }),
-        map(terminated(parse_param_name, tag("+")), |mut arg| {
-            arg.required = true;
+        },
+    )(input)
+}
+
+// Parse `str~` `str~*`
+fn parse_param_modifer_terminated(input: &str) -> nom::IResult<&str, ParamData> {
+    map(
+        pair(parse_param_name, preceded(tag("~"), opt(tag("*")))),
+        |(mut arg, greedy)| {
             arg.multiple = true;
+            arg.terminated = true;
+            arg.greedy = greedy.is_some();
             arg
-        }),
-        parse_param_name,
-    ))(input)
+        },
+    )(input)
+}
+
+// Parse `^^` `^`
+fn parse_unique(input: &str) -> nom::IResult<&str, Option<UniqueMode>> {
+    opt(alt((
+        map(tag("^^"), |_| UniqueMode::Reject),
+        map(tag("^"), |_| UniqueMode::Dedup),
+    )))(input)
 }
 
 // Parse `str=value`
@@ -357,8 +787,30 @@ fn parse_param_assign(input: &str) -> nom::IResult<&str, ParamData> {
     )(input)
 }
 
+// Parse `str=@path` or `str!=@path`
+fn parse_param_assign_file(input: &str) -> nom::IResult<&str, ParamData> {
+    if !name_suffix(input).trim_start_matches('!').starts_with("=@") {
+        return fail(input);
+    }
+    map(
+        tuple((
+            parse_param_name,
+            opt(char('!')),
+            preceded(tag("=@"), take_till1(is_default_value_terminate)),
+        )),
+        |(mut arg, required, path)| {
+            arg.required = required.is_some();
+            arg.default_file = Some(path.to_string());
+            arg
+        },
+    )(input)
+}
+
 // Parse str=`value`
 fn parse_param_assign_fn(input: &str) -> nom::IResult<&str, ParamData> {
+    if !name_suffix(input).starts_with("=`") {
+        return fail(input);
+    }
     map(
         separated_pair(parse_param_name, char('='), parse_value_fn),
         |(mut arg, value)| {
@@ -369,13 +821,22 @@ fn parse_param_assign_fn(input: &str) -> nom::IResult<&str, ParamData> {
 }
 
 fn parse_param_modifer_choices_default(input: &str) -> nom::IResult<&str, ParamData> {
+    if !bracket_body(input).trim_start_matches('~').starts_with('=') {
+        return fail(input);
+    }
     map(
         pair(
             parse_param_modifer,
-            delimited(char('['), parse_choices_default, char(']')),
+            delimited(
+                char('['),
+                pair(opt(char('~')), parse_choices_default),
+                char(']'),
+            ),
         ),
-        |(mut arg, (choices, default))| {
-            arg.choices = Some(choices.iter().map(|v| v.to_string()).collect());
+        |(mut arg, (ignore_case, (choices, default, any)))| {
+            arg.set_choices(choices);
+            arg.choices_ignore_case = ignore_case.is_some();
+            arg.choices_any = any;
             arg.required = false;
             arg.default = default.map(|v| v.to_string());
             arg
@@ -387,16 +848,21 @@ fn parse_param_modifer_choices(input: &str) -> nom::IResult<&str, ParamData> {
     map(
         pair(
             parse_param_modifer,
-            delimited(char('['), parse_choices, char(']')),
+            delimited(char('['), pair(opt(char('~')), parse_choices), char(']')),
         ),
-        |(mut arg, choices)| {
-            arg.choices = Some(choices.iter().map(|v| v.to_string()).collect());
+        |(mut arg, (ignore_case, (choices, any)))| {
+            arg.set_choices(choices);
+            arg.choices_ignore_case = ignore_case.is_some();
+            arg.choices_any = any;
             arg
         },
     )(input)
 }
 
 fn parse_param_modifer_choices_fn(input: &str) -> nom::IResult<&str, ParamData> {
+    if !bracket_body(input).trim_start_matches('?').starts_with('`') {
+        return fail(input);
+    }
     map(
         pair(
             parse_param_modifer,
@@ -409,11 +875,160 @@ fn parse_param_modifer_choices_fn(input: &str) -> nom::IResult<&str, ParamData>
     )(input)
 }
 
+// Parse `str[1..10]` `str=3[1..10]`
+fn parse_param_modifer_range(input: &str) -> nom::IResult<&str, ParamData> {
+    if !bracket_body(input).starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+        return fail(input);
+    }
+    map(
+        pair(
+            alt((parse_param_assign_int, parse_param_modifer)),
+            delimited(char('['), parse_range, char(']')),
+        ),
+        |(mut arg, range)| {
+            arg.range = Some(range);
+            arg
+        },
+    )(input)
+}
+
+// Parse `str=3`
+fn parse_param_assign_int(input: &str) -> nom::IResult<&str, ParamData> {
+    map(
+        separated_pair(parse_param_name, char('='), recognize(parse_int)),
+        |(mut arg, value)| {
+            arg.default = Some(value.to_string());
+            arg
+        },
+    )(input)
+}
+
+// Parse `1..10` `1..=10`, both ends are inclusive
+fn parse_range(input: &str) -> nom::IResult<&str, (i64, i64)> {
+    verify(
+        map(
+            tuple((parse_int, tag(".."), opt(char('=')), parse_int)),
+            |(start, _, _, end)| (start, end),
+        ),
+        |(start, end)| start <= end,
+    )(input)
+}
+
+fn parse_int(input: &str) -> nom::IResult<&str, i64> {
+    map_res(recognize(pair(opt(char('-')), digit1)), str::parse)(input)
+}
+
+// Parse `str?` `str?=a` `str?[a|b]` `str?=a[a|b]`
+fn parse_param_modifer_optional_value(input: &str) -> nom::IResult<&str, ParamData> {
+    if !name_suffix(input).starts_with('?') {
+        return fail(input);
+    }
+    map(
+        tuple((
+            terminated(parse_param_name, char('?')),
+            opt(preceded(
+                char('='),
+                alt((
+                    parse_quoted_string,
+                    take_till1(|c: char| c == '[' || c.is_whitespace()),
+                )),
+            )),
+            opt(delimited(
+                char('['),
+                pair(opt(char('~')), parse_choices),
+                char(']'),
+            )),
+        )),
+        |(mut arg, bare_value, choices)| {
+            arg.optional_value = true;
+            arg.bare_value = bare_value.map(|v| v.to_string());
+            if let Some((ignore_case, (choices, any))) = choices {
+                arg.set_choices(choices);
+                arg.choices_ignore_case = ignore_case.is_some();
+                arg.choices_any = any;
+            }
+            arg
+        },
+    )(input)
+}
+
+// Parse `str*[map]`
+fn parse_param_modifer_map(input: &str) -> nom::IResult<&str, ParamData> {
+    if !bracket_body(input).starts_with("map]") {
+        return fail(input);
+    }
+    map(
+        terminated(verify(parse_param_modifer, |v| v.multiple), tag("[map]")),
+        |mut arg| {
+            arg.map = true;
+            arg
+        },
+    )(input)
+}
+
+// Parse `str[@path]`
+fn parse_param_modifer_choices_file(input: &str) -> nom::IResult<&str, ParamData> {
+    if !bracket_body(input).starts_with('@') {
+        return fail(input);
+    }
+    map(
+        pair(
+            parse_param_modifer,
+            delimited(
+                char('['),
+                preceded(
+                    char('@'),
+                    take_till1(|c: char| c == ']' || c.is_whitespace()),
+                ),
+                char(']'),
+            ),
+        ),
+        |(mut arg, choices_file)| {
+            arg.choices_file = Some(choices_file.to_string());
+            arg
+        },
+    )(input)
+}
+
+/// The text after the name of a param, where its modifier starts
+fn name_suffix(input: &str) -> &str {
+    input.trim_start_matches(is_name_char)
+}
+
+/// The text after the first `[`, where the choices, range or `[map]` of a param start.
+/// Those parsers check it to fail fast before building the param
+fn bracket_body(input: &str) -> &str {
+    input.find('[').map_or("", |idx| &input[idx + 1..])
+}
+
 // Parse `str`
 fn parse_param_name(input: &str) -> nom::IResult<&str, ParamData> {
     map(parse_name, ParamData::new)(input)
 }
 
+// Zero or many alias names after the long name, e.g. `--out`, never a `-1` or `-x` in the describe
+fn parse_long_aliases(input: &str) -> nom::IResult<&str, Vec<&str>> {
+    many0(preceded(
+        space1,
+        terminated(
+            recognize(pair(tag("--"), parse_name)),
+            peek(alt((space1, eof))),
+        ),
+    ))(input)
+}
+
+// Parse `+name`, which has to start with a letter so `++` and `+1` are never taken for it
+fn parse_plus_name(input: &str) -> nom::IResult<&str, Option<&str>> {
+    let plus = delimited(
+        char('+'),
+        verify(parse_name, |v: &str| {
+            v.starts_with(|c: char| c.is_ascii_alphabetic())
+        }),
+        space1,
+    );
+    opt(plus)(input)
+}
+
 // Parse `-s`
 fn parse_short(input: &str) -> nom::IResult<&str, Option<char>> {
     let short = delimited(char('-'), satisfy(is_short_char), peek(space1));
@@ -421,43 +1036,105 @@ fn parse_short(input: &str) -> nom::IResult<&str, Option<char>> {
 }
 
 // Zero or many '<FOO>'
-fn parse_zero_or_many_value_notations(input: &str) -> nom::IResult<&str, Vec<&str>> {
+fn parse_zero_or_many_value_notations(input: &str) -> nom::IResult<&str, Vec<Notation<'_>>> {
     many0(parse_value_notation)(input)
 }
 
 // Zero or one '<FOO>'
-fn parse_zero_or_one_value_notation(input: &str) -> nom::IResult<&str, Option<&str>> {
+fn parse_zero_or_one_value_notation(input: &str) -> nom::IResult<&str, Option<Notation<'_>>> {
     opt(parse_value_notation)(input)
 }
 
-// Parse '<FOO>'
-fn parse_value_notation(input: &str) -> nom::IResult<&str, &str> {
+// Parse '<FOO>' or '<FOO:int>'
+fn parse_value_notation(input: &str) -> nom::IResult<&str, Notation<'_>> {
     preceded(space0, delimited(char('<'), parse_notation_text, char('>')))(input)
 }
 
-// Parse `a|b|c`
-fn parse_choices(input: &str) -> nom::IResult<&str, Vec<&str>> {
-    map(separated_list1(char('|'), parse_choice_value), |choices| {
-        choices
+// Zero or many attributes, e.g. `env:NAME env:only examples:a,b`. Each has a `key:`, so
+// a description starting with a plain word is never taken for one
+fn parse_param_attrs(input: &str) -> nom::IResult<&str, Vec<ParamAttr>> {
+    many0(preceded(
+        space1,
+        terminated(parse_param_attr, peek(alt((space1, eof)))),
+    ))(input)
+}
+
+fn parse_param_attr(input: &str) -> nom::IResult<&str, ParamAttr> {
+    alt((
+        map(parse_attr_keyword("env:only"), |_| ParamAttr::EnvOnly),
+        map(preceded(tag("env:"), parse_env_name), |v| {
+            ParamAttr::Env(v.to_string())
+        }),
+        map(
+            preceded(
+                tag("examples:"),
+                separated_list1(
+                    char(','),
+                    take_till1(|c: char| c == ',' || c.is_whitespace()),
+                ),
+            ),
+            |v: Vec<&str>| ParamAttr::Examples(v.iter().map(|v| v.to_string()).collect()),
+        ),
+        map(preceded(tag("validate:"), parse_value_fn), |v| {
+            ParamAttr::ValidateFn(v.to_string())
+        }),
+        map(parse_attr_keyword("scope:global"), |_| ParamAttr::Global),
+        map(preceded(tag("terminal:"), parse_value_fn), |v| {
+            ParamAttr::Terminal(Some(v.to_string()))
+        }),
+        map(parse_attr_keyword("terminal:true"), |_| {
+            ParamAttr::Terminal(None)
+        }),
+        map(parse_attr_keyword("expand:glob"), |_| ParamAttr::Glob),
+    ))(input)
+}
+
+/// An attribute without value, like `env:only`, must be a whole word
+fn parse_attr_keyword<'a>(
+    keyword: &'static str,
+) -> impl FnMut(&'a str) -> nom::IResult<&'a str, &'a str> {
+    terminated(tag(keyword), peek(alt((space1, eof))))
+}
+
+// Parse `a|b|c` `a|b|*`
+fn parse_choices(input: &str) -> nom::IResult<&str, (Vec<Choice<'_>>, bool)> {
+    map(separated_list1(char('|'), parse_choice), |choices| {
+        split_choices_any(input, choices)
     })(input)
 }
 
-// Parse `=a|b|c`
-fn parse_choices_default(input: &str) -> nom::IResult<&str, (Vec<&str>, Option<&str>)> {
+// Parse `=a|b|c` `=a|b|*`
+fn parse_choices_default(input: &str) -> nom::IResult<&str, (Vec<Choice<'_>>, Option<&str>, bool)> {
     map(
         tuple((
             char('='),
-            parse_choice_value,
-            many1(preceded(char('|'), parse_choice_value)),
+            parse_choice,
+            many1(preceded(char('|'), parse_choice)),
         )),
         |(_, head, tail)| {
             let mut choices = vec![head];
             choices.extend(tail);
-            (choices, Some(head))
+            let (choices, any) = split_choices_any(input, choices);
+            (choices, Some(head.0), any)
         },
     )(input)
 }
 
+/// Take off a trailing unquoted `*`, which accepts any value besides the listed ones
+fn split_choices_any<'a>(input: &str, mut choices: Vec<Choice<'a>>) -> (Vec<Choice<'a>>, bool) {
+    let any = match choices.last() {
+        Some(&(last, "")) if choices.len() > 1 && last == "*" => {
+            let offset = last.as_ptr() as usize - input.as_ptr() as usize;
+            input[..offset].ends_with('|')
+        }
+        _ => false,
+    };
+    if any {
+        choices.pop();
+    }
+    (choices, any)
+}
+
 fn parse_tail(input: &str) -> nom::IResult<&str, &str> {
     alt((
         eof,
@@ -477,6 +1154,10 @@ fn parse_name(input: &str) -> nom::IResult<&str, &str> {
     take_while1(is_name_char)(input)
 }
 
+fn parse_env_name(input: &str) -> nom::IResult<&str, &str> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(input)
+}
+
 fn parse_default_value(input: &str) -> nom::IResult<&str, &str> {
     alt((parse_quoted_string, take_till(is_default_value_terminate)))(input)
 }
@@ -485,6 +1166,24 @@ fn parse_value_fn(input: &str) -> nom::IResult<&str, &str> {
     delimited(char('`'), parse_fn_name, char('`'))(input)
 }
 
+// Parse `a` `a:b` `"a":Fast mode`, only a quoted value can have a description
+// so unquoted values like `localhost:8080` keep their `:`
+fn parse_choice(input: &str) -> nom::IResult<&str, Choice<'_>> {
+    alt((
+        map(
+            pair(
+                parse_quoted_string,
+                preceded(
+                    char(':'),
+                    alt((parse_quoted_string, take_till(is_choice_value_terminate))),
+                ),
+            ),
+            |(value, describe)| (value, describe),
+        ),
+        map(parse_choice_value, |value| (value, "")),
+    ))(input)
+}
+
 fn parse_choice_value(input: &str) -> nom::IResult<&str, &str> {
     if input.starts_with('=') || input.starts_with('`') {
         return fail(input);
@@ -506,42 +1205,55 @@ fn parse_quoted_string(input: &str) -> nom::IResult<&str, &str> {
     alt((single, double))(input)
 }
 
-fn parse_notation_text(input: &str) -> nom::IResult<&str, &str> {
-    let (_, size) = notation_text(input, 1)?;
-    Ok((&input[size - 1..], &input[0..size - 1]))
+fn parse_notation_text(input: &str) -> nom::IResult<&str, Notation<'_>> {
+    let (rest, size) = notation_text(input)?;
+    let text = &input[0..size];
+    let notation = text
+        .rsplit_once(':')
+        .and_then(|(name, kind)| Some((name, Some(ValueType::from_name(kind)?))))
+        .unwrap_or((text, None));
+    Ok((rest, notation))
 }
 
+// Parse the leading `#`s of a comment without collecting them
+fn parse_hashes(input: &str) -> nom::IResult<&str, &str> {
+    take_while1(|c: char| c == '#')(input)
+}
+
+// Parse the leading `#`s of a comment line that isn't a tag, returning them
 fn parse_normal_comment(input: &str) -> nom::IResult<&str, &str> {
     alt((
-        map(tuple((many1(char('#')), space0, eof)), |_| ""),
+        map(tuple((parse_hashes, space0, eof)), |(v, _, _)| v),
         map(
             tuple((
-                many1(char('#')),
+                parse_hashes,
                 opt(one_of(" \t")),
                 not(pair(space0, char('@'))),
             )),
-            |_| "",
+            |(v, _, _)| v,
         ),
     ))(input)
 }
 
-fn notation_text(input: &str, balances: usize) -> nom::IResult<&str, usize> {
-    let (i1, c1) = anychar(input)?;
-    match c1 {
-        '<' => {
-            let (i2, count) = notation_text(i1, balances + 1)?;
-            Ok((i2, count + 1))
-        }
-        '>' => {
-            if balances == 1 {
-                Ok((i1, 1))
-            } else {
-                let (i2, count) = notation_text(i1, balances - 1)?;
-                Ok((i2, count + 1))
+/// Byte length of the text before the `>` that balances the `<` preceding `input`
+fn notation_text(input: &str) -> nom::IResult<&str, usize> {
+    let mut balances = 1;
+    for (idx, c) in input.char_indices() {
+        match c {
+            '<' => balances += 1,
+            '>' => {
+                balances -= 1;
+                if balances == 0 {
+                    return Ok((&input[idx..], idx));
+                }
             }
+            _ => {}
         }
-        _ => notation_text(i1, balances).map(|(i3, v)| (i3, 1 + v)),
     }
+    Err(nom::Err::Error(nom::error::Error::new(
+        "",
+        nom::error::ErrorKind::Eof,
+    )))
 }
 
 fn verify_single_char(input: &str) -> nom::IResult<&str, &str> {
@@ -590,19 +1302,97 @@ fn is_short_char(c: char) -> bool {
     c.is_ascii() && is_not_fn_name_char(c) && !matches!(c, '-')
 }
 
-fn take_comment_lines(lines: &[&str], idx: usize, output: &mut String) -> usize {
-    let mut count = 0;
-    for line in lines.iter().skip(idx) {
-        if let Ok((text, _)) = parse_normal_comment(line) {
-            output.push('\n');
-            output.push_str(text);
-            count += 1;
+/// Append the comment lines that follow a tag to its description. With `@meta doc-marker`,
+/// only lines starting with exactly that many `#`s are taken and other comment lines are skipped
+fn take_comment_lines<'a>(
+    lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+    output: &mut String,
+    locales: &mut Locales,
+    lazy: bool,
+    doc_marker: Option<&str>,
+) {
+    let mut locale: Option<String> = None;
+    while let Some((_, line)) = lines.peek() {
+        if let Ok((_, (name, text))) = parse_locale_comment(line) {
+            locales.insert(name.to_string(), text.to_string());
+            locale = Some(name.to_string());
+        } else if let Ok((text, hashes)) = parse_normal_comment(line) {
+            if doc_marker.is_some_and(|v| v != hashes) {
+                lines.next();
+                continue;
+            }
+            let text = unescape_leading_at(text);
+            let (output, text) = match &locale {
+                Some(name) => (locales.get_mut(name).unwrap(), text.trim_start()),
+                None => (&mut *output, text.as_str()),
+            };
+            // Completion only shows the first line
+            if !lazy || output.trim().is_empty() {
+                output.push('\n');
+                output.push_str(text);
+            }
         } else {
             break;
         }
+        lines.next();
+    }
+    trim_in_place(output);
+    for text in locales.values_mut() {
+        trim_in_place(text);
     }
-    *output = output.trim().to_string();
-    count
+}
+
+/// Skip the comment lines that `take_comment_lines` would take
+pub(crate) fn skip_comment_lines<'a>(lines: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>) {
+    while let Some((_, line)) = lines.peek() {
+        if parse_locale_comment(line).is_err() && parse_normal_comment(line).is_err() {
+            break;
+        }
+        lines.next();
+    }
+}
+
+fn trim_in_place(text: &mut String) {
+    text.truncate(text.trim_end().len());
+    let start = text.len() - text.trim_start().len();
+    if start > 0 {
+        text.drain(..start);
+    }
+}
+
+/// `\@` starts a description line with a literal `@`, which would otherwise begin a tag
+fn unescape_leading_at(text: &str) -> String {
+    let trimmed = text.trim_start();
+    match trimmed.strip_prefix("\\@") {
+        Some(rest) => format!("{}@{}", &text[..text.len() - trimmed.len()], rest),
+        None => text.to_string(),
+    }
+}
+
+// Parse `#   @ja text`, locale names have two lowercase letters and an optional region
+fn parse_locale_comment(input: &str) -> nom::IResult<&str, (&str, &str)> {
+    preceded(
+        tuple((parse_hashes, space1, char('@'))),
+        pair(
+            parse_locale_name,
+            alt((preceded(space1, rest), map(eof, |_| ""))),
+        ),
+    )(input)
+}
+
+fn parse_locale_name(input: &str) -> nom::IResult<&str, &str> {
+    recognize(pair(
+        verify(take_while1(|c: char| c.is_ascii_lowercase()), |v: &str| {
+            v.len() == 2
+        }),
+        opt(pair(
+            one_of("-_"),
+            verify(
+                take_while1(|c: char| c.is_ascii_alphanumeric()),
+                |v: &str| (2..=4).contains(&v.len()),
+            ),
+        )),
+    ))(input)
 }
 
 #[cfg(test)]
@@ -683,6 +1473,24 @@ mod tests {
     #[test]
     fn test_parse_with_long_option_arg() {
         assert_parse_option_arg!("-f --foo=a <FOO> A foo option");
+        assert_parse_option_arg!("--count <N:int>");
+        assert_parse_option_arg!("-D*[map] <KEY=VALUE>");
+        assert_parse_option_arg!("--env+[map]");
+        assert_parse_option_arg!("--color?");
+        assert_parse_option_arg!("--color?[always|never|auto]");
+        assert_parse_option_arg!("--color?=always[~always|never|auto] <WHEN>");
+        assert_parse_option_arg!("-c?=1");
+        assert_parse_option_arg!("--color?[\"a|b\"|c]");
+        assert_parse_option_arg!("--mode[\"a\":Fast mode|\"b\":\"Safe | slow\"|c]");
+        assert_parse_option_arg!("--mode[=\"a\":Fast mode|b:c]");
+        assert_parse_option_arg!("--color?=\"\"");
+        assert_parse_option_arg!("--foo=\"\"");
+        assert_parse_option_arg!("--foo=\"`a`\"");
+        assert_parse_option_arg!("--foo='\"a'");
+        assert_parse_option_arg!("--foo[\"\"|\"=a\"]");
+        assert_parse_option_arg!("--size <W:int> <H:float>");
+        assert_parse_option_arg!("--config <CFG:file> <OUT:dir>");
+        assert_parse_option_arg!("--addr <HOST:PORT>");
         assert_parse_option_arg!("--foo!");
         assert_parse_option_arg!("--foo+");
         assert_parse_option_arg!("--foo*");
@@ -710,6 +1518,40 @@ mod tests {
         assert_parse_option_arg!("--foo <>");
         assert_parse_option_arg!("--foo <abc def>");
         assert_parse_option_arg!("--foo <<abc def>>");
+        assert_parse_option_arg!("--foo env:FOO");
+        assert_parse_option_arg!("--foo <FOO> env:FOO env:only A foo option");
+        assert_parse_option_arg!("--foo env:only");
+        assert_parse_option_arg!("--foo env:ONLY_FOO");
+        assert_parse_option_arg!("--profile <NAME> env:PROFILE scope:global");
+        assert_parse_option_arg!("-o --output --out <FILE> Output file");
+        assert_parse_option_arg!("--output --out --out-file");
+        assert_parse_option_arg!("--level[~debug|info]");
+        assert_parse_option_arg!("--level[~=info|debug]");
+        assert_parse_option_arg!("--country[@choices/countries.txt]");
+        assert_parse_option_arg!("--format[json|yaml|*]");
+        assert_parse_option_arg!("--format[~=json|yaml|*]");
+        assert_parse_option_arg!("--format[json|'*']", "--format[json|\"*\"]");
+        assert_parse_option_arg!("--format?[json|yaml|*]");
+        assert_parse_option_arg!("--country*[@countries.txt]");
+        assert_parse_option_arg!("--retries[1..10]");
+        assert_parse_option_arg!("--retries=3[1..10]");
+        assert_parse_option_arg!("--retries*[-5..5]");
+        assert_parse_option_arg!("--retries[1..=10]", "--retries[1..10]");
+        assert_parse_option_arg!("--http1.1[a|b]");
+        assert_parse_option_arg!("--tag*^");
+        assert_parse_option_arg!("--tag+^^ <TAG>");
+        assert_parse_option_arg!("--tag*^[a|b]");
+        assert_parse_option_arg!("--http1.1[=a|b]");
+        assert_parse_option_arg!("--http1.1=x");
+        assert_parse_option_arg!("--http1.1=`_fn`");
+        assert_parse_option_arg!("--http1.1* <VER>");
+        assert_parse_option_arg!("--http1.1! <VER> env:HTTP11");
+        assert_parse_option_arg!("--foo* validate:`_check` A foo option");
+        assert_parse_option_arg!("--branch examples:main,develop A branch");
+        assert_parse_option_arg!("--branch <NAME> env:BRANCH examples:main Pick");
+        assert_parse_option_arg!("--token=@/run/secrets/token");
+        assert_parse_option_arg!("--token!=@/run/secrets/token <TOKEN> API token");
+        assert_parse_option_arg!("--at=\"@home\"");
     }
 
     #[test]
@@ -744,6 +1586,43 @@ mod tests {
         assert_parse_option_arg!("-foo <<abc def>>");
     }
 
+    #[test]
+    fn test_parse_with_long_option_arg_symbol_short() {
+        assert_parse_option_arg!("-0 --level <N> Compression level");
+        assert_parse_option_arg!("-# --hash <HASH>");
+        assert_parse_option_arg!("-: --sep=, <SEP>");
+        assert_parse_option_arg!("-. --ext*[a|b]");
+        assert_parse_option_arg!("-9 --jobs![1..9] <N:int>");
+        assert_parse_option_arg!("-0 -level <N>");
+    }
+
+    #[test]
+    fn test_parse_attr_words_in_describe() {
+        let describe = |data| parse_option_param(data).unwrap().1.describe;
+        assert_eq!(describe("--token env-only settings"), "env-only settings");
+        let param = parse_option_param("--token env:only").unwrap().1;
+        assert!(param.env_only);
+        let describe = |data| parse_positional_param(data).unwrap().1.describe;
+        assert_eq!(describe("files* glob of the files"), "glob of the files");
+        assert_eq!(describe("name glob name"), "glob name");
+        let param = parse_flag_param("--foo global settings are applied")
+            .unwrap()
+            .1;
+        assert!(!param.global);
+        assert_eq!(param.describe, "global settings are applied");
+        let param = parse_option_param("--bar terminal output width").unwrap().1;
+        assert!(!param.terminal);
+        assert_eq!(param.describe, "terminal output width");
+        let param = parse_option_param("--offset -1 means the last line")
+            .unwrap()
+            .1;
+        assert!(param.aliases.is_empty());
+        assert_eq!(param.describe, "-1 means the last line");
+        let param = parse_flag_param("--quiet -q for short").unwrap().1;
+        assert!(param.aliases.is_empty());
+        assert_eq!(param.describe, "-q for short");
+    }
+
     #[test]
     fn test_parse_no_long_option_arg() {
         assert_parse_option_arg!("-f");
@@ -763,9 +1642,16 @@ mod tests {
         assert_parse_flag_arg!("-f --foo A foo flag");
         assert_parse_flag_arg!("-. --hidden");
         assert_parse_flag_arg!("--http1.1");
+        assert_parse_flag_arg!("+x -x --xtrace Print commands");
+        assert_parse_flag_arg!("+color --color --colour");
         assert_parse_flag_arg!("--foo A foo flag");
         assert_parse_flag_arg!("--foo");
         assert_parse_flag_arg!("--foo*");
+        assert_parse_flag_arg!("--foo env:FOO env:only A foo flag");
+        assert_parse_flag_arg!("--verbose scope:global Print more");
+        assert_parse_flag_arg!("--list-plugins terminal:`_list_plugins` List plugins");
+        assert_parse_flag_arg!("--dump-config terminal:true");
+        assert_parse_flag_arg!("--quiet --silent A quiet flag");
     }
 
     #[test]
@@ -791,6 +1677,12 @@ mod tests {
 
     #[test]
     fn test_parse_positional_arg() {
+        assert_parse_positional_arg!("country+[@countries.txt]");
+        assert_parse_positional_arg!("num![0..3]");
+        assert_parse_positional_arg!("format*[json|yaml|*]");
+        assert_parse_positional_arg!("file*^");
+        assert_parse_positional_arg!("cmd~ Command to run");
+        assert_parse_positional_arg!("enabled <ENABLED:bool>");
         assert_parse_positional_arg!("foo <FOO> A foo arg");
         assert_parse_positional_arg!("a.b");
         assert_parse_positional_arg!("foo");
@@ -812,6 +1704,9 @@ mod tests {
         assert_parse_positional_arg!("foo*[a|b]");
         assert_parse_positional_arg!("foo*[`_foo`]");
         assert_parse_positional_arg!("foo*[=a|b]");
+        assert_parse_positional_arg!("foo+ <FILE> validate:`_check` A foo arg");
+        assert_parse_positional_arg!("files* <FILE> expand:glob Files to read");
+        assert_parse_positional_arg!("host examples:localhost,example.com");
     }
 
     #[test]
@@ -819,9 +1714,22 @@ mod tests {
         assert_token!("# @describe A demo cli", Describe, "A demo cli");
         assert_token!("# @version 1.0.0", Version, "1.0.0");
         assert_token!("# @author Somebody", Author, "Somebody");
+        assert_token!("# @name mycli", Name, "mycli");
         assert_token!("# @cmd A subcommand", Cmd, "A subcommand");
-        assert_token!("# @alias tst", Aliases, vec!["tst"]);
-        assert_token!("# @alias t,tst", Aliases, vec!["t", "tst"]);
+        assert_token!("# @cmd", Cmd, "");
+        assert_token!("# @cmd  ", Cmd, "");
+        assert_token!("# @describe", Describe, "");
+        assert_token!("# @group Output options", Group, "Output options");
+        assert_token!("# @output JSON lines", Output, "JSON lines");
+        assert_token!("# @alias tst", Aliases, ["tst"]);
+        assert_token!("# @alias t,tst", Aliases, ["t", "tst"]);
+        assert_eq!(
+            parse_line("# @completion-alias b,bd").unwrap().1,
+            Some(Some(EventData::CompletionAliases(vec![
+                "b".to_string(),
+                "bd".to_string()
+            ])))
+        );
         assert_token!("# @flag -f --foo", FlagOption);
         assert_token!("# @option -f --foo", FlagOption);
         assert_token!("# @arg foo", Positional);
@@ -843,7 +1751,417 @@ mod tests {
         assert_token!("function foo:bar", Func, "foo:bar");
         assert_token!("function foo.bar", Func, "foo.bar");
         assert_token!("function foo@bar", Func, "foo@bar");
+        assert_eq!(
+            parse_line("# @meta no-forward-positionals").unwrap().1,
+            Some(Some(EventData::Meta(
+                "no-forward-positionals".into(),
+                "".into()
+            )))
+        );
+        assert_eq!(
+            parse_line("# @meta key some value").unwrap().1,
+            Some(Some(EventData::Meta("key".into(), "some value".into())))
+        );
         assert_token!("foo=bar", Ignore);
         assert_token!("#!/bin/bash", Ignore);
     }
+
+    #[test]
+    fn test_parse_multibyte_notation() {
+        assert_parse_option_arg!("--name <名前> A name");
+        assert_parse_option_arg!("--dir <<é>> A dir");
+        assert_parse_positional_arg!("file <ファイル>");
+        assert_token!("# @option --a <中>$)\"中>0,", Error);
+        assert_token!("# @option --a <é", FlagOption);
+        assert_eq!(
+            parse("# @arg a<[!中>*[").unwrap_err().to_string(),
+            "syntax error at line 1"
+        );
+    }
+
+    #[test]
+    fn test_parse_describe_locales() {
+        let source = r#"
+# @cmd Run the build
+# with cache
+#   @ja ビルドを実行する
+#   キャッシュ付き
+#   @zh-CN 运行构建
+# @arg target
+"#;
+        let events = parse(source).unwrap();
+        assert_eq!(
+            events[0].data,
+            EventData::Cmd("Run the build\nwith cache".into())
+        );
+        let mut locales = Locales::new();
+        locales.insert("ja".into(), "ビルドを実行する\nキャッシュ付き".into());
+        locales.insert("zh-CN".into(), "运行构建".into());
+        assert_eq!(events[1].data, EventData::DescribeLocales(locales));
+        assert!(matches!(events[2].data, EventData::Positional(_)));
+    }
+
+    #[test]
+    fn test_parse_text_tag_locale() {
+        assert_eq!(
+            parse_line("# @describe[fr] Un outil de démonstration")
+                .unwrap()
+                .1,
+            Some(Some(EventData::DescribeLocale(
+                "fr".into(),
+                "Un outil de démonstration".into()
+            )))
+        );
+        assert_eq!(
+            parse_line("# @cmd[zh-CN] 运行构建").unwrap().1,
+            Some(Some(EventData::CmdLocale(
+                "zh-CN".into(),
+                "运行构建".into()
+            )))
+        );
+        assert_token!("# @describe[french] Un outil", Unknown, "describe");
+        let events = parse("# @cmd[fr] Construire\n# avec cache\n# @arg target").unwrap();
+        assert_eq!(
+            events[0].data,
+            EventData::CmdLocale("fr".into(), "Construire\navec cache".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_escaped_at() {
+        let source = r#"
+# @describe A demo cli
+# \@see the docs
+#   \@ja is not a locale
+#   \\@ keeps the rest
+# @see
+"#;
+        assert_eq!(
+            parse(source).unwrap()[0].data,
+            EventData::Describe(
+                "A demo cli\n@see the docs\n  @ja is not a locale\n  \\\\@ keeps the rest".into()
+            )
+        );
+        assert_eq!(
+            parse(source).unwrap()[1].data,
+            EventData::Unknown("see".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_bom() {
+        let source = "\u{feff}#!/bin/bash\n# @describe A demo cli\n";
+        let events = parse(source).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, EventData::Describe("A demo cli".into()));
+        assert_eq!(events[0].position, 2);
+    }
+
+    #[test]
+    fn test_parse_skip_lines() {
+        let source = "echo '# @cmd'\n  function foo {\n  bar () {\n# @flag --all\n#   More help\n";
+        let events = parse(source).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].data, EventData::Func("foo".into()));
+        assert_eq!(events[1].data, EventData::Func("bar".into()));
+        assert_eq!(events[2].position, 4);
+    }
+
+    #[test]
+    fn test_parse_eval_line() {
+        let source = r#"
+eval "$(argc --argc-eval "$0" "$@")"
+  eval   "$( /opt/bin/argc --argc-eval "$0" "$@" )"
+eval `"$ARGC" --argc-eval $0 "$@"`
+# eval "$(argc --argc-eval "$0" "$@")"
+echo eval "$(argc --argc-eval "$0" "$@")"
+evaluate --argc-eval
+"#;
+        let events = parse(source).unwrap();
+        let positions: Vec<Position> = events
+            .iter()
+            .filter(|v| v.data == EventData::Eval)
+            .map(|v| v.position)
+            .collect();
+        assert_eq!(positions, [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parse_lazy() {
+        let source = r#"
+# @flag --verbose
+# @unknown
+# @cmd Build it
+# More about build
+# @alias b
+# @option --target
+#   The target
+#   to build
+build() { :; }
+# @cmd
+# @option --port
+serve() { :; }
+"#;
+        let words: Vec<String> = vec!["prog".into(), "b".into()];
+        let events = parse_lazy(source, &words).unwrap();
+        let names: Vec<String> = events
+            .iter()
+            .filter_map(|v| match &v.data {
+                EventData::FlagOption(param) => Some(param.describe.clone()),
+                EventData::Cmd(text) => Some(text.clone()),
+                EventData::Unknown(name) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["", "Build it", "The target", ""]);
+        assert_eq!(events.len(), 7);
+        assert!(events.windows(2).all(|v| v[0].position < v[1].position));
+    }
+
+    mod roundtrip {
+        use super::*;
+        use proptest::prelude::*;
+        use proptest::strategy::Union;
+
+        fn value() -> impl Strategy<Value = String> {
+            prop_oneof![
+                "[a-z0-9._/-]{1,6}",
+                "[a-zA-Z0-9 ._:/@|'=`\\[\\]-]{0,8}".prop_map(|v| format!("\"{v}\"")),
+            ]
+        }
+
+        /// `[a|b]`, `default` allows the `[=a|b]` form
+        fn choices(default: bool) -> impl Strategy<Value = String> {
+            let value = prop_oneof![
+                "[a-z0-9._-]{1,5}",
+                "[a-z :|\\[\\]]{0,5}".prop_map(|v| format!("\"{v}\"")),
+            ];
+            let describe = prop::option::of(prop_oneof![
+                "[a-zA-Z][a-zA-Z ]{0,5}",
+                "[a-z :|\\]]{1,5}".prop_map(|v| format!("\"{v}\"")),
+            ]);
+            let choice = (value, describe).prop_map(|(value, describe)| match describe {
+                Some(describe) if value.starts_with('"') => format!("{value}:{describe}"),
+                Some(describe) => format!("\"{value}\":{describe}"),
+                None => value,
+            });
+            (
+                prop::collection::vec(choice, 1..4),
+                any::<bool>(),
+                any::<bool>(),
+                any::<bool>(),
+            )
+                .prop_map(move |(values, ignore_case, with_default, any)| {
+                    format!(
+                        "[{}{}{}{}]",
+                        if ignore_case { "~" } else { "" },
+                        if default && with_default { "=" } else { "" },
+                        values.join("|"),
+                        if any { "|*" } else { "" }
+                    )
+                })
+        }
+
+        /// Everything that can follow the name: a modifier, a default, choices or a range.
+        /// Only options have `=@path` and `*[map]`
+        fn name_tail(option: bool) -> impl Strategy<Value = String> {
+            let modifier = || prop::sample::select(vec!["", "!", "*", "+", "*^", "+^^"]);
+            let mut forms = vec![
+                modifier().prop_map(|v| v.to_string()).boxed(),
+                (modifier(), choices(true))
+                    .prop_map(|(modifier, choices)| format!("{modifier}{choices}"))
+                    .boxed(),
+                (
+                    modifier(),
+                    prop::sample::select(vec!["[`_choices`]", "[?`_choices`]"]),
+                )
+                    .prop_map(|(modifier, choices)| format!("{modifier}{choices}"))
+                    .boxed(),
+                modifier()
+                    .prop_map(|v| format!("{v}[@choices.txt]"))
+                    .boxed(),
+                value().prop_map(|v| format!("={v}")).boxed(),
+                Just("=`_default`".to_string()).boxed(),
+                prop_oneof![
+                    modifier().prop_map(|v| v.to_string()),
+                    Just("=3".to_string())
+                ]
+                .prop_map(|v| format!("{v}[1..5]"))
+                .boxed(),
+            ];
+            if option {
+                forms.push(
+                    prop::sample::select(vec!["=@token.txt", "!=@token.txt"])
+                        .prop_map(|v| v.to_string())
+                        .boxed(),
+                );
+                forms.push(
+                    prop::sample::select(vec!["*", "+", "*^"])
+                        .prop_map(|v| format!("{v}[map]"))
+                        .boxed(),
+                );
+            }
+            Union::new(forms)
+        }
+
+        fn name() -> impl Strategy<Value = String> {
+            "[a-z][a-z0-9]{0,3}(-[a-z0-9]{1,3})?"
+        }
+
+        fn notations() -> impl Strategy<Value = String> {
+            let notation = (
+                "[A-Z]{1,4}",
+                prop::sample::select(vec!["", ":int", ":bool"]),
+            )
+                .prop_map(|(name, kind)| format!(" <{name}{kind}>"));
+            prop::collection::vec(notation, 0..3).prop_map(|v| v.concat())
+        }
+
+        fn attrs() -> impl Strategy<Value = String> {
+            (
+                prop::option::of("[A-Z]{1,5}"),
+                any::<bool>(),
+                prop::option::of(Just(" validate:`_check`")),
+                prop::option::of("[a-z]{1,3}(,[a-z]{1,3})?"),
+                any::<bool>(),
+                prop::option::of(prop::sample::select(vec![
+                    " terminal:true",
+                    " terminal:`_exit`",
+                ])),
+            )
+                .prop_map(|(env, env_only, validate, examples, global, terminal)| {
+                    let mut output = String::new();
+                    if let Some(env) = env {
+                        output.push_str(&format!(" env:{env}"));
+                        if env_only {
+                            output.push_str(" env:only");
+                        }
+                    }
+                    output.push_str(validate.unwrap_or_default());
+                    if let Some(examples) = examples {
+                        output.push_str(&format!(" examples:{examples}"));
+                    }
+                    if global {
+                        output.push_str(" scope:global");
+                    }
+                    output.push_str(terminal.unwrap_or_default());
+                    output
+                })
+        }
+
+        fn describe() -> impl Strategy<Value = String> {
+            prop::option::of("[A-Z][a-z]{0,4}( [a-z]{1,4}){0,2}")
+                .prop_map(|v| v.map(|v| format!(" {v}")).unwrap_or_default())
+        }
+
+        fn option() -> impl Strategy<Value = String> {
+            let head = prop_oneof![
+                (prop::option::of("[a-z]"), name()).prop_map(|(short, name)| match short {
+                    Some(short) => format!("-{short} --{name}"),
+                    None => format!("--{name}"),
+                }),
+                "[a-z][a-z0-9]{0,3}".prop_map(|v| format!("-{v}")),
+            ];
+            let optional = (prop::option::of(value()), prop::option::of(choices(false))).prop_map(
+                |(bare, choices)| {
+                    let bare = bare.map(|v| format!("={v}")).unwrap_or_default();
+                    format!("?{bare}{}", choices.unwrap_or_default())
+                },
+            );
+            let tail = prop_oneof![4 => name_tail(true), 1 => optional];
+            (head, tail, notations(), attrs(), describe()).prop_map(
+                |(head, tail, notations, attrs, describe)| {
+                    format!("{head}{tail}{notations}{attrs}{describe}")
+                },
+            )
+        }
+
+        fn flag() -> impl Strategy<Value = String> {
+            (
+                prop::option::of("[a-z]"),
+                name(),
+                prop::option::of(Just("*")),
+                attrs(),
+                describe(),
+            )
+                .prop_map(|(short, name, multiple, attrs, describe)| {
+                    let short = short.map(|v| format!("-{v} ")).unwrap_or_default();
+                    format!(
+                        "{short}--{name}{}{attrs}{describe}",
+                        multiple.unwrap_or_default()
+                    )
+                })
+        }
+
+        fn positional() -> impl Strategy<Value = String> {
+            let tail = prop_oneof![
+                8 => name_tail(false),
+                1 => Just("~".into()),
+                1 => Just("~*".into()),
+            ];
+            let glob = prop::option::of(Just(" expand:glob"));
+            let notation = prop::option::of("[A-Z]{1,4}(:int)?")
+                .prop_map(|v| v.map(|v| format!(" <{v}>")).unwrap_or_default());
+            (name(), tail, notation, glob, describe()).prop_map(
+                |(name, tail, notation, glob, describe)| {
+                    format!(
+                        "{name}{tail}{notation}{}{describe}",
+                        glob.unwrap_or_default()
+                    )
+                },
+            )
+        }
+
+        /// Parse a whole tag line, `None` when the line isn't a valid param
+        fn parse_param(tag: &str, text: &str) -> Option<EventData> {
+            match parse_line(&format!("# @{tag} {text}")) {
+                Ok((
+                    "",
+                    Some(Some(data @ (EventData::FlagOption(_) | EventData::Positional(_)))),
+                )) => Some(data),
+                _ => None,
+            }
+        }
+
+        fn render(data: &EventData) -> String {
+            match data {
+                EventData::FlagOption(param) => param.render(),
+                EventData::Positional(param) => param.render(),
+                _ => unreachable!(),
+            }
+        }
+
+        fn assert_roundtrip(tag: &str, text: &str) -> std::result::Result<(), TestCaseError> {
+            let Some(param) = parse_param(tag, text) else {
+                return Err(TestCaseError::reject("not a valid param"));
+            };
+            let rendered = render(&param);
+            let reparsed = parse_param(tag, &rendered);
+            prop_assert_eq!(
+                reparsed.as_ref(),
+                Some(&param),
+                "`{}` renders as `{}`",
+                text,
+                rendered
+            );
+            Ok(())
+        }
+
+        proptest! {
+            #[test]
+            fn option_render_roundtrip(text in option()) {
+                assert_roundtrip("option", &text)?;
+            }
+
+            #[test]
+            fn flag_render_roundtrip(text in flag()) {
+                assert_roundtrip("flag", &text)?;
+            }
+
+            #[test]
+            fn positional_render_roundtrip(text in positional()) {
+                assert_roundtrip("arg", &text)?;
+            }
+        }
+    }
 }
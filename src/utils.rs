@@ -1,29 +1,167 @@
-use convert_case::{Boundary, Converter, Pattern};
+use indexmap::IndexMap;
 use std::{
     collections::HashMap,
     env,
     path::{Path, PathBuf},
     process, thread,
 };
+use unicode_width::UnicodeWidthStr;
 use which::which;
 
 /// Transform into upper case string with an underscore between words. `foo-bar` => `FOO-BAR`
 pub fn to_cobol_case(value: &str) -> String {
-    Converter::new()
-        .set_pattern(Pattern::Uppercase)
-        .set_delim("-")
-        .set_boundaries(&[Boundary::Underscore, Boundary::LowerUpper, Boundary::Hyphen])
-        .convert(value)
+    let mut output = String::with_capacity(value.len());
+    let mut in_word = false;
+    let mut split = false;
+    let mut prev_lower = false;
+    for c in value.chars() {
+        if c == '_' || c == '-' {
+            split |= in_word;
+            in_word = false;
+            prev_lower = false;
+            continue;
+        }
+        if prev_lower && c.is_uppercase() && in_word {
+            split = true;
+        }
+        if split {
+            output.push('-');
+            split = false;
+        }
+        in_word = true;
+        prev_lower = c.is_lowercase();
+        output.extend(c.to_uppercase());
+    }
+    output
 }
 
-pub fn hyphens_to_underscores(name: &str) -> String {
-    name.replace('-', "_")
+/// Turn a param name into a legal shell identifier. `http1.1` => `http1_1`
+pub fn to_var_name(name: &str) -> String {
+    name.replace(['-', '.'], "_")
 }
 
 pub fn escape_shell_words(value: &str) -> String {
     shell_words::quote(value).to_string()
 }
 
+/// Escape text for an unquoted heredoc, which expands `$`, `` ` `` and `\`
+pub fn escape_heredoc(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('`', "\\`")
+}
+
+/// Pick the description variant for `ARGC_LOCALE`, `ARGC_LANG` or `LANG`, falling back to the default text
+pub(crate) fn localize<'a>(describe: &'a str, locales: &'a IndexMap<String, String>) -> &'a str {
+    if locales.is_empty() {
+        return describe;
+    }
+    get_locale()
+        .and_then(|locale| match_locale(locales, &locale))
+        .unwrap_or(describe)
+}
+
+fn get_locale() -> Option<String> {
+    ["ARGC_LOCALE", "ARGC_LANG", "LANG"]
+        .iter()
+        .find_map(|name| {
+            let value = env::var(name).ok()?;
+            let value = value.split(['.', '@']).next()?;
+            if value.is_empty() || value == "C" || value == "POSIX" {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        })
+}
+
+/// Match `zh_CN` against `zh-CN` first and then the bare language `zh`
+fn match_locale<'a>(locales: &'a IndexMap<String, String>, locale: &str) -> Option<&'a str> {
+    let normalize = |v: &str| v.replace('-', "_").to_lowercase();
+    let locale = normalize(locale);
+    let lang = locale.split('_').next().unwrap_or_default();
+    locales
+        .iter()
+        .find(|(k, _)| normalize(k) == locale)
+        .or_else(|| locales.iter().find(|(k, _)| normalize(k) == lang))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Columns taken by the text in a terminal, CJK and emoji take two, combining marks none
+pub(crate) fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Strip light markdown for terminal help: emphasis, inline code and `*`/`+` bullets
+pub(crate) fn render_plain_text(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim_start();
+            let indent = &line[..line.len() - content.len()];
+            match content
+                .strip_prefix("* ")
+                .or_else(|| content.strip_prefix("+ "))
+            {
+                Some(item) => format!("{indent}- {}", strip_inline_markdown(item)),
+                None => format!("{indent}{}", strip_inline_markdown(content)),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn strip_inline_markdown(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            if let Some(end) = chars[i + 1..].iter().position(|v| *v == '`') {
+                output.extend(&chars[i + 1..i + 1 + end]);
+                i += end + 2;
+                continue;
+            }
+        } else if c == '*' || c == '_' {
+            let marker = if chars.get(i + 1) == Some(&c) { 2 } else { 1 };
+            if let Some(end) = find_emphasis_end(&chars, i, marker) {
+                output.push_str(&strip_inline_markdown(
+                    &chars[i + marker..end].iter().collect::<String>(),
+                ));
+                i = end + marker;
+                continue;
+            }
+        }
+        output.push(c);
+        i += 1;
+    }
+    output
+}
+
+/// Emphasis must hug its content and not sit inside a word, so `snake_case` stays as is
+fn find_emphasis_end(chars: &[char], start: usize, marker: usize) -> Option<usize> {
+    let c = chars[start];
+    let is_word = |v: Option<&char>| v.map(|v| v.is_alphanumeric()).unwrap_or_default();
+    if (start > 0 && is_word(chars.get(start - 1)))
+        || chars.get(start + marker).is_none_or(|v| v.is_whitespace())
+    {
+        return None;
+    }
+    let mut end = start + marker + 1;
+    while end + marker <= chars.len() {
+        if chars[end..end + marker].iter().all(|v| *v == c)
+            && !chars[end - 1].is_whitespace()
+            && !is_word(chars.get(end + marker))
+            && chars.get(end + marker) != Some(&c)
+        {
+            return Some(end);
+        }
+        end += 1;
+    }
+    None
+}
+
 pub fn is_choice_value_terminate(c: char) -> bool {
     c == '|' || c == ']'
 }
@@ -32,6 +170,34 @@ pub fn is_default_value_terminate(c: char) -> bool {
     c.is_whitespace()
 }
 
+pub fn did_you_mean(value: &str, candidates: &[String]) -> Option<String> {
+    candidates
+        .iter()
+        .map(|v| (levenshtein(value, v), v))
+        .filter(|(distance, v)| *distance > 0 && *distance <= (v.chars().count() / 2).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, v)| v.to_string())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(cur).min(row[j]) + 1
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
 pub fn get_shell_path() -> Option<PathBuf> {
     let shell = match env::var("ARGC_SHELL_PATH") {
         Ok(v) => Path::new(&v).to_path_buf(),
@@ -43,6 +209,32 @@ pub fn get_shell_path() -> Option<PathBuf> {
     Some(shell)
 }
 
+/// Program and args to run a script with, the `@meta shell` command wins over `ARGC_SHELL_PATH` and bash
+pub fn get_script_shell(meta_shell: Option<&str>) -> Option<(PathBuf, Vec<String>)> {
+    match meta_shell {
+        Some(value) => {
+            let mut words = value.split_whitespace();
+            let program = PathBuf::from(words.next()?);
+            Some((program, words.map(|v| v.to_string()).collect()))
+        }
+        None => {
+            let shell = get_shell_path()?;
+            let args = get_shell_args(&shell);
+            Some((shell, args))
+        }
+    }
+}
+
+/// Whether the `@meta shell` command has bash-style arrays, e.g. not `sh`, `dash` or `busybox ash`
+pub(crate) fn shell_has_arrays(meta_shell: &str) -> bool {
+    let program = meta_shell.split_whitespace().next().unwrap_or_default();
+    let name = Path::new(program)
+        .file_stem()
+        .map(|v| v.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    matches!(name.as_str(), "bash" | "zsh" | "ksh" | "mksh")
+}
+
 pub fn get_shell_args(shell_path: &Path) -> Vec<String> {
     if let Some(name) = shell_path
         .file_stem()
@@ -77,9 +269,9 @@ pub fn run_param_fns(
     param_fns: &[&str],
     args: &[String],
     envs: HashMap<String, String>,
+    meta_shell: Option<&str>,
 ) -> Option<Vec<String>> {
-    let shell = get_shell_path()?;
-    let shell_extra_args = get_shell_args(&shell);
+    let (shell, shell_extra_args) = get_script_shell(meta_shell)?;
     let path_env = path_env_with_exe();
     let handles: Vec<_> = param_fns
         .iter()
@@ -152,5 +344,58 @@ mod tests {
         assert_eq!("FOO-BAR".to_string(), to_cobol_case("fooBar"));
         assert_eq!("FOO-BAR".to_string(), to_cobol_case("foo-bar"));
         assert_eq!("FOO1".to_string(), to_cobol_case("foo1"));
+        assert_eq!("FOOBAR-BAZ".to_string(), to_cobol_case("FOOBar_baz"));
+        assert_eq!("A-B".to_string(), to_cobol_case("_a--b_"));
+    }
+
+    #[test]
+    fn test_var_name() {
+        assert_eq!("foo_bar".to_string(), to_var_name("foo-bar"));
+        assert_eq!("http1_1".to_string(), to_var_name("http1.1"));
+    }
+
+    #[test]
+    fn test_match_locale() {
+        let mut locales = IndexMap::new();
+        locales.insert("ja".to_string(), "ja".to_string());
+        locales.insert("zh-CN".to_string(), "zh-CN".to_string());
+        assert_eq!(match_locale(&locales, "ja_JP"), Some("ja"));
+        assert_eq!(match_locale(&locales, "zh_CN"), Some("zh-CN"));
+        assert_eq!(match_locale(&locales, "zh_TW"), None);
+        assert_eq!(match_locale(&locales, "en_US"), None);
+    }
+
+    #[test]
+    fn test_render_plain_text() {
+        assert_eq!(
+            render_plain_text("Run **all** the `cargo test` _suites_"),
+            "Run all the cargo test suites"
+        );
+        assert_eq!(
+            render_plain_text("Items:\n* one\n  + *two*\n- three"),
+            "Items:\n- one\n  - two\n- three"
+        );
+        assert_eq!(
+            render_plain_text("Read snake_case, 2 * 3 and x*y*z"),
+            "Read snake_case, 2 * 3 and x*y*z"
+        );
+        assert_eq!(render_plain_text("Unclosed `code"), "Unclosed `code");
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let names = vec!["build".to_string(), "test".to_string()];
+        assert_eq!(did_you_mean("biuld", &names), Some("build".to_string()));
+        assert_eq!(did_you_mean("tset", &names), Some("test".to_string()));
+        assert_eq!(did_you_mean("deploy", &names), None);
+        assert_eq!(did_you_mean("build", &names), None);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("名前"), 4);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("✅"), 2);
     }
 }
@@ -1,7 +1,10 @@
-use assert_fs::{fixture::PathChild, TempDir};
+use assert_fs::{
+    fixture::{FileWriteStr, PathChild},
+    TempDir,
+};
 use rstest::rstest;
 
-use crate::fixtures::{get_path_env_var, tmpdir, tmpdir_path, Error, SCRIPT_PATHS};
+use crate::fixtures::{get_path_env_var, tmpdir, tmpdir_bare, tmpdir_path, Error, SCRIPT_PATHS};
 use assert_cmd::prelude::*;
 use std::process::Command;
 
@@ -45,3 +48,105 @@ fn argcfile_path(tmpdir: TempDir) -> Result<(), Error> {
         .success();
     Ok(())
 }
+
+const TASKS_SCRIPT: &str = r#"
+# @cmd
+# @arg name
+greet() { echo "hello $argc_name"; }
+
+# @cmd
+fail() { echo "failing"; exit 3; }
+
+eval "$(argc --argc-eval "$0" "$@")"
+"#;
+
+#[rstest]
+fn argcfile_parallel(tmpdir_bare: TempDir) -> Result<(), Error> {
+    tmpdir_bare.child("Argcfile.sh").write_str(TASKS_SCRIPT)?;
+    Command::cargo_bin("argc")?
+        .args(["--argc-parallel", "greet", "foo", ":::", "greet", "bar"])
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout(predicates::str::contains("[greet] hello foo\n"))
+        .stdout(predicates::str::contains("[greet] hello bar\n"))
+        .success();
+    Command::cargo_bin("argc")?
+        .args(["--argc-parallel", "fail", ":::", "greet", "bar"])
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout(predicates::str::contains("[fail] failing\n"))
+        .stdout(predicates::str::contains("[greet] hello bar\n"))
+        .code(3);
+    Ok(())
+}
+
+#[rstest]
+fn argcfile_seq(tmpdir_bare: TempDir) -> Result<(), Error> {
+    tmpdir_bare.child("Argcfile.sh").write_str(TASKS_SCRIPT)?;
+    Command::cargo_bin("argc")?
+        .args(["--argc-seq", "greet", "foo", ":::", "greet", "bar"])
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout("hello foo\nhello bar\n")
+        .success();
+    Command::cargo_bin("argc")?
+        .args(["--argc-seq", "fail", ":::", "greet", "bar"])
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout("failing\n")
+        .code(3);
+    Ok(())
+}
+
+#[rstest]
+fn argcfile_cwd(tmpdir_bare: TempDir) -> Result<(), Error> {
+    let script = r#"
+# @cmd
+show() { cat data.txt; echo "$ARGC_PWD"; }
+
+eval "$(argc --argc-eval "$0" "$@")"
+"#;
+    tmpdir_bare.child("Argcfile.sh").write_str(script)?;
+    tmpdir_bare.child("data.txt").write_str("root\n")?;
+    tmpdir_bare.child("sub/data.txt").write_str("sub\n")?;
+    let subdir = tmpdir_bare.child("sub");
+    let subdir_path = subdir.path().display().to_string();
+    Command::cargo_bin("argc")?
+        .arg("show")
+        .current_dir(subdir.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout(format!("root\n{subdir_path}\n"))
+        .success();
+
+    let script = format!("# @meta inherit-cwd\n{script}");
+    tmpdir_bare.child("Argcfile.sh").write_str(&script)?;
+    Command::cargo_bin("argc")?
+        .arg("show")
+        .current_dir(subdir.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout(format!("sub\n{subdir_path}\n"))
+        .success();
+    Ok(())
+}
+
+#[rstest]
+fn argcfile_dry_run(tmpdir_bare: TempDir) -> Result<(), Error> {
+    tmpdir_bare.child("Argcfile.sh").write_str(TASKS_SCRIPT)?;
+    Command::cargo_bin("argc")?
+        .args(["--argc-dry-run", "greet", "foo"])
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .assert()
+        .stdout(predicates::str::contains("argc__fn=greet\ngreet foo\n"))
+        .stdout(predicates::boolean::PredicateBooleanExt::not(
+            predicates::str::contains("hello"),
+        ))
+        .success();
+    Ok(())
+}
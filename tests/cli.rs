@@ -41,3 +41,721 @@ fn compgen() {
         .stdout(predicates::str::contains("abc\ndef\nghi"))
         .success();
 }
+
+#[test]
+fn version_from_var() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @version ${VERSION}
+VERSION=1.2.3
+"###,
+        "version.sh",
+    );
+    Command::new("bash")
+        .arg(&script_path)
+        .arg("--version")
+        .assert()
+        .stdout(predicates::str::contains("1.2.3"))
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn posixly_correct() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @flag -v --verbose
+# @arg args*
+main() {
+    echo "verbose=${argc_verbose:-0} args=${argc_args[*]}"
+}
+"###,
+        "posixly.sh",
+    );
+    Command::new("bash")
+        .arg(&script_path)
+        .args(["a", "-v"])
+        .assert()
+        .stdout("verbose=1 args=a\n")
+        .success();
+    Command::new("bash")
+        .env("POSIXLY_CORRECT", "1")
+        .arg(&script_path)
+        .args(["a", "-v"])
+        .assert()
+        .stdout("verbose=0 args=a -v\n")
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn describe_locales() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @describe A demo cli
+#   @ja デモ CLI
+
+# @cmd Run the build
+#   @ja ビルドを実行する
+# @flag --release Build in release mode
+#   @ja リリースモードでビルド
+build() { :; }
+"###,
+        "locales.sh",
+    );
+    Command::new("bash")
+        .env("ARGC_LOCALE", "ja")
+        .args([&script_path, "build", "--help"])
+        .assert()
+        .stdout(predicates::str::contains("ビルドを実行する"))
+        .stdout(predicates::str::contains("リリースモードでビルド"))
+        .success();
+    Command::new("bash")
+        .env_remove("ARGC_LOCALE")
+        .env("LANG", "ja_JP.UTF-8")
+        .args([&script_path, "--help"])
+        .assert()
+        .stdout(predicates::str::contains("デモ CLI"))
+        .stdout(predicates::str::contains("ビルドを実行する"))
+        .success();
+    Command::new("bash")
+        .env("ARGC_LOCALE", "fr")
+        .args([&script_path, "--help"])
+        .assert()
+        .stdout(predicates::str::contains("A demo cli"))
+        .stdout(predicates::str::contains("Run the build"))
+        .success();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .env("ARGC_LOCALE", "ja")
+        .args([
+            "--argc-compgen",
+            "fish",
+            &script_path,
+            "locales",
+            "build",
+            "--",
+        ])
+        .assert()
+        .stdout(predicates::str::contains("リリースモードでビルド"))
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn describe_lang_tags() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @meta lang en
+# @describe A demo cli
+# @describe[fr] Un outil de démonstration
+
+# @cmd Run the build
+# @cmd[fr] Lancer la compilation
+build() { :; }
+"###,
+        "lang.sh",
+    );
+    Command::new("bash")
+        .env_remove("ARGC_LOCALE")
+        .env("ARGC_LANG", "fr")
+        .args([&script_path, "--help"])
+        .assert()
+        .stdout(predicates::str::contains("Un outil de démonstration"))
+        .stdout(predicates::str::contains("Lancer la compilation"))
+        .success();
+    Command::new("bash")
+        .env_remove("ARGC_LOCALE")
+        .env_remove("ARGC_LANG")
+        .env("LANG", "de_DE.UTF-8")
+        .args([&script_path, "--help"])
+        .assert()
+        .stdout(predicates::str::contains("A demo cli"))
+        .stdout(predicates::str::contains("Run the build"))
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn help_stdout_error_stderr() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @describe A demo cli
+# @option --port! Listen port
+main() { :; }
+"###,
+        "streams.sh",
+    );
+    Command::new("bash")
+        .env("PAGER", "false")
+        .args([&script_path, "--help"])
+        .assert()
+        .stdout(predicates::str::contains("A demo cli"))
+        .stderr("")
+        .success();
+    Command::new("bash")
+        .arg(&script_path)
+        .assert()
+        .stdout("")
+        .stderr(predicates::str::contains("--port <PORT>"))
+        .code(1);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn run_fn() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @cmd
+# @option --env[dev|prod]
+deploy() { echo "deploy env=$argc_env"; exit 3; }
+
+# @cmd
+db() { :; }
+
+# @cmd
+# @flag --force
+db::reset() { echo "reset force=${argc_force:-0}"; }
+
+_helper() { echo "helper $*"; }
+
+main() { echo main; }
+"###,
+        "run.sh",
+    );
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("argc").unwrap();
+        cmd.arg("--argc-run").arg(&script_path).args(args);
+        cmd.assert()
+    };
+    run(&["deploy", "--env", "prod"])
+        .stdout("deploy env=prod\n")
+        .code(3);
+    run(&["deploy", "--env", "stage"])
+        .stderr(predicates::str::contains(
+            "invalid value `stage` for `<ENV>`",
+        ))
+        .code(1);
+    run(&["db::reset", "--force"])
+        .stdout("reset force=1\n")
+        .success();
+    run(&["_helper", "a", "b"]).stdout("helper a b\n").success();
+    run(&["main"]).stdout("main\n").success();
+    run(&["nope"])
+        .stderr(predicates::str::contains("Function 'nope' not found"))
+        .code(1);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn parse_cache() {
+    let cache_dir = assert_fs::TempDir::new().unwrap();
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @option --name=world
+main() { echo "hello $argc_name"; }
+"###,
+        "cache.sh",
+    );
+    let run = |args: &[&str]| {
+        let mut cmd = Command::new("bash");
+        cmd.env("ARGC_CACHE_DIR", cache_dir.path())
+            .arg(&script_path)
+            .args(args);
+        cmd.assert()
+    };
+    let cache_files = || std::fs::read_dir(cache_dir.path()).unwrap().count();
+
+    run(&[]).stdout("hello world\n").success();
+    assert_eq!(cache_files(), 1);
+    run(&["--name", "argc"]).stdout("hello argc\n").success();
+    assert_eq!(cache_files(), 1);
+
+    let cache_file = std::fs::read_dir(cache_dir.path())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    std::fs::write(&cache_file, b"\xff\xff\xff\xff\xff\xff\xff\xff\xff").unwrap();
+    run(&[]).stdout("hello world\n").success();
+
+    let content = std::fs::read_to_string(&script_path).unwrap();
+    std::fs::write(&script_path, content.replace("=world", "=again")).unwrap();
+    run(&[]).stdout("hello again\n").success();
+
+    std::fs::remove_file(&cache_file).unwrap();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .env("ARGC_CACHE_DIR", cache_dir.path())
+        .args(["--argc-no-cache", "--argc-eval", &script_path])
+        .assert()
+        .stdout(predicates::str::contains("argc_name=again"))
+        .success();
+    assert_eq!(cache_files(), 0);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn exit_code() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @meta exit-code unknown-option=2 spec-error=3
+# @option --name
+main() { echo "hello $argc_name"; }
+"###,
+        "exit_code.sh",
+    );
+    let run = |args: &[&str]| {
+        let mut cmd = Command::new("bash");
+        cmd.arg(&script_path).args(args);
+        cmd.assert()
+    };
+    run(&["--name", "argc"]).stdout("hello argc\n").success();
+    run(&["--foo"]).stdout("").code(2);
+
+    let content = std::fs::read_to_string(&script_path).unwrap();
+    std::fs::write(&script_path, content.replace("--name", "--name[]")).unwrap();
+    run(&[]).stdout("").code(3);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn trace() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @cmd
+# @flag -f --force
+# @flag -q --quiet
+# @option -o --output
+# @option --level
+# @arg src
+# @arg rest*
+build() { echo "build $argc_src"; }
+"###,
+        "trace.sh",
+    );
+    let output = Command::new("bash")
+        .env("ARGC_TRACE", "1")
+        .arg(&script_path)
+        .args([
+            "build",
+            "-fq",
+            "--level=3",
+            "-o",
+            "out",
+            "a",
+            "--",
+            "-x",
+            "--bad",
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "build a\n");
+    assert!(output.status.success());
+    // The elapsed time changes from run to run
+    let stderr: Vec<String> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|v| match v.split_once(" in ") {
+            Some((head, _)) if v.contains(" exited with ") => format!("{head} in <elapsed>"),
+            _ => v.to_string(),
+        })
+        .collect();
+    insta::assert_snapshot!(stderr.join("\n"));
+
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args([
+            "--argc-trace",
+            "--argc-eval",
+            &script_path,
+            "build",
+            "--bad",
+        ])
+        .assert()
+        .stderr(predicates::str::contains(
+            r#"{"index":2,"token":"--bad","kind":"unknown"}"#,
+        ))
+        .stdout(predicates::str::contains("exit 1"))
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn format() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+#   @option   --name   A name
+main() { :; }
+"###,
+        "format.sh",
+    );
+    let run = |args: &[&str]| {
+        let mut cmd = Command::cargo_bin("argc").unwrap();
+        cmd.arg("--argc-format").args(args).arg(&script_path);
+        cmd.assert()
+    };
+    run(&["--check"])
+        .stderr(predicates::str::contains("is not formatted"))
+        .code(1);
+    run(&["--check", "other.sh"])
+        .stderr(predicates::str::contains("Usage: argc --argc-format"))
+        .failure();
+    run(&[]).success();
+    let content = std::fs::read_to_string(&script_path).unwrap();
+    assert!(content.contains("\n# @option --name A name\nmain() { :; }\n"));
+    run(&["--check"]).success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn migrate() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @option --level![=info|debug] Log level
+main() { :; }
+"###,
+        "migrate.sh",
+    );
+    let path = &script_path;
+    Command::cargo_bin("argc")
+        .unwrap()
+        .arg("--argc-migrate")
+        .arg(&script_path)
+        .assert()
+        .stdout(format!(
+            "--- {path}\n+++ {path}\n@@ -2 +2 @@\n-# @option --level![=info|debug] Log level\n+# @option --level[=info|debug] Log level\n"
+        ))
+        .success();
+    let content = std::fs::read_to_string(&script_path).unwrap();
+    assert!(content.contains("\n# @option --level[=info|debug] Log level\n"));
+    script_file.close().unwrap();
+}
+
+#[test]
+fn check() {
+    let (script_path, _script_content, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @option --level![=info|debug]
+main() { :; }
+"###,
+        "check.sh",
+    );
+    Command::cargo_bin("argc")
+        .unwrap()
+        .arg("--argc-check")
+        .arg(&script_path)
+        .assert()
+        .stderr(predicates::str::contains(
+            "error: @option(line 2) has a choice default, so `!` is ignored",
+        ))
+        .code(1);
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args(["--argc-migrate", &script_path])
+        .assert()
+        .success();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args(["--argc-check", &script_path])
+        .assert()
+        .stderr("")
+        .success();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn check_missing_eval() {
+    let tmpdir = assert_fs::TempDir::new().unwrap();
+    let script_path = tmpdir.path().join("no-eval.sh");
+    std::fs::write(&script_path, "# @flag --foo\n").unwrap();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .arg("--argc-check")
+        .arg(&script_path)
+        .assert()
+        .stderr(
+            "error: no `eval \"$(argc --argc-eval \"$0\" \"$@\")\"` line, so the args are never parsed\n",
+        )
+        .code(1);
+    std::fs::write(&script_path, "#!/usr/bin/env argc\n# @flag --foo\n").unwrap();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .arg("--argc-check")
+        .arg(&script_path)
+        .assert()
+        .stderr("")
+        .success();
+}
+
+#[cfg(unix)]
+#[test]
+fn shebang() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpdir = assert_fs::TempDir::new().unwrap();
+    let script_path = tmpdir.path().join("greet");
+    std::fs::write(
+        &script_path,
+        r###"#!/usr/bin/env argc
+# @describe Greet people, no need for `eval "$(argc --argc-eval "$0" "$@")"`
+# @option --greeting=hi
+
+echo "top $argc_greeting"
+
+# @cmd
+# @arg name!
+hello() {
+    echo "$argc_greeting $argc_name from $(basename "$0")"
+    cat
+    exit 3
+}
+"###,
+    )
+    .unwrap();
+    std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path_env_var = get_path_env_var();
+    assert_cmd::Command::new(&script_path)
+        .args(["--greeting", "hello", "hello", "bob"])
+        .env("PATH", &path_env_var)
+        .write_stdin("from stdin\n")
+        .assert()
+        .stdout("top hello\nhello bob from greet\nfrom stdin\n")
+        .code(3);
+    assert_cmd::Command::new(&script_path)
+        .arg("hello")
+        .env("PATH", &path_env_var)
+        .assert()
+        .stderr(predicates::str::contains(
+            "error: the following required arguments were not provided",
+        ))
+        .code(1);
+    tmpdir.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn shebang_temp_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmpdir = assert_fs::TempDir::new().unwrap();
+    let script_path = tmpdir.path().join("greet");
+    let temp_dir = tmpdir.path().join("tmp");
+    std::fs::create_dir(&temp_dir).unwrap();
+    let write_script = |shell: &str| {
+        let script = format!(
+            "#!/usr/bin/env argc\n# @meta shell {shell}\n# @arg name\n[ -e \"$BASH_SOURCE\" ] || echo \"removed $BASH_SOURCE\"\n"
+        );
+        std::fs::write(&script_path, script).unwrap();
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    };
+    let path_env_var = get_path_env_var();
+    write_script("bash");
+    assert_cmd::Command::new(&script_path)
+        .arg("bob")
+        .env("PATH", &path_env_var)
+        .env("TMPDIR", &temp_dir)
+        .assert()
+        .stdout(predicates::str::starts_with(format!(
+            "removed {}/argc-",
+            temp_dir.display()
+        )))
+        .success();
+    assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+    write_script("argc-missing-shell");
+    assert_cmd::Command::new(&script_path)
+        .env("PATH", &path_env_var)
+        .env("TMPDIR", &temp_dir)
+        .assert()
+        .stderr(predicates::str::contains("Failed to run"))
+        .failure();
+    assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+    tmpdir.close().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn meta_shell_dash() {
+    // Runners without dash have nothing to check
+    if Command::new("dash").arg("-c").arg(":").status().is_err() {
+        return;
+    }
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @meta shell dash
+# @option --tag* Tags
+# @arg files*
+main() {
+    printf 'tag:%s\n' "$argc_tag"
+    printf 'args:%s\n' "$argc__args"
+    for file in "$@"; do
+        echo "file=$file"
+    done
+}
+"###,
+        "dash.sh",
+    );
+    Command::new("dash")
+        .args([&script_path, "--tag", "a", "--tag", "b c", "--", "x", "y z"])
+        .assert()
+        .stdout("tag:a\nb c\nargs:x\ny z\nfile=x\nfile=y z\n")
+        .success();
+    Command::new("dash")
+        .args([&script_path, "--nope"])
+        .assert()
+        .stderr(predicates::str::contains("error:"))
+        .code(1);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn version_check() {
+    let version = env!("CARGO_PKG_VERSION");
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        "# @meta min-argc-version 1.0.0-beta.2\nmain() { :; }\n",
+        "version_check.sh",
+    );
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args(["--argc-version-check", &script_path])
+        .assert()
+        .stdout(format!(
+            "argc {version}\nmin-argc-version 1.0.0-beta.2: satisfied\n"
+        ))
+        .success();
+    std::fs::write(&script_path, "# @meta min-argc-version 999.0\n").unwrap();
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args(["--argc-version-check", &script_path])
+        .assert()
+        .stdout(format!(
+            "argc {version}\nmin-argc-version 999.0: not satisfied\n"
+        ))
+        .code(1);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn dry_run() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+# @cmd
+# @option --env[dev|prod]
+# @option --msg="it's here"
+# @arg target=web
+deploy() { echo "deploying"; }
+"###,
+        "dry_run.sh",
+    );
+    Command::new("bash")
+        .args([&script_path, "--argc-dry-run", "deploy", "--env", "prod"])
+        .assert()
+        .stdout(
+            r#"argc_env=prod
+argc_env__source=cli
+argc_msg='it'\''s here'
+argc_msg__source=default
+argc_target=web
+argc_target__source=default
+argc__args=( web )
+argc__fn=deploy
+deploy web
+"#,
+        )
+        .success();
+    Command::new("bash")
+        .env("ARGC_DRY_RUN", "1")
+        .args([&script_path, "deploy", "--env", "test"])
+        .assert()
+        .stdout("")
+        .stderr(predicates::str::contains("invalid value `test`"))
+        .code(1);
+    script_file.close().unwrap();
+}
+
+#[test]
+fn strict_shell() {
+    let (script_path, _, script_file) = crate::fixtures::create_argc_script(
+        r###"
+set -euo pipefail
+# @meta strict-shell
+# @flag --force
+# @option --env
+# @option --tag*
+# @option --label*[map]
+# @arg files*
+main() {
+    echo "force=$argc_force env=$argc_env tags=${argc_tag[*]} labels=${argc_label__keys[*]} files=${argc_files[*]} source=$argc_env__source"
+    false | true
+    echo "pipefail is off"
+}
+"###,
+        "strict_shell.sh",
+    );
+    Command::new("bash")
+        .args([&script_path])
+        .assert()
+        .stdout("force= env= tags= labels= files= source=\n")
+        .failure();
+    Command::new("bash")
+        .args([&script_path, "--env", "prod", "a"])
+        .assert()
+        .stdout("force= env=prod tags= labels= files=a source=cli\n")
+        .failure();
+    script_file.close().unwrap();
+}
+
+#[test]
+fn vars_initialized() {
+    let script = r###"
+set -u
+# @flag --force
+# @option --env
+# @option --tag*
+# @option --token env:API_TOKEN
+# @arg files*
+main() {
+    echo "force=${argc_force+set}$argc_force env=$argc_env tags=${#argc_tag[@]} token=$argc_token ($argc_token__source) files=${#argc_files[@]}"
+}
+"###;
+    let (script_path, _, script_file) =
+        crate::fixtures::create_argc_script(script, "vars_initialized.sh");
+    Command::new("bash")
+        .args([&script_path])
+        .env_remove("API_TOKEN")
+        .assert()
+        .stdout("force=set env= tags=0 token= () files=0\n")
+        .success();
+    Command::new("bash")
+        .args([&script_path, "x", "--force", "--tag", "a"])
+        .env("API_TOKEN", "secret")
+        .assert()
+        .stdout("force=set1 env= tags=1 token=secret (env) files=1\n")
+        .success();
+    let sparse_script = script.replace("set -u\n", "# @meta sparse-vars\n");
+    let (script_path, _, sparse_script_file) =
+        crate::fixtures::create_argc_script(&sparse_script, "vars_sparse.sh");
+    Command::new("bash")
+        .args([&script_path, "--tag", "a"])
+        .env_remove("API_TOKEN")
+        .assert()
+        .stdout("force= env= tags=1 token= () files=0\n")
+        .success();
+    script_file.close().unwrap();
+    sparse_script_file.close().unwrap();
+}
+
+#[test]
+fn completions_name() {
+    let (script_path, _, script_file) =
+        crate::fixtures::create_argc_script("# @name mycli\n", "completions_name.sh");
+    Command::cargo_bin("argc")
+        .unwrap()
+        .args(["--argc-completions", "bash", &script_path, "other"])
+        .assert()
+        .stdout(predicates::str::contains(
+            "complete -F _argc_completer argc mycli other\n",
+        ))
+        .success();
+    script_file.close().unwrap();
+}
@@ -1,5 +1,3 @@
-use crate::*;
-
 #[test]
 fn multiple() {
     let script = r###"
@@ -70,7 +68,6 @@ fn shorts() {
 #[test]
 fn subcmds() {
     const SCRIPT: &str = r###"
-# @arg file
 # @cmd
 cmda() { :; }
 # @cmd
@@ -94,7 +91,6 @@ cmdb() { :; }
 #[test]
 fn nested_subcmds() {
     const SCRIPT: &str = r###"
-# @arg file
 # @cmd
 cmd() { :; }
 # @cmd
@@ -387,6 +383,46 @@ _choice_fn() {
     snapshot_compgen_shells!(script, vec!["prog", "--oa", ""]);
 }
 
+#[test]
+fn notation_complete() {
+    let script = r###"
+# @meta notation-complete BRANCH _complete_branches
+# @option --base <BRANCH>
+# @option --branch
+# @option --mode[fast|slow] <BRANCH>
+# @option --other <NAME>
+
+# @cmd
+# @arg target <BRANCH>
+checkout() { :; }
+
+_complete_branches() {
+    echo main
+    echo develop
+}
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--base", ""],
+            vec!["prog", "--branch", "d"],
+            vec!["prog", "--mode", ""],
+            vec!["prog", "--other", ""],
+            vec!["prog", "checkout", ""],
+        ]
+    );
+}
+
+#[test]
+fn choices_describe() {
+    let script = r###"
+# @option --mode["fast":Skip the checks|"safe":Run every check|"a:b":Colon|plain|c:d]
+"###;
+
+    snapshot_compgen_shells!(script, vec!["prog", "--mode", ""]);
+}
+
 #[test]
 fn value_display() {
     let script = r###"
@@ -433,3 +469,260 @@ _choice_fn() {
 
     snapshot_compgen_shells!(script, vec!["prog", "--oa", "A/B/"]);
 }
+
+#[test]
+fn completion_alias() {
+    let script = r###"
+# @cmd Build the project
+# @alias b
+# @completion-alias bd
+build() { :; }
+"###;
+
+    snapshot_compgen_shells!(script, vec!["prog", "b"]);
+}
+
+#[test]
+fn option_aliases() {
+    let script = r###"
+# @option -o --output --out <FILE> Output file
+"###;
+
+    snapshot_compgen!(script, vec![vec!["prog", "--"]]);
+}
+
+#[test]
+fn choices_ignore_case() {
+    let script = r###"
+# @option --level[~debug|info|warn|error]
+"###;
+
+    snapshot_compgen!(script, vec![vec!["prog", "--level", ""]]);
+}
+
+#[test]
+fn choices_any() {
+    let script = r###"
+# @option --format[json|yaml|*]
+"###;
+
+    snapshot_compgen!(script, vec![vec!["prog", "--format", ""]]);
+}
+
+#[test]
+fn param_examples() {
+    let script = r###"
+# @option --branch examples:main,develop
+# @arg file <FILE> examples:Cargo.toml
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--branch", ""],
+            vec!["prog", "--branch", "d"],
+            vec!["prog", "--branch", "x"],
+            vec!["prog", ""],
+            vec!["prog", "src/"],
+        ]
+    );
+}
+
+#[test]
+fn choices_file() {
+    let script = r###"
+# @option --country[@countries.txt]
+"###;
+    let path = crate::locate_script("choices.sh");
+    let args: Vec<String> = ["prog", "--country", ""]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    let words = argc::compgen(argc::Shell::Fish, &path, script, &args).unwrap();
+    assert_eq!(words, "us\ngb\nfr");
+}
+
+#[test]
+fn option_range() {
+    let script = r###"
+# @option --retries=3[1..10]
+# @option --port=8080[1..65535]
+# @option --offset[-9223372036854775808..9223372036854775807]
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--retries", ""],
+            vec!["prog", "--port", ""],
+            vec!["prog", "--offset", ""],
+        ]
+    );
+}
+
+#[test]
+fn trailing_args() {
+    let script = r###"
+# @flag --dry-run
+# @arg name[a|b]
+# @arg cmd~
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", ""],
+            vec!["prog", "a", ""],
+            vec!["prog", "a", "ls", "--"],
+        ]
+    );
+}
+
+#[test]
+fn value_types() {
+    let script = r###"
+# @option --count <N:int>
+# @option --debug <ON:bool>
+# @arg enabled <ENABLED:bool>
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--count", ""],
+            vec!["prog", "--debug", ""],
+            vec!["prog", ""],
+        ]
+    );
+}
+
+#[test]
+fn path_value_types() {
+    let script = r###"
+# @option --config <CONFIG:file>
+# @option --out <TARGET:dir>
+# @option --mode[fast|slow] <MODE:file>
+# @arg src <SOURCE:path>
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--config", ""],
+            vec!["prog", "--out", ""],
+            vec!["prog", "--mode", ""],
+            vec!["prog", ""],
+        ]
+    );
+}
+
+#[test]
+fn option_optional_value() {
+    let script = r###"
+# @option --color?[always|never|auto]
+# @arg files*[x|y]
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![vec!["prog", "--color="], vec!["prog", "--color", ""]]
+    );
+}
+
+#[test]
+fn unicode() {
+    let script = r###"
+# @option --mark[✅|❌] 状态
+# @option --city[東京|東京都|大阪] 都市
+# @arg name[café|café|日本語] 名前
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--mark", ""],
+            vec!["prog", "--city", "東"],
+            vec!["prog", "--city", "東京"],
+            vec!["prog", "--city=東"],
+            vec!["prog", "caf"],
+            vec!["prog", "日"],
+        ]
+    );
+    snapshot_compgen_shells!(script, vec!["prog", "--city", "東"]);
+}
+
+#[test]
+fn choice_fn_cword() {
+    let script = r###"
+# @option --env[`_choice_fn`]
+_choice_fn() {
+    echo "${ARGC_CWORD}1"
+    echo "${ARGC_CWORD}2"
+    echo other
+}
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "--env", ""],
+            vec!["prog", "--env", "pro"],
+            vec!["prog", "--env=pro"],
+        ]
+    );
+}
+
+#[test]
+fn dashdash_files() {
+    let script = r###"
+# @flag --verbose
+
+# @cmd
+# @arg target[x|y]
+# @arg args~
+run() { :; }
+
+# @cmd
+# @arg name[p|q]
+# @arg rest*
+plain() { :; }
+
+# @cmd
+# @arg dirs*  <DIR>
+dirs() { :; }
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "run", "--", ""],
+            vec!["prog", "run", "x", "a", "-"],
+            vec!["prog", "plain", "--", ""],
+            vec!["prog", "plain", "p", "--", "--v"],
+            vec!["prog", "dirs", "--", ""],
+            vec!["prog", "--", ""],
+        ]
+    );
+}
+
+#[test]
+fn flag_option_global() {
+    let script = r###"
+# @flag -v --verbose scope:global
+# @option --profile[dev|prod] scope:global
+# @option --local
+
+# @cmd
+# @flag -q --quiet
+test() { :; }
+"###;
+
+    snapshot_compgen!(
+        script,
+        vec![
+            vec!["prog", "test", "-"],
+            vec!["prog", "test", "-v", "-"],
+            vec!["prog", "test", "--profile", ""],
+        ]
+    );
+}
@@ -35,10 +35,29 @@ fn create_with_tasks(tmpdir_bare: TempDir) -> Result<(), Error> {
         .success();
     Command::cargo_bin("argc")?
         .current_dir(tmpdir_bare.path())
-        .env("PATH", path_env_var)
+        .env("PATH", path_env_var.clone())
         .arg("bar")
         .assert()
         .stdout(predicates::str::contains("To implement command: bar"))
         .success();
+    Command::cargo_bin("argc")?
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", path_env_var)
+        .arg("--help")
+        .assert()
+        .stdout(predicates::str::contains("A boilerplate argcfile"))
+        .success();
+    Ok(())
+}
+
+#[rstest]
+fn create_with_invalid_tasks(tmpdir_bare: TempDir) -> Result<(), Error> {
+    Command::cargo_bin("argc")?
+        .current_dir(tmpdir_bare.path())
+        .arg("--argc-create")
+        .args(["foo", "bar::baz"])
+        .assert()
+        .failure();
+    assert!(!tmpdir_bare.path().join("Argcfile.sh").exists());
     Ok(())
 }
@@ -0,0 +1,165 @@
+use argc::{diff_specs, Command, SpecChangeKind};
+
+fn diff(old: &str, new: &str) -> Vec<String> {
+    let old = Command::new(old).unwrap();
+    let new = Command::new(new).unwrap();
+    diff_specs(&old, &new)
+        .iter()
+        .map(|v| {
+            let breaking = if v.breaking { "breaking" } else { "additive" };
+            format!("{} {} {}", v.kind.as_str(), breaking, v.summary)
+        })
+        .collect()
+}
+
+#[test]
+fn changes() {
+    let old = r###"
+# @flag --verbose
+# @option --level[info|debug]
+# @option --out=a.txt
+
+# @cmd
+# @option --target[a|b]
+# @arg src
+build() { :; }
+
+# @cmd
+clean() { :; }
+"###;
+    let new = r###"
+# @option --level[info|debug|trace]
+# @option --out=b.txt
+# @option --token!
+
+# @cmd
+# @option --target[a]
+# @arg src!
+# @arg dest
+build() { :; }
+
+# @cmd
+test() { :; }
+"###;
+    assert_eq!(
+        diff(old, new),
+        [
+            "option-removed breaking flag '--verbose' removed",
+            "choices-changed additive option '--level' choices added trace",
+            "default-changed additive option '--out' default changed from 'a.txt' to 'b.txt'",
+            "option-added breaking option '--token' added",
+            "command-removed breaking command 'clean' removed",
+            "command-added additive command 'test' added",
+            "choices-changed breaking build: option '--target' choices removed b",
+            "required-changed breaking build: positional 'src' became required",
+            "positional-added additive build: positional 'dest' added",
+        ]
+    );
+}
+
+#[test]
+fn path() {
+    let old = r###"
+# @cmd
+db() { :; }
+
+# @cmd
+# @flag --force
+db::reset() { :; }
+"###;
+    let new = r###"
+# @cmd
+db() { :; }
+
+# @cmd
+db::reset() { :; }
+"###;
+    let changes = diff_specs(&Command::new(old).unwrap(), &Command::new(new).unwrap());
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].kind, SpecChangeKind::OptionRemoved);
+    assert_eq!(changes[0].path, ["db", "reset"]);
+    assert_eq!(changes[0].summary, "db reset: flag '--force' removed");
+}
+
+#[test]
+fn same() {
+    let script = r###"
+# @option --level[info|debug] <LEVEL>
+# @arg src*
+"###;
+    assert!(diff(script, script).is_empty());
+}
+
+#[test]
+fn short_removed() {
+    assert_eq!(
+        diff("# @option -e --env", "# @option --env"),
+        ["names-changed breaking option '--env' names removed -e"]
+    );
+    assert_eq!(
+        diff("# @option --env", "# @option -e --env"),
+        ["names-changed additive option '--env' names added -e"]
+    );
+}
+
+#[test]
+fn long_alias_removed() {
+    assert_eq!(
+        diff("# @option --output --out", "# @option --output"),
+        ["names-changed breaking option '--output' names removed --out"]
+    );
+}
+
+#[test]
+fn command_alias_removed() {
+    let old = r###"
+# @cmd
+# @alias b,bld
+build() { :; }
+"###;
+    let new = r###"
+# @cmd
+# @alias bld
+build() { :; }
+"###;
+    assert_eq!(
+        diff(old, new),
+        ["names-changed breaking command 'build' aliases removed b"]
+    );
+}
+
+#[test]
+fn flag_became_option() {
+    assert_eq!(
+        diff("# @flag --force", "# @option --force"),
+        ["kind-changed breaking option '--force' was a flag"]
+    );
+    assert_eq!(
+        diff("# @option --force", "# @flag --force"),
+        ["kind-changed breaking flag '--force' was an option"]
+    );
+}
+
+#[test]
+fn multiple_removed() {
+    assert_eq!(
+        diff("# @option --x*", "# @option --x"),
+        ["multiple-changed breaking option '--x' became single"]
+    );
+    assert_eq!(
+        diff("# @arg files*", "# @arg files"),
+        ["multiple-changed breaking positional 'files' became single"]
+    );
+    assert_eq!(
+        diff("# @option --x", "# @option --x*"),
+        ["multiple-changed additive option '--x' became multiple"]
+    );
+}
+
+#[test]
+fn value_names_changed() {
+    assert_eq!(
+        diff("# @option --size <W> <H>", "# @option --size <W>"),
+        ["values-changed breaking option '--size' takes 1 values instead of 2"]
+    );
+}
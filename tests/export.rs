@@ -1,5 +1,3 @@
-use crate::*;
-
 #[test]
 fn case1() {
     let script = r###"
@@ -15,3 +13,247 @@ cmd() { :; }
 "###;
     snapshot_export!(script);
 }
+
+#[test]
+fn dot() {
+    let script = r###"
+# @flag --verbose
+# @cmd
+# @option --target
+# @arg files*
+build() { :; }
+# @cmd
+build::wasm() { :; }
+# @cmd
+build::js() { :; }
+# @cmd
+# @alias t
+test() { :; }
+"###;
+    let output = argc::export_dot(script, None, "prog").unwrap();
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn metadata() {
+    let script = r###"
+# @describe Test argc
+# @version    1.0.0
+# @author     nobody <nobody@example.com>
+# @name test
+# @flag --verbose
+# @cmd
+# @describe A subcommand
+# @version 2.0.0
+cmd() { :; }
+"###;
+    let metadata = argc::extract_metadata(script).unwrap();
+    assert_eq!(
+        metadata,
+        argc::Metadata {
+            describe: "Test argc".into(),
+            version: Some("1.0.0".into()),
+            author: Some("nobody <nobody@example.com>".into()),
+            name: Some("test".into()),
+        }
+    );
+}
+
+#[test]
+fn meta() {
+    let script = r###"
+# @meta parse-mode posix
+# @cmd
+# @meta args-separator ++
+build() { :; }
+"###;
+    snapshot_export!(script);
+}
+
+#[test]
+fn output() {
+    let script = r###"
+# @cmd
+# @output One JSON object per line
+# @output Nothing when no release matches
+releases() { :; }
+"###;
+    snapshot_export!(script);
+}
+
+#[test]
+fn eval_lines() {
+    let script = r###"
+# @flag --verbose
+main() { :; }
+
+eval "$(argc --argc-eval "$0" "$@")"
+"###;
+    let json = argc::export(script, None).unwrap();
+    assert_eq!(json["eval_lines"], serde_json::json!([5]));
+    let json = argc::export("# @flag --verbose", None).unwrap();
+    assert!(json.get("eval_lines").is_none());
+}
+
+#[test]
+fn clap() {
+    let script = r###"
+# @describe Test argc
+# @version 1.0.0
+# @flag -v --verbose* Verbose level
+# @option -o --output --out <FILE> Output file
+# @option --level[~debug|info] Log level
+# @option --retries=3[1..10]
+# @option -D*[map] <KEY=VALUE>
+# @option --size <W:int> <H:int>
+
+# @cmd Build the project
+# @alias b
+# @arg target!
+# @arg files*
+build() { :; }
+
+# @cmd
+# @arg cmd~
+run() { :; }
+
+# @cmd
+run::fast() { :; }
+"###;
+    let output = argc::export_clap(script, None, "prog").unwrap();
+    insta::assert_snapshot!(output);
+}
+
+#[rustfmt::skip]
+#[path = "scripts/clap_command.rs"]
+mod clap_command;
+
+#[test]
+fn clap_debug_assert() {
+    let script = r###"
+# @describe Copy files
+# @flag -f --force Overwrite existing files
+
+# @cmd Copy files into a dir
+# @arg src* Source files
+# @arg dest! Destination dir
+copy() { :; }
+
+# @cmd
+# @arg cmd~
+run() { :; }
+"###;
+    let output = argc::export_clap(script, None, "clap_command").unwrap();
+    assert_eq!(output, include_str!("scripts/clap_command.rs").trim_end());
+    let command = clap_command::command();
+    command.clone().debug_assert();
+    let matches = command.get_matches_from(["prog", "copy", "a", "b", "c"]);
+    let matches = matches.subcommand_matches("copy").unwrap();
+    let src: Vec<&String> = matches.get_many("src").unwrap().collect();
+    assert_eq!(src, ["a", "b"]);
+    assert_eq!(matches.get_one::<String>("dest").unwrap(), "c");
+}
+
+#[test]
+fn command_node() {
+    let script = r###"
+# @flag --verbose
+# @option --config <FILE>
+# @cmd Build things
+# @alias b
+build() { :; }
+# @cmd Build wasm
+# @option --target[web|node]
+build::wasm() { :; }
+"###;
+    let names = vec!["b".to_string(), "wasm".to_string()];
+    let json = argc::export_command(script, None, &names).unwrap();
+    insta::assert_snapshot!(serde_json::to_string_pretty(&json).unwrap());
+    let err = argc::export_command(script, None, &["test".to_string()]).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown subcommand 'test'");
+}
+
+#[test]
+fn resolve_path() {
+    let script = r###"
+# @option -e --env Target env
+# @cmd
+# @alias t
+test() { :; }
+
+# @cmd
+# @alias d
+db() { :; }
+
+# @cmd
+# @alias m
+db::migrate() { :; }
+"###;
+    let cmd = argc::Command::new(script).unwrap();
+    let resolve = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|v| v.to_string()).collect();
+        cmd.resolve_path(&args)
+    };
+    assert_eq!(resolve(&["t", "x"]).unwrap(), vec!["test", "x"]);
+    assert_eq!(
+        resolve(&["-e", "d", "d", "m", "--up"]).unwrap(),
+        vec!["-e", "d", "db", "migrate", "--up"]
+    );
+    assert_eq!(resolve(&["help", "t"]).unwrap(), vec!["help", "test"]);
+    assert_eq!(
+        resolve(&["d", "x"]).unwrap_err(),
+        argc::ResolveError {
+            index: 1,
+            token: "x".into(),
+            path: vec!["db".into()],
+            candidates: vec!["migrate".into(), "m".into()],
+        }
+    );
+    assert_eq!(
+        resolve(&["tset"]).unwrap_err().to_string(),
+        "Unknown subcommand 'tset' at argument 0"
+    );
+}
+
+#[test]
+fn choices_file() {
+    let script = r###"
+# @option --country[@countries.txt]
+"###;
+    let path = Some("tests/scripts/prog.sh");
+    let json = argc::export(script, path).unwrap();
+    assert_eq!(
+        json["options"][0]["choices"],
+        serde_json::json!(["us", "gb", "fr"])
+    );
+    let output = argc::export_clap(script, path, "prog").unwrap();
+    assert!(output.contains(r#".value_parser(["us", "gb", "fr"])"#));
+    let err = argc::export(script, None).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "countries.txt(line 2) is relative to the script, which has no path"
+    );
+}
+
+#[test]
+fn meta_config() {
+    let script = r###"
+# @meta config defaults.toml
+# @option --port
+# @option --log-level
+# @arg target
+"###;
+    let path = Some("tests/scripts/prog.sh");
+    let json = argc::export(script, path).unwrap();
+    assert_eq!(json["options"][0]["default"], "8080");
+    assert_eq!(
+        json["options"][1]["choices"],
+        serde_json::json!(["debug", "info", "warn", "error"])
+    );
+    assert_eq!(
+        json["positionals"][0]["choices"],
+        serde_json::json!(["linux", "macos", "windows"])
+    );
+    let output = argc::export_clap(script, path, "prog").unwrap();
+    assert!(output.contains(r#".default_value("8080")"#));
+}
@@ -7,7 +7,30 @@ fn unsupported_tag() {
 }
 
 #[test]
-fn unexpected_arg() {
+fn name_not_single_word() {
+    fail!(
+        "# @name my cli",
+        &["prog"],
+        "@name(line 1) must be a single word"
+    );
+}
+
+#[test]
+fn name_not_root() {
+    let script = r###"
+# @cmd
+# @name sub
+foo() { :; }
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@name(line 3) can only be used on the root command"
+    );
+}
+
+#[test]
+fn arg_miss_fn_at_end() {
     let script = r###"
 # @flag --foo
 foo() {
@@ -19,7 +42,43 @@ foo() {
     fail!(
         script,
         &["prog"],
-        "@arg(line 7) is unexpected, maybe miss @cmd?"
+        "@arg(line 7) is not attached to any function"
+    );
+}
+
+#[test]
+fn unexpected_option_before_fn() {
+    let script = r###"
+foo() {
+}
+
+# @option --bar
+bar() {
+}
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 5) is unexpected, maybe miss @cmd?"
+    );
+}
+
+#[test]
+fn option_miss_fn_before_cmd() {
+    let script = r###"
+foo() {
+}
+
+# @flag --bar
+
+# @cmd
+baz() {
+}
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@flag(line 5) is not attached to any function"
     );
 }
 
@@ -127,6 +186,45 @@ try() {
     );
 }
 
+#[test]
+fn conflict_alias_cmd_name() {
+    let script = r###"
+# @cmd
+build() {
+}
+# @cmd
+# @alias build
+bundle() {
+}
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@alias(line 6) is conflicted with cmd or alias at line 3"
+    );
+}
+
+#[test]
+fn conflict_alias_nested_cmd_name() {
+    let script = r###"
+# @cmd
+foo() {
+}
+# @cmd
+# @alias build
+foo::compile() {
+}
+# @cmd
+foo::build() {
+}
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "foo::build(line 10) is conflicted with cmd or alias at line 6"
+    );
+}
+
 #[test]
 fn option_miss_default_fn() {
     let script = r###"
@@ -143,6 +241,14 @@ fn option_miss_choice_fn() {
     fail!(script, &["prog"], "_fn(line 2) is missing");
 }
 
+#[test]
+fn flag_miss_terminal_fn() {
+    let script = r###"
+# @flag --list terminal:`_fn`
+    "###;
+    fail!(script, &["prog"], "_fn(line 2) is missing");
+}
+
 #[test]
 fn arg_miss_default_fn() {
     let script = r###"
@@ -165,5 +271,561 @@ fn cmd_miss_fn() {
 # @cmd
 # @cmd
     "###;
-    fail!(script, &["prog"], "@cmd(line 2) miss function?");
+    fail!(
+        script,
+        &["prog"],
+        "@cmd(line 2) is not attached to any function"
+    );
+}
+
+#[test]
+fn cmd_miss_fn_at_end() {
+    let script = r###"
+# @flag --foo
+# @cmd
+foo() {
+}
+
+# @cmd Deploy the app
+# @option --env
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@cmd(line 7) is not attached to any function"
+    );
+}
+
+#[test]
+fn root_positional_with_subcmd() {
+    let script = r###"
+# @flag --verbose
+# @arg val
+# @cmd
+foo() {
+}
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 3) is conflicted with @cmd(line 4), root positionals can't be used together with subcommands"
+    );
+}
+
+#[test]
+fn env_only_miss_env() {
+    let script = r###"
+# @option --token env:only
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 2) has env:only but no env:NAME binding"
+    );
+}
+
+#[test]
+fn unknown_meta() {
+    let script = r###"
+# @meta foo
+    "###;
+    fail!(script, &["prog"], "@meta(line 2) has unknown key 'foo'");
+}
+
+#[test]
+fn unknown_meta_suggestion() {
+    let script = r###"
+# @meta parse-mod posix
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) has unknown key 'parse-mod', did you mean 'parse-mode'?"
+    );
+}
+
+#[test]
+fn meta_switch_with_value() {
+    let script = r###"
+# @meta no-forward-positionals yes
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) no-forward-positionals takes no value"
+    );
+}
+
+#[test]
+fn meta_doc_marker_invalid() {
+    let script = r###"
+# @meta doc-marker //
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) doc-marker '//' must only contain '#'"
+    );
+}
+
+#[test]
+fn meta_notation_complete_invalid() {
+    let script = r###"
+# @meta notation-complete BRANCH
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) notation-complete 'BRANCH' must be like 'NOTATION fn'"
+    );
+}
+
+#[test]
+fn meta_notation_complete_miss_fn() {
+    let script = r###"
+# @meta notation-complete BRANCH _fn
+    "###;
+    fail!(script, &["prog"], "_fn(line 2) is missing");
+}
+
+#[test]
+fn cmd_fn_name_empty() {
+    let script = r###"
+# @cmd
+foo() { :; }
+# @cmd
+foo::() { :; }
+    "###;
+    fail!(script, &["prog"], "foo::(line 5) has an empty command name");
+}
+
+#[test]
+fn cmd_fn_name_option_like() {
+    let script = r###"
+# @cmd
+-foo() { :; }
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "-foo(line 3) has command name '-foo' that looks like an option"
+    );
+}
+
+#[test]
+fn cmd_fn_name_invalid_char() {
+    let script = r###"
+# @cmd
+foo*bar() { :; }
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "foo*bar(line 3) has command name 'foo*bar' with '*', only letters, digits and _ - . : @ + are allowed"
+    );
+}
+
+#[test]
+fn cmd_locale_without_cmd() {
+    let script = r###"
+# @cmd[fr] Lancer
+build() { :; }
+    "###;
+    fail!(script, &["prog"], "@cmd[fr](line 2) must follow a @cmd");
+}
+
+#[test]
+fn describe_locale_default_lang() {
+    let script = r###"
+# @meta lang fr
+# @describe Un outil
+# @describe[fr] Un outil
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@describe[fr](line 4) is in 'fr', the language of the default text set by @meta lang"
+    );
+}
+
+#[test]
+fn meta_min_argc_version() {
+    let script = r###"
+# @meta min-argc-version 999.1
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        format!(
+            "@meta(line 2) min-argc-version requires argc 999.1 or newer, this is argc {}",
+            env!("CARGO_PKG_VERSION")
+        )
+    );
+}
+
+#[test]
+fn meta_min_argc_version_invalid() {
+    let script = r###"
+# @meta min-argc-version 1.x
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) min-argc-version '1.x' is not a version like '1.8' or '1.8.2'"
+    );
+}
+
+#[test]
+fn conflict_meta() {
+    let script = r###"
+# @meta parse-mode gnu
+# @meta parse-mode posix
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 3) has 'parse-mode' already exists at line 2"
+    );
+}
+
+#[test]
+fn option_miss_validate_fn() {
+    let script = r###"
+# @option --foo validate:`_fn`
+    "###;
+    fail!(script, &["prog"], "_fn(line 2) is missing");
+}
+
+#[test]
+fn conflict_option_alias() {
+    let script = r###"
+# @option --output
+# @flag --verbose --output
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@flag(line 3) has '--output' already exists at line 2"
+    );
+}
+
+#[test]
+fn args_separator_option_like() {
+    let script = r###"
+# @meta args-separator -x
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) args-separator '-x' looks like an option"
+    );
+}
+
+#[test]
+fn args_separator_empty() {
+    let script = r###"
+# @meta args-separator
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) args-separator requires a value"
+    );
+}
+
+#[test]
+fn choices_file_missing() {
+    let script = r###"
+# @option --country[@missing-countries.txt]
+    "###;
+    let args = vec!["prog".to_string()];
+    let err = argc::eval(script, &args, Some("tests/scripts/prog.sh"), None).unwrap_err();
+    assert_eq!(err.to_string(), "missing-countries.txt(line 2) is missing");
+    fail!(
+        script,
+        &["prog"],
+        "missing-countries.txt(line 2) is relative to the script, which has no path"
+    );
+}
+
+#[test]
+fn meta_config_unknown_option() {
+    let script = r###"
+# @meta config tests/scripts/defaults.toml
+# @option --port
+# @option --log-level
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) config 'tests/scripts/defaults.toml' has unknown option 'target'"
+    );
+}
+
+#[test]
+fn meta_config_missing() {
+    let script = r###"
+# @meta config missing.toml
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) config 'missing.toml' is missing"
+    );
+}
+
+#[test]
+fn glob_not_multiple() {
+    let script = r###"
+# @arg file <FILE> expand:glob
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 2) can't use expand:glob, it takes a single value"
+    );
+}
+
+#[test]
+fn glob_without_notation() {
+    let script = r###"
+# @arg files* expand:glob
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 2) can't use expand:glob without a value notation like <FILE>"
+    );
+}
+
+#[test]
+fn glob_on_option() {
+    let script = r###"
+# @option --file* <FILE> expand:glob
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 2) can't use expand:glob, only multiple @arg can"
+    );
+}
+
+#[test]
+fn reserved_var_name() {
+    let script = r###"
+# @option --_fn
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 2) has '_fn' that collides with the argc__fn variable"
+    );
+}
+
+#[test]
+fn var_prefix_invalid() {
+    let script = r###"
+# @meta var-prefix my-app
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) var-prefix 'my-app' is not a valid variable name"
+    );
+}
+
+#[test]
+fn duplicate_choice() {
+    let script = r###"
+# @option --color[=red|green|red] Pick a color
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 2) has duplicate choice 'red' at column 30"
+    );
+}
+
+#[test]
+fn duplicate_choice_quoted() {
+    let script = r###"
+# @arg mode*[~"fast"|slow|'fast']
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 2) has duplicate choice 'fast' at column 28"
+    );
+}
+
+#[test]
+fn empty_choice() {
+    let script = r###"
+# @flag --verbose
+# @option --level[debug||info]
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@option(line 3) has empty choice at column 25"
+    );
+}
+
+#[test]
+fn required_arg_after_optional() {
+    let script = r###"
+# @cmd
+# @arg src
+# @arg dest!
+copy() { :; }
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 4) is required but follows the optional @arg(line 3)"
+    );
+}
+
+#[test]
+fn arg_after_multiple() {
+    let script = r###"
+# @arg files+
+# @arg dest
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 3) is unexpected after the multiple @arg(line 2), only the last arg can take many values"
+    );
+    let script = r###"
+# @arg v1*
+# @arg v2*
+# @arg v3*
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 3) is unexpected after the multiple @arg(line 2), only the last arg can take many values"
+    );
+}
+
+#[test]
+fn trailing_arg_not_last() {
+    let script = r###"
+# @arg cmd~
+# @arg name
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@arg(line 3) is unexpected after the trailing arg 'cmd~'"
+    );
+}
+
+#[test]
+fn option_after_greedy_arg() {
+    let script = r###"
+# @arg rest~*
+# @flag --force
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@flag(line 3) is unexpected after the greedy arg 'rest~*'"
+    );
+}
+
+#[test]
+fn plus_flag_digit() {
+    let script = r###"
+# @flag +1 --one
+    "###;
+    fail!(script, &["prog"], "syntax error at line 2");
+}
+
+#[test]
+fn parse_mode_invalid() {
+    let script = r###"
+# @meta parse-mode strict
+    "###;
+    fail!(
+        script,
+        &["prog"],
+        "@meta(line 2) parse-mode must be 'gnu' or 'posix'"
+    );
+}
+
+#[test]
+fn reserved_names() {
+    for name in ["_args", "_fn", "_words", "_dashdash", "_unknown_args"] {
+        fail!(
+            &format!("# @option --{name}"),
+            &["prog"],
+            &format!("@option(line 1) has '{name}' that collides with the argc_{name} variable")
+        );
+        fail!(
+            &format!("# @flag --{name}"),
+            &["prog"],
+            &format!("@flag(line 1) has '{name}' that collides with the argc_{name} variable")
+        );
+        fail!(
+            &format!("# @arg {name}"),
+            &["prog"],
+            &format!("@arg(line 1) has '{name}' that collides with the argc_{name} variable")
+        );
+    }
+    for name in ["help", "version"] {
+        fail!(
+            &format!("# @option --{name}"),
+            &["prog"],
+            &format!("@option(line 1) has '{name}' that collides with the built-in --{name} flag, use @flag to override it")
+        );
+    }
+}
+
+#[test]
+fn meta_exit_code_invalid() {
+    fail!(
+        "# @meta exit-code unknown-option",
+        &["prog"],
+        "@meta(line 1) exit-code 'unknown-option' must be like 'class=code'"
+    );
+    fail!(
+        "# @meta exit-code unknown-opton=2",
+        &["prog"],
+        "@meta(line 1) exit-code has unknown error class 'unknown-opton', did you mean 'unknown-option'?"
+    );
+    fail!(
+        "# @meta exit-code missing-required=256",
+        &["prog"],
+        "@meta(line 1) exit-code 'missing-required=256' needs a code between 1 and 255"
+    );
+}
+
+#[test]
+fn default_out_of_range() {
+    fail!(
+        "# @option --retries=20[1..10]",
+        &["prog"],
+        "@option(line 1) has default '20' outside its range 1..10"
+    );
+    fail!(
+        "# @arg count=0[1..10]",
+        &["prog"],
+        "@arg(line 1) has default '0' outside its range 1..10"
+    );
+}
+
+#[test]
+fn var_name_conflict() {
+    fail!(
+        "# @option --foo.bar\n# @flag --foo-bar",
+        &["prog"],
+        "@flag(line 2) has 'foo-bar' that sets the argc_foo_bar variable like 'foo.bar' at line 1"
+    );
+    fail!(
+        "# @option --foo-bar\n# @arg foo_bar",
+        &["prog"],
+        "@arg(line 2) has 'foo_bar' that sets the argc_foo_bar variable like 'foo-bar' at line 1"
+    );
 }
@@ -0,0 +1,64 @@
+#[test]
+fn tags() {
+    let script = r###"#!/usr/bin/env bash
+#   @describe    A demo cli
+#   with more lines
+#     @ja デモ
+# @meta   exit-code   unknown-option=2
+##@meta no-auto-annotations
+
+set -e # @option --not-a-tag
+
+# @cmd   Build the project
+# @alias b ,   bd
+# @option  --target*[a|b]   Targets to build
+#   more about the target
+# @flag     -f    --force     Overwrite
+# @option   -o   --out    <FILE>
+# @arg    src!<PATH>   Source dir
+#@arg rest~
+build() {
+    # @flag --inside-fn
+    echo "build   $argc_target"
+}
+"###;
+    let output = argc::format_script(script).unwrap();
+    assert_eq!(argc::format_script(&output).unwrap(), output);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn line_endings() {
+    let script = "\u{feff}#  @describe   Demo\r\n# @flag  -v   Verbose\r\necho done  \r\n# @arg  a";
+    assert_eq!(
+        argc::format_script(script).unwrap(),
+        "\u{feff}# @describe Demo\r\n# @flag -v Verbose\r\necho done  \r\n# @arg a"
+    );
+}
+
+#[test]
+fn invalid() {
+    let err = argc::format_script("# @option -").unwrap_err();
+    assert_eq!(err.to_string(), "syntax error at line 1");
+}
+
+#[test]
+fn align() {
+    let script = r###"# @flag -v Verbose
+# @option --name <NAME>   Name to greet
+# @arg  target  The target
+# @option --no-describe
+
+# @flag --alone   Alone
+"###;
+    assert_eq!(
+        argc::format_script(script).unwrap(),
+        r###"# @flag -v              Verbose
+# @option --name <NAME> Name to greet
+# @arg target           The target
+# @option --no-describe
+
+# @flag --alone Alone
+"###
+    );
+}
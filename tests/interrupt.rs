@@ -1,9 +1,16 @@
-use assert_fs::{fixture::PathChild, TempDir};
+use assert_fs::{
+    fixture::{FileWriteStr, PathChild},
+    TempDir,
+};
 use rstest::rstest;
 
-use crate::fixtures::{get_path_env_var, tmpdir, Error};
+use crate::fixtures::{get_path_env_var, tmpdir, tmpdir_bare, Error};
 use assert_cmd::prelude::*;
-use std::{process::Command, thread, time::Duration};
+use std::{
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
 
 fn kill(process_id: u32) {
     unsafe {
@@ -32,3 +39,44 @@ fn interrupt(tmpdir: TempDir) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[rstest]
+fn watch(tmpdir_bare: TempDir) -> Result<(), Error> {
+    tmpdir_bare.child("Argcfile.sh").write_str(
+        r#"
+# @cmd
+greet() { echo "hello"; }
+
+eval "$(argc --argc-eval "$0" "$@")"
+"#,
+    )?;
+    tmpdir_bare
+        .child(".gitignore")
+        .write_str("src/ignored.txt\n")?;
+    tmpdir_bare.child("src/a.txt").write_str("")?;
+
+    let child = Command::cargo_bin("argc")?
+        .current_dir(tmpdir_bare.path())
+        .env("PATH", get_path_env_var())
+        .args(["--argc-watch", "src/*.txt", "greet"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("argc invocation failed");
+
+    thread::sleep(Duration::from_millis(1000));
+    tmpdir_bare.child("src/ignored.txt").write_str("x").unwrap();
+    tmpdir_bare.child("src/a.md").write_str("x").unwrap();
+    thread::sleep(Duration::from_millis(1000));
+    tmpdir_bare.child("src/a.txt").write_str("x").unwrap();
+    thread::sleep(Duration::from_millis(1000));
+
+    kill(child.id());
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("hello").count(), 2);
+
+    Ok(())
+}
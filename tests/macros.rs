@@ -153,7 +153,7 @@ macro_rules! snapshot_compgen_shells {
 #[macro_export]
 macro_rules! snapshot_export {
     ($source:expr) => {
-        let json = argc::export($source).unwrap();
+        let json = argc::export($source, None).unwrap();
         let output = serde_json::to_string_pretty(&json).unwrap();
         insta::assert_snapshot!(output);
     };
@@ -1,5 +1,3 @@
-use crate::*;
-
 #[test]
 fn with_main() {
     let script = r###"
@@ -58,3 +56,212 @@ cmd::foo() { :; }
 "###;
     snapshot!(script, &["prog", "cmd"]);
 }
+
+#[test]
+fn root_params_no_main() {
+    let script = r###"
+# @option --foo
+# @arg val*
+"###;
+    snapshot!(script, &["prog", "--foo", "x", "v1", "v2"]);
+}
+
+#[test]
+fn root_params_main() {
+    let script = r###"
+# @option --foo
+# @arg val*
+main() { :; }
+"###;
+    snapshot!(script, &["prog", "--foo", "x", "v1", "v2"]);
+}
+
+#[test]
+fn root_params_subcmd() {
+    let script = r###"
+# @flag --verbose
+# @cmd
+cmd() { :; }
+"###;
+    snapshot!(script, &["prog", "--verbose", "cmd"]);
+}
+
+#[test]
+fn forward_positionals() {
+    let script = r###"
+# @cmd
+# @arg src
+# @arg files*
+cmd() { :; }
+"###;
+    snapshot!(script, &["prog", "cmd", "a b", "c", "$d"]);
+}
+
+#[test]
+fn no_forward_positionals() {
+    let script = r###"
+# @meta no-forward-positionals
+# @cmd
+# @arg files*
+cmd() { :; }
+"###;
+    snapshot!(script, &["prog", "cmd", "a", "b"]);
+}
+
+#[test]
+fn no_forward_positionals_subcmd() {
+    let script = r###"
+# @cmd
+# @meta no-forward-positionals
+# @arg files*
+cmd() { :; }
+
+# @cmd
+# @arg files*
+cmd2() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "cmd", "a", "b"],
+            vec!["prog", "cmd2", "a", "b"]
+        ]
+    );
+}
+
+#[test]
+fn subcmd_main_args() {
+    let script = r###"
+# @flag --verbose
+# @cmd
+cmd() { :; }
+main() { :; }
+"###;
+    snapshot!(script, &["prog", "--verbose", "foo", "bar"]);
+}
+
+#[test]
+fn subcmd_no_main_args() {
+    let script = r###"
+# @cmd
+cmd() { :; }
+"###;
+    snapshot!(script, &["prog", "foo", "bar"]);
+}
+
+#[test]
+fn main_no_subcmd_args() {
+    let script = r###"
+main() { :; }
+"###;
+    snapshot!(script, &["prog", "foo", "bar"]);
+}
+
+#[test]
+fn nested_subcmd_main_args() {
+    let script = r###"
+# @cmd
+cmd() { :; }
+cmd::main() { :; }
+# @cmd
+cmd::foo() { :; }
+"###;
+    snapshot!(script, &["prog", "cmd", "bar"]);
+}
+
+#[test]
+fn main_cmd_help() {
+    let script = r###"
+# @cmd
+main() { :; }
+# @cmd
+foo() { :; }
+"###;
+    snapshot!(script, &["prog", "-h"]);
+}
+
+#[test]
+fn fallback_fn() {
+    let script = r###"
+# @flag --verbose
+# @cmd
+build() { :; }
+_argc_fallback() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--verbose", "frobnicate", "--force", "build"],
+            vec!["prog", "build"],
+            vec!["prog", "--help"],
+        ]
+    );
+}
+
+#[test]
+fn nested_fallback_fn() {
+    let script = r###"
+# @cmd
+cmd() { :; }
+# @cmd
+cmd::foo() { :; }
+cmd::_argc_fallback() { :; }
+"###;
+    snapshot!(script, &["prog", "cmd", "bar", "baz"]);
+}
+
+#[test]
+fn no_fallback_fn_did_you_mean() {
+    let script = r###"
+# @cmd
+build() { :; }
+# @cmd
+test() { :; }
+"###;
+    snapshot!(script, &["prog", "biuld"]);
+}
+
+#[test]
+fn did_you_mean_alias() {
+    let script = r###"
+# @cmd
+# @alias tst
+test() { :; }
+# @cmd
+build() { :; }
+"###;
+    snapshot!(script, &["prog", "tsx"]);
+}
+
+#[test]
+fn require_subcommand() {
+    let script = r###"
+# @meta require-subcommand
+# @describe A command group
+# @version 1.0.0
+# @cmd
+build() { :; }
+# @cmd
+test() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog"],
+            vec!["prog", "--help"],
+            vec!["prog", "--version"],
+            vec!["prog", "build"],
+        ]
+    );
+}
+
+#[test]
+fn require_subcommand_main() {
+    let script = r###"
+# @meta require-subcommand
+# @cmd
+build() { :; }
+main() { :; }
+"###;
+    snapshot!(script, &["prog"]);
+}
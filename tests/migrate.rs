@@ -0,0 +1,14 @@
+#[test]
+fn choices_default() {
+    let script = "# @describe Demo [=a|b]\r\n# @option -f --foo![=a|b]   Foo [=a]\r\n# @option --bar+[~=x|y]\r\n#   more about bar+[=x|y]\r\n# @arg baz![=1|2] <NUM>\r\n# @arg qux![a|b]\r\n";
+    assert_eq!(
+        argc::migrate_script(script).unwrap(),
+        "# @describe Demo [=a|b]\r\n# @option -f --foo[=a|b]   Foo [=a]\r\n# @option --bar*[~=x|y]\r\n#   more about bar+[=x|y]\r\n# @arg baz[=1|2] <NUM>\r\n# @arg qux![a|b]\r\n"
+    );
+}
+
+#[test]
+fn unchanged() {
+    let script = "\u{feff}#!/usr/bin/env bash\n# @option --foo[=a|b]\n  # @option --bar![=a|b]\necho '--foo![=a|b]'";
+    assert_eq!(argc::migrate_script(script).unwrap(), script);
+}
@@ -7,3 +7,29 @@ fn escape() {
         &["prog", "cmda", "$foo", "`pwd`", "$(pwd)", "'", "\\1", "", "\n", "世界", " "]
     );
 }
+
+#[test]
+fn eval_outcome_help() {
+    let args: Vec<String> = ["prog", "cmda", "--help"]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    let values = argc::eval(SCRIPT_ARGS, &args, None, Some(80)).unwrap();
+    let outcome = argc::eval_outcome(SCRIPT_ARGS, &args, None, Some(80)).unwrap();
+    let help = values.into_iter().find_map(|v| match v {
+        argc::ArgcValue::Error((message, 0)) => Some(message),
+        _ => None,
+    });
+    assert_eq!(outcome, argc::EvalOutcome::HelpRequested(help.unwrap()));
+}
+
+#[test]
+fn eval_outcome_values() {
+    let args: Vec<String> = ["prog", "cmdb", "v1"]
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    let values = argc::eval(SCRIPT_ARGS, &args, None, None).unwrap();
+    let outcome = argc::eval_outcome(SCRIPT_ARGS, &args, None, None).unwrap();
+    assert_eq!(outcome, argc::EvalOutcome::Values(values));
+}
@@ -0,0 +1,26 @@
+use clap::{Arg, ArgAction, Command};
+
+pub fn command() -> Command {
+    cmd_clap_command()
+}
+
+fn cmd_clap_command() -> Command {
+    Command::new("clap_command")
+        .about("Copy files")
+        .arg(Arg::new("force").short('f').long("force").help("Overwrite existing files").action(ArgAction::SetTrue))
+        .subcommand(cmd_clap_command_copy())
+        .subcommand(cmd_clap_command_run())
+}
+
+fn cmd_clap_command_copy() -> Command {
+    Command::new("copy")
+        .about("Copy files into a dir")
+        .allow_missing_positional(true)
+        .arg(Arg::new("src").help("Source files").value_name("SRC").num_args(1..).action(ArgAction::Append))
+        .arg(Arg::new("dest").help("Destination dir").value_name("DEST").required(true))
+}
+
+fn cmd_clap_command_run() -> Command {
+    Command::new("run")
+        .arg(Arg::new("cmd").value_name("CMD").num_args(1..).trailing_var_arg(true).allow_hyphen_values(true))
+}
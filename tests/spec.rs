@@ -53,6 +53,36 @@ fn option_shorts() {
     );
 }
 
+#[test]
+fn option_shorts_inline_value() {
+    let script = r###"
+# @flag -a
+# @flag -b
+# @option -o
+# @option -n <N:int>
+# @arg rest*
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-abo", "val"],
+            vec!["prog", "-abovalue"],
+            vec!["prog", "-ofoo"],
+            vec!["prog", "-oab"],
+            vec!["prog", "-aob", "x"],
+            vec!["prog", "-an3", "-o", "x"],
+            vec!["prog", "-ano", "x"],
+            vec!["prog", "-abo"],
+            vec!["prog", "-ao", "-b"],
+            vec!["prog", "-axo", "v"],
+            vec!["prog", "-ao=x"],
+            vec!["prog", "-abo="],
+            vec!["prog", "-an=3"],
+            vec!["prog", "-oa=b"],
+        ]
+    );
+}
+
 #[test]
 fn arg_eval() {
     snapshot_multi!(
@@ -185,3 +215,1118 @@ fn option_to_variable() {
         ]
     );
 }
+
+#[test]
+fn option_env() {
+    let script = r###"
+# @option --token! env:ARGC_TEST_ENV_TOKEN env:only
+# @flag   --debug env:ARGC_TEST_ENV_DEBUG env:only
+# @option --port=8080 env:ARGC_TEST_ENV_PORT
+"###;
+    std::env::set_var("ARGC_TEST_ENV_TOKEN", "secret");
+    std::env::set_var("ARGC_TEST_ENV_DEBUG", "1");
+    std::env::set_var("ARGC_TEST_ENV_PORT", "3000");
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog"],
+            vec!["prog", "--port", "80"],
+            vec!["prog", "--token", "abc"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn option_env_only_missing() {
+    let script = r###"
+# @option --token! env:ARGC_TEST_ENV_MISSING env:only
+"###;
+    snapshot!(script, &["prog"]);
+}
+
+#[test]
+fn completion_alias_help() {
+    let script = r###"
+# @cmd Build the project
+# @alias b
+# @completion-alias bd
+build() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "-h"], vec!["prog", "bd"]]);
+}
+
+#[test]
+fn stdin_dash_value() {
+    let script = r###"
+# @cmd
+# @option --output
+# @arg file*
+cat() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "cat", "-"],
+            vec!["prog", "cat", "-", "other.txt"],
+            vec!["prog", "cat", "--output", "-", "other.txt"],
+        ]
+    );
+}
+
+#[test]
+fn option_aliases() {
+    let script = r###"
+# @option -o --output --out <FILE> Output file
+# @flag --quiet --silent
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-h"],
+            vec!["prog", "--out", "a.txt", "--silent"],
+            vec!["prog", "--output=b.txt"],
+        ]
+    );
+}
+
+#[test]
+fn choices_ignore_case() {
+    let script = r###"
+# @option --level[~debug|info|warn|error]
+# @arg mode[~=Fast|Slow]
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--level", "INFO", "fast"],
+            vec!["prog", "--level", "Verbose"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn choices_any() {
+    let script = r###"
+# @option --format[json|yaml|*]
+# @option --mark["*"|x]
+# @arg mode[=fast|slow|*]
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--format", "toml", "medium"],
+            vec!["prog", "--format", "json", "--mark", "*"],
+            vec!["prog", "--mark", "y"],
+        ]
+    );
+}
+
+#[test]
+fn choices_default_modifier() {
+    let script = r###"
+# @option --format![=json|yaml]
+# @arg mode+[~=fast|slow]
+"###;
+    snapshot_multi!(script, vec![vec!["prog"], vec!["prog", "--format", "toml"]]);
+    assert_eq!(
+        argc::check(script).unwrap(),
+        [
+            "@option(line 2) has a choice default, so `!` is ignored and the value stays optional",
+            "@arg(line 3) has a choice default, so `+` acts as `*` and the value stays optional",
+        ]
+    );
+    assert!(argc::check("# @option --format[=json|yaml]")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn plus_flags() {
+    let script = r###"
+# @flag +x -x --xtrace Print commands
+# @flag --color
+# @arg nums*
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "+x", "+5", "a"],
+            vec!["prog", "--", "+x"],
+            vec!["prog", "+color"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn args_separator() {
+    let script = r###"
+# @meta args-separator ++
+# @cmd
+# @flag --verbose
+# @arg args*
+run() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "run", "--verbose", "++", "--verbose", "-x"],
+            vec!["prog", "run", "--", "--verbose"],
+        ]
+    );
+}
+
+#[test]
+fn choices_file() {
+    let script = r###"
+# @option --country[@countries.txt]
+# @arg region*[@countries.txt]
+"###;
+    let path = locate_script("choices.sh");
+    snapshot!(script, &["prog", "-h"], Some(path.as_str()), None);
+}
+
+#[test]
+fn meta_config() {
+    let script = r###"
+# @meta config defaults.toml
+# @option --port
+# @option --log-level[=warn|error]
+# @arg target
+"###;
+    let path = locate_script("config.sh");
+    snapshot!(script, &["prog", "-h"], Some(path.as_str()), None);
+}
+
+#[test]
+fn meta_config_json() {
+    let script = r###"
+# @meta config defaults.json
+# @option --port=8000
+# @arg target
+"###;
+    let path = locate_script("config.sh");
+    snapshot!(script, &["prog"], Some(path.as_str()), None);
+}
+
+#[test]
+fn meta_preserve_order() {
+    let script = r###"
+# @meta preserve-order
+# @flag --verbose Print more
+
+# @cmd Copy a file
+# @option --input Where to read from
+# @arg source The source file
+# @flag --force Overwrite the target
+# @group Network
+# @option --proxy Proxy url
+# @arg target The target file
+# @option --mode[copy|move] How to transfer
+copy() { :; }
+
+# @cmd
+# @arg name
+list() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        [
+            vec!["prog", "-h"],
+            vec!["prog", "copy", "-h"],
+            vec!["prog", "list", "-h"]
+        ]
+    );
+}
+
+#[test]
+fn meta_exit_code() {
+    let script = r###"
+# @meta exit-code unknown-option=2 missing-required=3 invalid-value=4
+# @option --mode[fast|slow]
+# @cmd
+# @arg target!
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        [
+            vec!["prog", "extra"],
+            vec!["prog", "build", "x", "--foo"],
+            vec!["prog", "build", "x", "y"],
+            vec!["prog", "build"],
+            vec!["prog", "--mode", "quick", "build", "x"],
+        ]
+    );
+}
+
+#[test]
+fn choices_file_invalid() {
+    let script = r###"
+# @option --country[@countries.txt]
+"###;
+    let path = locate_script("choices.sh");
+    snapshot!(
+        script,
+        &["prog", "--country", "de"],
+        Some(path.as_str()),
+        None
+    );
+}
+
+#[test]
+fn option_dotted_name() {
+    let script = r###"
+# @flag --http1.1
+# @option --tls1.2[a|b]
+# @option --proto.v=x
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--http1.1", "--tls1.2", "b"],
+            vec!["prog", "--tls1.2=a", "--proto.v=y"],
+        ]
+    );
+}
+
+#[test]
+fn option_range() {
+    let script = r###"
+# @option --retries=3[1..10]
+# @option --offset[-5..5]
+# @arg level*[0..3]
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--retries", "10", "--offset=-5", "0", "3"],
+            vec!["prog"],
+            vec!["prog", "--retries", "20"],
+            vec!["prog", "--retries", "abc"],
+            vec!["prog", "1", "4"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn version_from_var() {
+    let script = r###"
+# @describe Demo
+# @version ${VERSION:-0.1.0}
+# @cmd
+# @version ${BUILD_VERSION}
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--version"],
+            vec!["prog", "-h"],
+            vec!["prog", "build", "--version"],
+        ]
+    );
+}
+
+#[test]
+fn unique_values() {
+    let script = r###"
+# @option --tag*^[~a|b|c]
+# @option --label*^^
+# @arg files*^
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "x", "y", "x", "--tag", "b", "--tag", "A", "--tag", "a"],
+            vec!["prog", "--label", "foo", "--label", "bar", "--label", "foo"],
+        ]
+    );
+}
+
+#[test]
+fn trailing_args() {
+    let script = r###"
+# @cmd
+# @flag --dry-run
+# @arg name! Container name
+# @arg cmd~ Command to run
+run() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "run", "box", "ls", "-la", "--color", "--", "x"],
+            vec!["prog", "run", "--dry-run", "box", "sh", "-c", "--help"],
+            vec!["prog", "run", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn greedy_args() {
+    let script = r###"
+# @cmd
+# @flag --dry-run
+# @arg name! Container name
+# @arg rest~* Args to pass through
+exec() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "exec", "box", "--dry-run", "-la", "--", "x"],
+            vec!["prog", "exec", "--dry-run", "box", "-h", "--version"],
+            vec!["prog", "exec", "box"],
+        ]
+    );
+}
+
+#[test]
+fn parse_mode_posix() {
+    let script = r###"
+# @meta parse-mode posix
+# @flag -v --verbose
+# @arg args*
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-v", "a", "-v", "b"],
+            vec!["prog", "a", "--", "-v"],
+            vec!["prog", "--", "-v", "a"],
+        ]
+    );
+}
+
+#[test]
+fn parse_mode_gnu() {
+    let script = r###"
+# @meta parse-mode gnu
+# @flag -v --verbose
+# @arg args*
+"###;
+    snapshot_multi!(
+        script,
+        vec![vec!["prog", "a", "-v", "b"], vec!["prog", "a", "--", "-v"],]
+    );
+}
+
+#[test]
+fn parse_mode_posix_subcmd() {
+    let script = r###"
+# @meta parse-mode posix
+# @flag -v --verbose
+
+# @cmd
+# @flag -f --force
+# @arg args*
+rm() { :; }
+
+# @cmd
+# @meta parse-mode gnu
+# @flag -f --force
+# @arg args*
+cp() { :; }
+
+# @cmd
+# @arg cmd~
+exec() { :; }
+
+# @cmd
+# @arg args*
+echo() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-v", "rm", "-f", "a", "-f"],
+            vec!["prog", "cp", "a", "-f"],
+            vec!["prog", "exec", "ls", "-la"],
+            vec!["prog", "echo", "a", "--help"],
+        ]
+    );
+}
+
+#[test]
+fn value_types() {
+    let script = r###"
+# @option --count <N:int>
+# @option --ratio <R:float>
+# @option --size* <W:int> <H:int>
+# @arg enabled <ENABLED:bool>
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "true", "--count", "-3", "--ratio", "0.5", "--size", "2", "4"],
+            vec!["prog", "--count", "3.5"],
+            vec!["prog", "--ratio", "abc"],
+            vec!["prog", "--size", "2", "x"],
+            vec!["prog", "yes"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn option_map() {
+    let script = r###"
+# @option -D*[map] <KEY=VALUE>  Define a macro
+# @option -e --env+[map]
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-D", "A=1", "-D", "B", "-e", "x=1"],
+            vec![
+                "prog",
+                "-D",
+                "A=1",
+                "-D",
+                "B=2",
+                "-D",
+                "A=",
+                "-e",
+                "msg=a b=c"
+            ],
+            vec!["prog", "-e", "=1"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn option_groups() {
+    let script = r###"
+# @flag --verbose Print more
+# @group Output options
+# @option -o --output <FILE> Output file
+# @flag --color Colorize output
+# @group Network options
+# @option --proxy <URL> Proxy server
+# @group
+# @flag --dry-run
+# @group Output options
+# @option --format[json|text]
+
+# @cmd
+# @flag --force
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![vec!["prog", "-h"], vec!["prog", "build", "-h"]]
+    );
+}
+
+#[test]
+fn help_annotations() {
+    let script = r###"
+# @option --port=8080 env:PORT Listen port
+# @option --host=localhost Bind address [default: all interfaces]
+# @option --tz[UTC|CET|EET|MSK|IST|CST|JST|AEST|NZST|HST|AKST|PST] Time zone
+# @option --name! Service name
+
+# @cmd
+# @arg mode[=dev|prod]
+run() { :; }
+
+# @cmd
+# @meta no-auto-annotations
+# @option --port=8080 env:PORT Listen port
+# @arg target!
+deploy() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-h"],
+            vec!["prog", "run", "-h"],
+            vec!["prog", "deploy", "-h"]
+        ]
+    );
+}
+
+#[test]
+fn command_groups() {
+    let script = r###"
+# @flag --verbose
+
+# @cmd Show help for tasks
+tasks() { :; }
+
+# @group CI tasks
+# @cmd Run the linters
+lint() { :; }
+
+# @cmd Run the tests
+# @group Filter options
+# @option --filter <PATTERN>
+test() { :; }
+
+# @group Local dev
+# @cmd Start the dev server
+serve() { :; }
+
+# @group CI tasks
+# @cmd Build release artifacts
+# @alias b
+build() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "-h"], vec!["prog", "test", "-h"]]);
+}
+
+#[test]
+fn option_optional_value() {
+    let script = r###"
+# @option --color?[always|never|auto] <WHEN> Colorize output
+# @option --level?=3
+# @option -j?
+# @arg files*
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "a"],
+            vec!["prog", "--color", "a", "--level", "-j"],
+            vec!["prog", "--color=never", "--level=5", "-j=2", "a"],
+            vec!["prog", "--color=sometimes"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn describe_markdown() {
+    let script = r###"
+# @describe Run **tasks** of the `project`
+#
+# * build the app
+# * run the `tests`
+# @option --log_file <PATH> Write logs to `PATH`, *not* stdout
+
+# @cmd Bump the __version__
+bump() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "-h"]]);
+}
+
+#[test]
+fn meta_doc_marker() {
+    let script = r###"
+# @meta doc-marker ##
+# @describe Deploy the app
+## to the cluster
+# TODO: drop the legacy flags
+# @flag --force Skip checks
+# internal: only used by CI
+## Use with care
+##
+## Checks can't be undone
+# @option --target Where to deploy
+## @ja デプロイ先
+## クラスタ名
+# note for developers
+
+# @cmd Roll back
+# see runbook
+rollback() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![vec!["prog", "-h"], vec!["prog", "rollback", "-h"]]
+    );
+}
+
+#[test]
+fn arg_glob() {
+    let script = r###"
+# @arg files* <FILE> expand:glob
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec![
+                "prog",
+                "tests/scripts/*.sh",
+                "tests/scripts/*.none",
+                "a.txt"
+            ],
+            vec!["prog", "--", "tests/scripts/countries.tx?"],
+        ]
+    );
+}
+
+#[test]
+fn help_json() {
+    let script = r###"
+# @flag --verbose
+# @cmd Build things
+# @arg target
+build() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "build", "--help", "json"]]);
+}
+
+#[test]
+fn var_prefix() {
+    let script = r###"
+# @meta var-prefix myapp_
+# @option --port=8080
+# @option -D*[map]
+# @cmd
+# @arg files*
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![vec!["prog", "-D", "a=1", "--port", "9", "build", "x", "y"]]
+    );
+}
+
+#[test]
+fn meta_shell_no_arrays() {
+    let script = r###"
+# @meta shell dash
+# @option --tag* Tags
+# @option -D*[map] <KEY=VALUE>
+# @arg files*
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--tag", "a", "--tag", "b c", "-D", "k=v", "--", "x", "y z"],
+            vec!["prog"],
+        ]
+    );
+}
+
+#[test]
+fn builtin_overrides() {
+    let script = r###"
+# @flag -h --help Show the manual
+# @flag -V --verbose Print more
+# @option --fn Function to run
+# @option --args Raw args
+main() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--help"],
+            vec!["prog", "-h"],
+            vec!["prog", "-V", "--fn", "x", "--args", "y"],
+            vec!["prog", "--version"]
+        ]
+    );
+}
+
+#[test]
+fn option_symbol_short() {
+    let script = r###"
+# @option -0 --level <N> Compression level
+# @option -# --hash[md5|sha1] Hash
+# @option -: --sep=, Separator
+# @flag -1 --once
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-h"],
+            vec!["prog", "-0", "9", "-#", "sha1", "-:", ";", "-1"],
+            vec!["prog", "-10", "3"],
+            vec!["prog", "-#", "crc"]
+        ]
+    );
+}
+
+#[test]
+fn unicode_values() {
+    let script = r###"
+# @describe 一个演示 🚀
+# @option --mark[✅|❌] 状态
+# @option -c --city[=東京|大阪] 都市の名前
+# @option --accent=café Combining accent
+# @option --label <名前> ラベル
+# @flag -v --verbose Print more
+# @arg name*[café|日本語] 名前
+"###;
+    snapshot_multi!(
+        script,
+        [
+            vec!["prog", "-h"],
+            vec!["prog", "--mark", "✅", "café", "日本語"],
+            vec!["prog", "--label", "東 京's"],
+            vec!["prog", "--mark", "⭕"],
+            vec!["prog", "--city", "名古屋"],
+        ]
+    );
+}
+
+#[test]
+fn alias_scoped_to_siblings() {
+    let script = r###"
+# @cmd
+# @alias b
+build() { :; }
+
+# @cmd
+deploy() { :; }
+
+# @cmd
+# @alias b
+deploy::bundle() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "b"], vec!["prog", "deploy", "b"]]);
+}
+
+#[test]
+fn option_default_file() {
+    let script = r###"
+# @option --token=@tests/scripts/token.txt
+# @option --missing=@tests/scripts/missing.txt
+# @option --need!=@tests/scripts/token.txt
+# @option --need-missing!=@tests/scripts/missing.txt
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--need-missing", "v"],
+            vec!["prog", "--token", "abc", "--need-missing", "v"],
+            vec!["prog"],
+            vec!["prog", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn meta_usage() {
+    let script = r###"
+# @cmd Deploy a target
+# @meta usage prog deploy [OPTIONS] <TARGET> [-- <REMOTE ARGS>...]
+# @option --env
+# @arg args~
+deploy() { :; }
+
+# @cmd
+# @arg target!
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "deploy", "-h"],
+            vec!["prog", "deploy", "--env"],
+            vec!["prog", "build", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn meta_pass_unknown() {
+    let script = r###"
+# @meta pass-unknown
+# @flag -v --verbose
+
+# @cmd
+# @option -o --output
+# @arg args*
+run() { :; }
+
+# @cmd
+# @flag --dry
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec![
+                "prog",
+                "-v",
+                "run",
+                "--color=always",
+                "-x",
+                "--output",
+                "out",
+                "a"
+            ],
+            vec!["prog", "run", "--output=out", "--jobs", "4", "-vz"],
+            vec![
+                "prog",
+                "--quiet",
+                "build",
+                "--dry",
+                "--release",
+                "--target=wasm"
+            ],
+        ]
+    );
+}
+
+#[test]
+fn meta_strict_shell() {
+    let script = r###"
+# @meta strict-shell
+# @flag --force
+# @option --env
+# @option --tag*
+# @option --label*[map]
+
+# @cmd
+# @arg target
+# @arg files*
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "build"],
+            vec!["prog", "--env", "prod", "build", "web"],
+        ]
+    );
+}
+
+#[test]
+fn name_tag() {
+    let script = r###"
+# @name mycli
+# @version 1.0.0
+
+# @cmd
+# @arg target!
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog.sh", "-h"],
+            vec!["prog.sh", "-V"],
+            vec!["prog.sh", "build"],
+            vec!["prog.sh", "build", "-h"],
+        ]
+    );
+}
+
+#[test]
+fn arg_multiple_then_required() {
+    let script = r###"
+# @cmd
+# @arg source*
+# @arg dest!
+cp() { :; }
+
+# @cmd
+# @arg source+
+# @arg dest!
+mv() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "cp"],
+            vec!["prog", "cp", "a"],
+            vec!["prog", "cp", "a", "b"],
+            vec!["prog", "cp", "a", "b", "c", "d"],
+            vec!["prog", "mv", "a"],
+            vec!["prog", "mv", "a", "b", "c"],
+        ]
+    );
+}
+
+#[test]
+fn output_tag() {
+    let script = r###"
+# @describe A demo cli
+# @output Nothing, run a subcommand
+
+# @cmd List the releases
+# @output One JSON object per line
+# @output Nothing when no release matches
+# @flag --all
+releases() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![vec!["prog", "-h"], vec!["prog", "releases", "-h"]]
+    );
+}
+
+#[test]
+fn flag_option_same_name_in_subcmd() {
+    let script = r###"
+# @option --profile
+
+# @cmd
+# @option --profile
+deploy() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--profile", "z", "deploy"],
+            vec!["prog", "deploy", "--profile", "y"],
+            vec!["prog", "deploy"],
+        ]
+    );
+}
+
+#[test]
+fn flag_option_global() {
+    let script = r###"
+# @flag -v --verbose scope:global  Print more
+# @option --profile[dev|prod] scope:global  Build profile
+# @option --local  Not inherited
+
+# @cmd
+# @option --profile  Any profile
+build() { :; }
+
+# @cmd
+# @flag -q --quiet
+test() { :; }
+
+# @cmd
+test::unit() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "test", "unit", "--help"],
+            vec!["prog", "test", "--profile", "prod", "-vq", "unit"],
+            vec!["prog", "test", "unit", "--profile=bad"],
+            vec!["prog", "test", "unit", "--local", "x"],
+            vec!["prog", "build", "--profile", "x", "-v"],
+            vec!["prog", "build", "--help"],
+        ]
+    );
+}
+
+#[test]
+fn describe_cmd_empty() {
+    let script = r###"
+# @describe
+# @flag --foo
+
+# @cmd
+# @arg target
+build() { :; }
+
+# @cmd Run the tests
+test() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-h"],
+            vec!["prog", "build", "-h"],
+            vec!["prog", "build", "x"],
+        ]
+    );
+}
+
+#[test]
+fn choices_with_colon() {
+    let script = r###"
+# @option --addr[localhost:8080|0.0.0.0:80]
+# @arg time[12:00|13:00]
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--addr", "localhost:8080", "12:00"],
+            vec!["prog", "--addr", "localhost"],
+            vec!["prog", "12"],
+        ]
+    );
+}
+
+#[test]
+fn cmd_fn_name_chars() {
+    let script = r###"
+# @cmd
+foo@bar() { :; }
+
+# @cmd
+foo.bar() { :; }
+
+# @cmd
+foo:bar() { :; }
+
+# @cmd
+foo:bar::baz() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "-h"],
+            vec!["prog", "foo@bar"],
+            vec!["prog", "foo.bar"],
+            vec!["prog", "foo:bar"],
+            vec!["prog", "foo:bar", "baz"],
+        ]
+    );
+}
+
+#[test]
+fn cmd_after_eval() {
+    let script = r###"
+# @cmd
+build() { :; }
+
+eval "$(argc --argc-eval "$0" "$@")"
+
+# @cmd
+test() { :; }
+"###;
+    assert_eq!(
+        argc::check(script).unwrap(),
+        ["@cmd(line 7) comes after the eval at line 5, so its function is not defined yet when argc calls it"]
+    );
+    assert!(
+        argc::check("# @cmd\nbuild() { :; }\neval \"$(argc --argc-eval \"$0\" \"$@\")\"")
+            .unwrap()
+            .is_empty()
+    );
+    let script = r###"
+eval "$(argc --argc-eval "$0" "$@")"
+main() { :; }
+eval "$(argc --argc-eval "$0" "$@")"
+"###;
+    assert_eq!(
+        argc::check(script).unwrap(),
+        [
+            "main(line 3) comes after the eval at line 2, so it is not defined yet when argc calls it",
+            "eval(line 4) parses the args again after the eval at line 2",
+        ]
+    );
+}
+
+#[test]
+fn flag_option_terminal() {
+    let script = r###"
+# @flag --list-plugins terminal:`_list_plugins`  List plugins
+# @option --dump-config[json|toml] terminal:true  Print the config
+# @option --width terminal output width
+# @option --target! <DIR>
+
+# @cmd
+# @arg name!
+build() { :; }
+
+_list_plugins() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "--list-plugins", "extra"],
+            vec!["prog", "--dump-config", "toml"],
+            vec!["prog", "--list-plugins", "--help"],
+            vec!["prog", "build"],
+            vec!["prog", "--width", "80"],
+        ]
+    );
+}
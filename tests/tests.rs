@@ -1,3 +1,5 @@
+#![allow(clippy::useless_vec)]
+
 mod fixtures;
 
 pub const SCRIPT_OPTIONS: &str = include_str!("scripts/options.sh");
@@ -11,9 +13,12 @@ mod argcfile;
 mod cli;
 mod compgen;
 mod create;
+mod diff;
 mod export;
 mod fail;
+mod format;
 mod main_fn;
+mod migrate;
 mod misc;
 mod param_fn;
 mod spec;
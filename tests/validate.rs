@@ -212,3 +212,75 @@ _choice_fn() {
 "###;
     snapshot_multi!(script, vec![vec!["prog", "cmd", "a\\b", "a\\b"],]);
 }
+
+#[test]
+fn validate_fn() {
+    let script = r###"
+# @option --email* validate:`_check_email`
+# @arg files* validate:`_check_file`
+_check_email() { :; }
+_check_file() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog"],
+            vec!["prog", "--email", "a@b.c", "--email", "x y"],
+            vec!["prog", "f1", "f2", "--email", "a@b.c"],
+        ]
+    );
+}
+
+#[test]
+fn validate_fn_non_cli() {
+    let script = r###"
+# @option --mail env:ARGC_TEST_VALIDATE_MAIL validate:`_check_mail`
+# @option --level=info validate:`_check_level`
+# @option --host=`_default_host` validate:`_check_host`
+# @arg target=`_default_target` validate:`_check_target`
+_check_mail() { :; }
+_check_level() { :; }
+_check_host() { :; }
+_check_target() { :; }
+_default_host() { :; }
+_default_target() { :; }
+"###;
+    std::env::set_var("ARGC_TEST_VALIDATE_MAIL", "nope");
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog"],
+            vec!["prog", "--mail", "a@b.c", "--level", "debug", "--host", "h", "t"],
+        ]
+    );
+}
+
+#[test]
+fn usage_on_error() {
+    let script = r###"
+# @option --level[debug|info]
+# @cmd
+# @option --target! Build target
+build() { :; }
+"###;
+    snapshot_multi!(
+        script,
+        vec![
+            vec!["prog", "build", "--tgt", "x"],
+            vec!["prog", "build"],
+            vec!["prog", "--level", "warn", "build", "--target", "x"],
+        ]
+    );
+}
+
+#[test]
+fn full_help_on_error() {
+    let script = r###"
+# @meta full-help-on-error
+# @option --level[debug|info]
+# @cmd
+# @option --target! Build target
+build() { :; }
+"###;
+    snapshot_multi!(script, vec![vec!["prog", "build"]]);
+}